@@ -0,0 +1,324 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+use crate::sessions;
+
+/// Turns remembered per thread; older ones are only available from the rollout.
+const MAX_TURNS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TurnState {
+    InProgress,
+    Completed,
+    Interrupted,
+    Failed,
+}
+
+impl TurnState {
+    fn from_status(status: Option<&str>) -> Self {
+        match status {
+            Some("inProgress") => TurnState::InProgress,
+            Some("interrupted") => TurnState::Interrupted,
+            Some("failed") => TurnState::Failed,
+            _ => TurnState::Completed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnSummary {
+    pub turn_id: String,
+    pub state: TurnState,
+    /// Last agent message of the turn, normally its final answer.
+    pub agent_message: Option<String>,
+    pub error: Option<String>,
+    pub changed_files: Vec<String>,
+    pub commands: Vec<String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadSnapshot {
+    pub thread_id: String,
+    pub running: bool,
+    pub turns: Vec<TurnSummary>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+struct ThreadActivity {
+    turns: VecDeque<TurnSummary>,
+    updated_at: DateTime<Utc>,
+    /// Loaded in the running app-server; cleared when the app-server stops.
+    loaded: bool,
+}
+
+impl ThreadActivity {
+    fn new() -> Self {
+        Self {
+            turns: VecDeque::new(),
+            updated_at: Utc::now(),
+            loaded: true,
+        }
+    }
+
+    fn turn_mut(&mut self, turn_id: Option<&str>) -> Option<&mut TurnSummary> {
+        match turn_id {
+            Some(turn_id) => self.turns.iter_mut().rfind(|turn| turn.turn_id == turn_id),
+            None => self.turns.back_mut(),
+        }
+    }
+}
+
+/// Live state of the threads seen in this session, built from app-server notifications so
+/// that callers can ask "is this thread busy and what did its last turn do" without
+/// replaying the rollout.
+#[derive(Debug)]
+pub struct ActivityTracker {
+    threads: HashMap<String, ThreadActivity>,
+    changes: watch::Sender<u64>,
+}
+
+impl Default for ActivityTracker {
+    fn default() -> Self {
+        Self {
+            threads: HashMap::new(),
+            changes: watch::channel(0).0,
+        }
+    }
+}
+
+impl ActivityTracker {
+    /// Notifies the receiver after every recorded change.
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    /// Marks a thread as loaded in the app-server, before it has any turn.
+    pub fn track(&mut self, thread_id: &str) {
+        self.threads
+            .entry(thread_id.to_string())
+            .or_insert_with(ThreadActivity::new)
+            .loaded = true;
+    }
+
+    pub fn is_tracked(&self, thread_id: &str) -> bool {
+        self.threads.get(thread_id).is_some_and(|thread| thread.loaded)
+    }
+
+    /// `(thread_id, turn_id)` of every turn still in progress.
+    pub fn running_turns(&self) -> Vec<(String, String)> {
+        self.threads
+            .iter()
+            .filter_map(|(thread_id, thread)| {
+                let turn = thread.turns.back()?;
+                (turn.state == TurnState::InProgress)
+                    .then(|| (thread_id.clone(), turn.turn_id.clone()))
+            })
+            .collect()
+    }
+
+    /// Records that the app-server stopped: turns in progress end as interrupted, and every
+    /// thread has to be resumed before it takes new turns.
+    pub fn server_stopped(&mut self) {
+        for thread in self.threads.values_mut() {
+            thread.loaded = false;
+            for turn in thread
+                .turns
+                .iter_mut()
+                .filter(|turn| turn.state == TurnState::InProgress)
+            {
+                turn.state = TurnState::Interrupted;
+                turn.completed_at = Some(Utc::now());
+            }
+        }
+        self.changes.send_modify(|version| *version += 1);
+    }
+
+    pub fn record_notification(&mut self, notification: &Value) {
+        let Some(method) = notification.get("method").and_then(Value::as_str) else {
+            return;
+        };
+        let Some(params) = notification.get("params") else {
+            return;
+        };
+        let thread_id = params
+            .get("threadId")
+            .or_else(|| params.pointer("/thread/id"))
+            .and_then(Value::as_str);
+        let Some(thread_id) = thread_id else {
+            return;
+        };
+        let turn_id = params
+            .get("turnId")
+            .or_else(|| params.pointer("/turn/id"))
+            .and_then(Value::as_str);
+
+        let thread = self
+            .threads
+            .entry(thread_id.to_string())
+            .or_insert_with(ThreadActivity::new);
+        thread.loaded = true;
+        match method {
+            "turn/started" => {
+                let Some(turn_id) = turn_id else {
+                    return;
+                };
+                thread.turns.push_back(TurnSummary {
+                    turn_id: turn_id.to_string(),
+                    state: TurnState::InProgress,
+                    agent_message: None,
+                    error: None,
+                    changed_files: Vec::new(),
+                    commands: Vec::new(),
+                    started_at: Utc::now(),
+                    completed_at: None,
+                });
+                while thread.turns.len() > MAX_TURNS {
+                    thread.turns.pop_front();
+                }
+            }
+            "turn/completed" => {
+                let Some(turn) = thread.turn_mut(turn_id) else {
+                    return;
+                };
+                turn.state =
+                    TurnState::from_status(params.pointer("/turn/status").and_then(Value::as_str));
+                if let Some(message) = params
+                    .pointer("/turn/error/message")
+                    .and_then(Value::as_str)
+                {
+                    turn.error = Some(message.to_string());
+                }
+                turn.completed_at = Some(Utc::now());
+            }
+            "item/completed" => {
+                let Some(turn) = thread.turn_mut(turn_id) else {
+                    return;
+                };
+                let Some(item) = params.get("item") else {
+                    return;
+                };
+                match item.get("type").and_then(Value::as_str) {
+                    Some("agentMessage") => {
+                        if let Some(text) = item.get("text").and_then(Value::as_str) {
+                            turn.agent_message = Some(text.to_string());
+                        }
+                    }
+                    Some("fileChange") => {
+                        let paths = item
+                            .get("changes")
+                            .and_then(Value::as_array)
+                            .into_iter()
+                            .flatten()
+                            .filter_map(|change| change.get("path").and_then(Value::as_str));
+                        for path in paths {
+                            if !turn.changed_files.iter().any(|known| known == path) {
+                                turn.changed_files.push(path.to_string());
+                            }
+                        }
+                    }
+                    Some("commandExecution") => match item.get("command") {
+                        Some(Value::String(command)) => turn.commands.push(command.clone()),
+                        Some(Value::Array(argv)) => turn.commands.push(sessions::display_command(
+                            &argv
+                                .iter()
+                                .filter_map(|part| part.as_str().map(str::to_string))
+                                .collect::<Vec<_>>(),
+                        )),
+                        _ => {}
+                    },
+                    _ => {}
+                }
+            }
+            "error" => {
+                if params.get("willRetry").and_then(Value::as_bool) == Some(true) {
+                    return;
+                }
+                let Some(turn) = thread.turn_mut(turn_id) else {
+                    return;
+                };
+                if let Some(message) = params.pointer("/error/message").and_then(Value::as_str) {
+                    turn.error = Some(message.to_string());
+                }
+            }
+            _ => return,
+        }
+        thread.updated_at = Utc::now();
+        self.changes.send_modify(|version| *version += 1);
+    }
+
+    pub fn snapshot(&self, thread_id: &str) -> Option<ThreadSnapshot> {
+        let thread = self.threads.get(thread_id)?;
+        Some(ThreadSnapshot {
+            thread_id: thread_id.to_string(),
+            running: thread
+                .turns
+                .back()
+                .is_some_and(|turn| turn.state == TurnState::InProgress),
+            turns: thread.turns.iter().cloned().collect(),
+            updated_at: thread.updated_at,
+        })
+    }
+
+    /// Every tracked thread, most recently active first.
+    pub fn snapshots(&self) -> Vec<ThreadSnapshot> {
+        let mut snapshots: Vec<ThreadSnapshot> = self
+            .threads
+            .keys()
+            .filter_map(|thread_id| self.snapshot(thread_id))
+            .collect();
+        snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.updated_at));
+        snapshots
+    }
+
+    pub fn turn(&self, thread_id: &str, turn_id: &str) -> Option<TurnSummary> {
+        self.threads
+            .get(thread_id)?
+            .turns
+            .iter()
+            .rfind(|turn| turn.turn_id == turn_id)
+            .cloned()
+    }
+}
+
+/// Waits until a turn has ended or `timeout` elapses, and returns what is known about it.
+/// `changes` must be subscribed before the turn is started so its end can't be missed.
+pub async fn wait_for_turn(
+    tracker: &Mutex<ActivityTracker>,
+    changes: &mut watch::Receiver<u64>,
+    thread_id: &str,
+    turn_id: &str,
+    timeout: Duration,
+) -> Option<TurnSummary> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let turn = tracker
+            .lock()
+            .ok()
+            .and_then(|tracker| tracker.turn(thread_id, turn_id));
+        if turn
+            .as_ref()
+            .is_some_and(|turn| turn.state != TurnState::InProgress)
+        {
+            return turn;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero()
+            || tokio::time::timeout(remaining, changes.changed())
+                .await
+                .is_err()
+        {
+            return turn;
+        }
+    }
+}
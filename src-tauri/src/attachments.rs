@@ -0,0 +1,459 @@
+//! Files attached to a turn. Attachments are staged per thread in a temporary directory,
+//! checked and (for large images) downscaled there, and turned into `localImage` or text
+//! input items when the turn is sent.
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use image::{GenericImageView, ImageFormat};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tauri::State;
+
+use crate::commands::CodexError;
+use crate::state::AppState;
+
+const ATTACHMENTS_DIR: &str = "codexia-zen-attachments";
+/// Largest image accepted before downscaling.
+const MAX_IMAGE_BYTES: usize = 20 * 1024 * 1024;
+/// Images are downscaled so their longest side fits, which is what the models use anyway.
+const MAX_IMAGE_DIMENSION: u32 = 2048;
+/// Images above this size are re-encoded even when their dimensions fit.
+const REENCODE_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+const MAX_TEXT_BYTES: usize = 256 * 1024;
+/// Thread directories left behind by a crash are removed after this long.
+const STALE_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AttachmentKind {
+    Image,
+    Text,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: String,
+    pub thread_id: String,
+    pub name: String,
+    pub kind: AttachmentKind,
+    pub mime_type: String,
+    /// Staged copy; `None` for remote images, which are passed by URL.
+    pub path: Option<String>,
+    pub url: Option<String>,
+    pub size: u64,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub downscaled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Where an attachment comes from: a file on disk (picker or drag-and-drop), raw bytes
+/// (clipboard images, dropped blobs), or an image URL.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AttachmentSource {
+    Path {
+        path: String,
+    },
+    Bytes {
+        name: Option<String>,
+        /// Base64-encoded content.
+        data: String,
+    },
+    Url {
+        url: String,
+    },
+}
+
+#[derive(Debug, Default)]
+pub struct AttachmentStore {
+    staged: HashMap<String, Vec<Attachment>>,
+}
+
+fn attachments_root() -> PathBuf {
+    std::env::temp_dir().join(ATTACHMENTS_DIR)
+}
+
+fn thread_dir(thread_id: &str) -> PathBuf {
+    let name: String = thread_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    attachments_root().join(name)
+}
+
+fn image_mime(format: ImageFormat) -> Option<&'static str> {
+    match format {
+        ImageFormat::Png => Some("image/png"),
+        ImageFormat::Jpeg => Some("image/jpeg"),
+        ImageFormat::Gif => Some("image/gif"),
+        ImageFormat::WebP => Some("image/webp"),
+        _ => None,
+    }
+}
+
+fn extension(format: ImageFormat) -> &'static str {
+    format.extensions_str().first().copied().unwrap_or("img")
+}
+
+impl AttachmentStore {
+    pub fn list(&self, thread_id: &str) -> Vec<Attachment> {
+        self.staged.get(thread_id).cloned().unwrap_or_default()
+    }
+
+    pub fn add(&mut self, attachment: Attachment) {
+        self.staged
+            .entry(attachment.thread_id.clone())
+            .or_default()
+            .push(attachment);
+    }
+
+    pub fn remove(&mut self, thread_id: &str, attachment_id: &str) -> bool {
+        let Some(staged) = self.staged.get_mut(thread_id) else {
+            return false;
+        };
+        let Some(position) = staged.iter().position(|a| a.id == attachment_id) else {
+            return false;
+        };
+        let attachment = staged.remove(position);
+        if let Some(path) = attachment.path
+            && let Err(e) = fs::remove_file(&path)
+        {
+            log::warn!("Failed to remove attachment {}: {}", path, e);
+        }
+        true
+    }
+
+    /// User input items for the given staged attachments: images by path or URL, text files
+    /// inlined.
+    pub fn input_items(
+        &self,
+        thread_id: &str,
+        attachment_ids: &[String],
+    ) -> Result<Vec<Value>, String> {
+        let staged = self
+            .staged
+            .get(thread_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let mut items = Vec::new();
+        for id in attachment_ids {
+            let attachment = staged
+                .iter()
+                .find(|a| &a.id == id)
+                .ok_or_else(|| format!("Attachment {} is not staged for this thread", id))?;
+            items.push(
+                match (&attachment.kind, &attachment.path, &attachment.url) {
+                    (AttachmentKind::Image, _, Some(url)) => json!({ "type": "image", "url": url }),
+                    (AttachmentKind::Image, Some(path), None) => {
+                        json!({ "type": "localImage", "path": path })
+                    }
+                    (AttachmentKind::Text, Some(path), _) => {
+                        let content = fs::read_to_string(path).map_err(|e| {
+                            format!("Failed to read attachment {}: {}", attachment.name, e)
+                        })?;
+                        json!({
+                            "type": "text",
+                            "text": format!(
+                                "<attached_file name=\"{}\">\n{}\n</attached_file>",
+                                attachment.name, content
+                            ),
+                        })
+                    }
+                    _ => return Err(format!("Attachment {} has no content", attachment.name)),
+                },
+            );
+        }
+        Ok(items)
+    }
+
+    /// Takes sent attachments off the staged list. Their files stay until the thread's
+    /// attachments are cleared, since the app-server reads them while the turn runs.
+    pub fn mark_sent(&mut self, thread_id: &str, attachment_ids: &[String]) {
+        if let Some(staged) = self.staged.get_mut(thread_id) {
+            staged.retain(|a| !attachment_ids.contains(&a.id));
+        }
+    }
+
+    /// Drops everything staged or sent for a thread, e.g. when it is closed.
+    pub fn clear(&mut self, thread_id: &str) -> Result<(), String> {
+        self.staged.remove(thread_id);
+        let dir = thread_dir(thread_id);
+        if dir.exists() {
+            fs::remove_dir_all(&dir)
+                .map_err(|e| format!("Failed to remove {}: {}", dir.display(), e))?;
+        }
+        Ok(())
+    }
+
+    /// Drops the attachments of every thread, when the app-server stops or the app exits.
+    pub fn clear_all(&mut self) -> Result<(), String> {
+        self.staged.clear();
+        let root = attachments_root();
+        if root.exists() {
+            fs::remove_dir_all(&root)
+                .map_err(|e| format!("Failed to remove {}: {}", root.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads, validates and writes the staged copy of an attachment. Slow for large images, so it
+/// runs outside the store's lock.
+pub fn prepare(thread_id: &str, source: AttachmentSource) -> Result<Attachment, String> {
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    Ok(match source {
+        AttachmentSource::Url { url } => {
+            let url = url.trim().to_string();
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return Err(format!("Unsupported image URL {}", url));
+            }
+            let name = url
+                .rsplit('/')
+                .next()
+                .filter(|name| !name.is_empty())
+                .unwrap_or("image")
+                .to_string();
+            Attachment {
+                id,
+                thread_id: thread_id.to_string(),
+                name,
+                kind: AttachmentKind::Image,
+                mime_type: "image/*".to_string(),
+                path: None,
+                url: Some(url),
+                size: 0,
+                width: None,
+                height: None,
+                downscaled: false,
+                created_at: Utc::now(),
+            }
+        }
+        AttachmentSource::Path { path } => {
+            let path = Path::new(&path);
+            let metadata = fs::metadata(path)
+                .map_err(|e| format!("Cannot attach {}: {}", path.display(), e))?;
+            if !metadata.is_file() {
+                return Err(format!("{} is not a file", path.display()));
+            }
+            if metadata.len() > MAX_IMAGE_BYTES as u64 {
+                return Err(format!(
+                    "{} is larger than {} MB",
+                    path.display(),
+                    MAX_IMAGE_BYTES / 1024 / 1024
+                ));
+            }
+            let data =
+                fs::read(path).map_err(|e| format!("Cannot attach {}: {}", path.display(), e))?;
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| "attachment".to_string());
+            store(thread_id, id, name, data)?
+        }
+        AttachmentSource::Bytes { name, data } => {
+            let data = base64::engine::general_purpose::STANDARD
+                .decode(data.trim())
+                .map_err(|e| format!("Attachment data is not valid base64: {}", e))?;
+            if data.len() > MAX_IMAGE_BYTES {
+                return Err(format!(
+                    "Attachment is larger than {} MB",
+                    MAX_IMAGE_BYTES / 1024 / 1024
+                ));
+            }
+            let name = name
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| "pasted".to_string());
+            store(thread_id, id, name, data)?
+        }
+    })
+}
+
+/// Validates the content and writes the staged copy, downscaling large images.
+fn store(thread_id: &str, id: String, name: String, data: Vec<u8>) -> Result<Attachment, String> {
+    let dir = thread_dir(thread_id);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let format = image::guess_format(&data).ok();
+    if let Some((format, mime)) = format.and_then(|f| image_mime(f).map(|mime| (f, mime))) {
+        let image = image::load_from_memory_with_format(&data, format)
+            .map_err(|e| format!("{} is not a readable image: {}", name, e))?;
+        let (width, height) = image.dimensions();
+        let too_large = width.max(height) > MAX_IMAGE_DIMENSION;
+        // Animated GIFs would lose their frames when re-encoded, so they are kept as is.
+        let downscale =
+            format != ImageFormat::Gif && (too_large || data.len() > REENCODE_IMAGE_BYTES);
+
+        let (path, size, width, height, mime) = if downscale {
+            let image = if too_large {
+                image.resize(
+                    MAX_IMAGE_DIMENSION,
+                    MAX_IMAGE_DIMENSION,
+                    image::imageops::FilterType::Lanczos3,
+                )
+            } else {
+                image
+            };
+            // Photos stay JPEG; everything else becomes PNG to keep sharp edges.
+            let (format, mime) = if format == ImageFormat::Jpeg {
+                (ImageFormat::Jpeg, "image/jpeg")
+            } else {
+                (ImageFormat::Png, "image/png")
+            };
+            let path = dir.join(format!("{}.{}", id, extension(format)));
+            let image = if format == ImageFormat::Jpeg {
+                image::DynamicImage::ImageRgb8(image.to_rgb8())
+            } else {
+                image
+            };
+            image
+                .save_with_format(&path, format)
+                .map_err(|e| format!("Failed to downscale {}: {}", name, e))?;
+            let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+            (path, size, image.width(), image.height(), mime)
+        } else {
+            let path = dir.join(format!("{}.{}", id, extension(format)));
+            fs::write(&path, &data)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+            (path, data.len() as u64, width, height, mime)
+        };
+        return Ok(Attachment {
+            id,
+            thread_id: thread_id.to_string(),
+            name,
+            kind: AttachmentKind::Image,
+            mime_type: mime.to_string(),
+            path: Some(path.to_string_lossy().to_string()),
+            url: None,
+            size,
+            width: Some(width),
+            height: Some(height),
+            downscaled: downscale,
+            created_at: Utc::now(),
+        });
+    }
+    if format.is_some() {
+        return Err(format!(
+            "{} is an unsupported image format; use PNG, JPEG, GIF or WebP",
+            name
+        ));
+    }
+
+    if data.len() > MAX_TEXT_BYTES {
+        return Err(format!(
+            "{} is larger than {} KB; only images can be bigger",
+            name,
+            MAX_TEXT_BYTES / 1024
+        ));
+    }
+    if data.contains(&0) || std::str::from_utf8(&data).is_err() {
+        return Err(format!(
+            "{} is neither an image nor a UTF-8 text file",
+            name
+        ));
+    }
+    let path = dir.join(format!("{}.txt", id));
+    fs::write(&path, &data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(Attachment {
+        id,
+        thread_id: thread_id.to_string(),
+        name,
+        kind: AttachmentKind::Text,
+        mime_type: "text/plain".to_string(),
+        path: Some(path.to_string_lossy().to_string()),
+        url: None,
+        size: data.len() as u64,
+        width: None,
+        height: None,
+        downscaled: false,
+        created_at: Utc::now(),
+    })
+}
+
+/// Removes thread directories left over from earlier runs.
+pub fn remove_stale_attachments() {
+    let Ok(entries) = fs::read_dir(attachments_root()) else {
+        return;
+    };
+    let cutoff = SystemTime::now() - STALE_AFTER;
+    for entry in entries.flatten() {
+        let stale = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified < cutoff);
+        if stale && let Err(e) = fs::remove_dir_all(entry.path()) {
+            log::warn!(
+                "Failed to remove stale attachments {}: {}",
+                entry.path().display(),
+                e
+            );
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn stage_attachment(
+    thread_id: String,
+    source: AttachmentSource,
+    state: State<'_, AppState>,
+) -> Result<Attachment, CodexError> {
+    let attachment = tauri::async_runtime::spawn_blocking(move || prepare(&thread_id, source))
+        .await
+        .map_err(|e| CodexError::internal(format!("Failed to stage attachment: {}", e)))?
+        .map_err(CodexError::io)?;
+    let mut store = state
+        .attachments
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    store.add(attachment.clone());
+    Ok(attachment)
+}
+
+#[tauri::command]
+pub async fn list_attachments(
+    thread_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Attachment>, CodexError> {
+    let store = state
+        .attachments
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(store.list(&thread_id))
+}
+
+#[tauri::command]
+pub async fn remove_attachment(
+    thread_id: String,
+    attachment_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, CodexError> {
+    let mut store = state
+        .attachments
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(store.remove(&thread_id, &attachment_id))
+}
+
+#[tauri::command]
+pub async fn clear_attachments(
+    thread_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), CodexError> {
+    let mut store = state
+        .attachments
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    store.clear(&thread_id).map_err(CodexError::io)
+}
@@ -0,0 +1,774 @@
+//! Unattended batch runs: the same kind of prompt sent to many projects, a few at a time, with
+//! approvals answered by a rule set. Every job runs in its own thread through the app's
+//! client, so jobs show up in the thread list like any other, and the outcome of each job is
+//! written to a Markdown and a JSON report under `~/.codexia-zen/batches/<id>/`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{Emitter, Manager, State};
+use tokio::sync::Semaphore;
+
+use crate::activity::{self, TurnState};
+use crate::codex::command_analysis::RiskLevel;
+use crate::codex::types::{ApprovalRequest, ApprovalRequestKind};
+use crate::commands::{self, ApprovalDecisionType, ApprovalResponse, CodexError};
+use crate::config;
+use crate::projects::{self, ProjectDefaults};
+use crate::state::AppState;
+use crate::storage;
+use crate::usage::{PriceTable, UsageBucket, UsageReportParams};
+
+const BATCH_EVENT: &str = "codex://batch-updated";
+const BATCHES_DIR: &str = "batches";
+const DEFAULT_CONCURRENCY: usize = 2;
+const MAX_CONCURRENCY: usize = 8;
+const DEFAULT_TIMEOUT_MINUTES: u64 = 30;
+/// How long a timed-out or cancelled turn gets to acknowledge the interrupt.
+const INTERRUPT_GRACE: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchJobSpec {
+    pub cwd: String,
+    pub prompt: String,
+    /// Profile from the Codex config whose model settings the job uses.
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RuleAction {
+    Accept,
+    Decline,
+    /// Leave the request to the user, as outside a batch.
+    Ask,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ApprovalKind {
+    Command,
+    FileChange,
+}
+
+/// Matches when every condition that is set holds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalRule {
+    #[serde(default)]
+    pub kind: Option<ApprovalKind>,
+    /// Single commands starting with these words, e.g. `cargo` or `npm install`. Chained,
+    /// piped or substituted commands never match, see `CommandDetail::starts_with_command`.
+    #[serde(default)]
+    pub command_prefix: Option<String>,
+    /// Commands assessed at this risk level or below. Without `command_prefix` this is the
+    /// only guard: any command, compound ones included, matches if its assessed risk does.
+    #[serde(default)]
+    pub max_risk: Option<RiskLevel>,
+    pub action: RuleAction,
+}
+
+/// Rules are tried in order; the first match decides.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalRuleSet {
+    #[serde(default)]
+    pub rules: Vec<ApprovalRule>,
+    #[serde(default = "default_action")]
+    pub default_action: RuleAction,
+}
+
+fn default_action() -> RuleAction {
+    RuleAction::Decline
+}
+
+impl Default for ApprovalRuleSet {
+    fn default() -> Self {
+        Self {
+            rules: Vec::new(),
+            default_action: default_action(),
+        }
+    }
+}
+
+impl ApprovalRuleSet {
+    fn decide(&self, request: &ApprovalRequest) -> RuleAction {
+        let (kind, details) = match &request.kind {
+            ApprovalRequestKind::CommandExecution { details, .. } => {
+                (ApprovalKind::Command, details.as_ref())
+            }
+            ApprovalRequestKind::FileChange { .. } => (ApprovalKind::FileChange, None),
+        };
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.kind.is_none_or(|k| k == kind)
+                    && rule.command_prefix.as_deref().is_none_or(|prefix| {
+                        details.is_some_and(|d| d.starts_with_command(prefix))
+                    })
+                    && rule
+                        .max_risk
+                        .is_none_or(|max| details.is_some_and(|d| d.risk.level <= max))
+            })
+            .map(|rule| rule.action)
+            .unwrap_or(self.default_action)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub jobs: Vec<BatchJobSpec>,
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    #[serde(default)]
+    pub approval_rules: ApprovalRuleSet,
+    #[serde(default)]
+    pub sandbox: Option<String>,
+    #[serde(default)]
+    pub approval_policy: Option<String>,
+    /// Per job; a turn still running after this long is interrupted.
+    #[serde(default)]
+    pub timeout_minutes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+    Interrupted,
+    TimedOut,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobApproval {
+    pub kind: ApprovalKind,
+    pub summary: String,
+    pub action: RuleAction,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchJob {
+    pub spec: BatchJobSpec,
+    pub status: JobStatus,
+    pub thread_id: Option<String>,
+    pub turn_id: Option<String>,
+    pub final_message: Option<String>,
+    pub changed_files: Vec<String>,
+    pub usage: Option<UsageBucket>,
+    pub approvals: Vec<JobApproval>,
+    pub error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReportPaths {
+    pub markdown: String,
+    pub json: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Batch {
+    pub id: String,
+    pub name: String,
+    pub concurrency: usize,
+    pub jobs: Vec<BatchJob>,
+    pub cancelled: bool,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub report: Option<BatchReportPaths>,
+    pub report_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct BatchStore {
+    batches: HashMap<String, Batch>,
+    rules: HashMap<String, ApprovalRuleSet>,
+    /// Thread of each running job, to route its approval requests to the batch's rules.
+    threads: HashMap<String, (String, usize)>,
+}
+
+impl BatchStore {
+    fn job_mut(&mut self, batch_id: &str, index: usize) -> Option<&mut BatchJob> {
+        self.batches.get_mut(batch_id)?.jobs.get_mut(index)
+    }
+
+    fn is_cancelled(&self, batch_id: &str) -> bool {
+        self.batches
+            .get(batch_id)
+            .is_some_and(|batch| batch.cancelled)
+    }
+}
+
+/// Applies a batch's rules to an approval request from one of its jobs. Returns `false` when
+/// the request is not a batch's to answer and should go to the user.
+pub fn handle_approval(app: &tauri::AppHandle, request: &ApprovalRequest) -> bool {
+    let state = app.state::<AppState>();
+    let Ok(mut store) = state.batches.lock() else {
+        return false;
+    };
+    let Some((batch_id, index)) = store.threads.get(&request.thread_id).cloned() else {
+        return false;
+    };
+    let action = store
+        .rules
+        .get(&batch_id)
+        .map(|rules| rules.decide(request))
+        .unwrap_or(RuleAction::Ask);
+
+    let (kind, summary) = match &request.kind {
+        ApprovalRequestKind::CommandExecution { details, .. } => (
+            ApprovalKind::Command,
+            details
+                .as_ref()
+                .map(|d| d.command.clone())
+                .unwrap_or_else(|| request.reason.clone().unwrap_or_default()),
+        ),
+        ApprovalRequestKind::FileChange { files, .. } => (
+            ApprovalKind::FileChange,
+            files
+                .iter()
+                .map(|file| file.path.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    };
+    if let Some(job) = store.job_mut(&batch_id, index) {
+        job.approvals.push(JobApproval {
+            kind,
+            summary,
+            action,
+            at: Utc::now(),
+        });
+    }
+    drop(store);
+
+    let decision = match action {
+        RuleAction::Accept => ApprovalDecisionType::Accept,
+        RuleAction::Decline => ApprovalDecisionType::Decline,
+        RuleAction::Ask => return false,
+    };
+    let response = ApprovalResponse {
+        request_id: request.request_id.clone(),
+        decision,
+        is_command_execution: kind == ApprovalKind::Command,
+    };
+    // Answered off the client's event loop, which is the thread calling us.
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        if let Err(e) = commands::respond_to_approval(response, state, app.clone()).await {
+            log::error!("Failed to answer batch approval: {}", e);
+        }
+    });
+    true
+}
+
+fn emit_batch(app: &tauri::AppHandle, batch_id: &str) {
+    let state = app.state::<AppState>();
+    let Ok(store) = state.batches.lock() else {
+        return;
+    };
+    if let Some(batch) = store.batches.get(batch_id)
+        && let Err(e) = app.emit(BATCH_EVENT, batch)
+    {
+        log::warn!("Failed to emit batch update: {}", e);
+    }
+}
+
+fn update_job(
+    app: &tauri::AppHandle,
+    batch_id: &str,
+    index: usize,
+    update: impl FnOnce(&mut BatchJob),
+) {
+    let state = app.state::<AppState>();
+    if let Ok(mut store) = state.batches.lock()
+        && let Some(job) = store.job_mut(batch_id, index)
+    {
+        update(job);
+    }
+    emit_batch(app, batch_id);
+}
+
+/// Thread-start parameters of a job, with the profile's model settings filled in.
+fn thread_start_params(spec: &BatchJobSpec, request: &BatchRequest) -> Result<Value, String> {
+    let mut params = json!({
+        "cwd": spec.cwd,
+        "sandbox": request.sandbox,
+        "approvalPolicy": request.approval_policy,
+    });
+    if let Some(name) = &spec.profile {
+        let codex_config = config::load_codex_config()?;
+        let profile = codex_config
+            .profiles
+            .get(name)
+            .ok_or_else(|| format!("Profile '{}' is not defined in the Codex config", name))?;
+        projects::apply_defaults(
+            &mut params,
+            &ProjectDefaults {
+                model: profile.model.clone(),
+                model_provider: profile.model_provider.clone(),
+                ..Default::default()
+            },
+        );
+        if let Some(effort) = &profile.model_reasoning_effort {
+            params["config"] = json!({ "model_reasoning_effort": effort });
+        }
+    }
+    Ok(params)
+}
+
+/// Runs one job to the end and records its outcome.
+async fn run_job(
+    app: tauri::AppHandle,
+    batch_id: String,
+    index: usize,
+    request: Arc<BatchRequest>,
+) {
+    let spec = &request.jobs[index];
+    let state = app.state::<AppState>();
+    update_job(&app, &batch_id, index, |job| {
+        job.status = JobStatus::Running;
+        job.started_at = Some(Utc::now());
+    });
+
+    let result: Result<(JobStatus, Option<String>), String> = async {
+        let params = thread_start_params(spec, &request)?;
+        let params =
+            serde_json::from_value(params).map_err(|e| format!("Invalid thread options: {}", e))?;
+        let started = commands::thread_start(params, state.clone(), app.clone()).await;
+        let thread_id = match &started {
+            Ok(response) => serde_json::to_value(&response.thread)
+                .ok()
+                .and_then(|thread| thread.get("id").and_then(Value::as_str).map(str::to_string)),
+            Err(e) => e.recovered_id("/thread/id"),
+        };
+        // A thread started with an unreadable response still belongs to the job.
+        if let Some(thread_id) = &thread_id {
+            if let Ok(mut store) = state.batches.lock() {
+                store
+                    .threads
+                    .insert(thread_id.clone(), (batch_id.clone(), index));
+            }
+            update_job(&app, &batch_id, index, |job| {
+                job.thread_id = Some(thread_id.clone())
+            });
+        }
+        started.map_err(|e| e.to_string())?;
+        let thread_id = thread_id.ok_or("thread/start response has no thread id")?;
+
+        let mut changes = state
+            .activity
+            .lock()
+            .map_err(|e| format!("Failed to acquire lock: {}", e))?
+            .subscribe();
+        let params = serde_json::from_value(json!({
+            "threadId": thread_id,
+            "input": [{ "type": "text", "text": spec.prompt }],
+        }))
+        .map_err(|e| format!("Invalid turn parameters: {}", e))?;
+        let response = commands::turn_start(params, None, None, state.clone(), app.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+        let turn_id = serde_json::to_value(&response)
+            .ok()
+            .and_then(|value| {
+                value
+                    .pointer("/turn/id")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .ok_or("turn/start response has no turn id")?;
+        update_job(&app, &batch_id, index, |job| {
+            job.turn_id = Some(turn_id.clone())
+        });
+
+        let timeout =
+            Duration::from_secs(60 * request.timeout_minutes.unwrap_or(DEFAULT_TIMEOUT_MINUTES));
+        let mut turn =
+            activity::wait_for_turn(&state.activity, &mut changes, &thread_id, &turn_id, timeout)
+                .await;
+        let timed_out = turn
+            .as_ref()
+            .is_none_or(|t| t.state == TurnState::InProgress);
+        if timed_out {
+            log::warn!(
+                "Batch job {} timed out, interrupting thread {}",
+                index,
+                thread_id
+            );
+            let params =
+                serde_json::from_value(json!({ "threadId": thread_id, "turnId": turn_id }))
+                    .map_err(|e| format!("Invalid interrupt parameters: {}", e))?;
+            if let Err(e) = commands::turn_interrupt(params, state.clone()).await {
+                log::warn!("Failed to interrupt timed-out job: {}", e);
+            }
+            turn = activity::wait_for_turn(
+                &state.activity,
+                &mut changes,
+                &thread_id,
+                &turn_id,
+                INTERRUPT_GRACE,
+            )
+            .await;
+        }
+
+        let usage = state
+            .usage
+            .lock()
+            .map(|mut usage| {
+                let params = UsageReportParams {
+                    thread_id: Some(thread_id.clone()),
+                    ..Default::default()
+                };
+                usage
+                    .report(&params, &PriceTable::load().unwrap_or_default())
+                    .total
+            })
+            .ok();
+        let cancelled = state
+            .batches
+            .lock()
+            .map(|store| store.is_cancelled(&batch_id))
+            .unwrap_or(false);
+        let status = match turn.as_ref().map(|t| t.state) {
+            _ if timed_out => JobStatus::TimedOut,
+            Some(TurnState::Completed) => JobStatus::Completed,
+            Some(TurnState::Interrupted) if cancelled => JobStatus::Cancelled,
+            Some(TurnState::Interrupted) => JobStatus::Interrupted,
+            _ => JobStatus::Failed,
+        };
+        update_job(&app, &batch_id, index, |job| {
+            if let Some(turn) = &turn {
+                job.final_message = turn.agent_message.clone();
+                job.changed_files = turn.changed_files.clone();
+            }
+            job.usage = usage;
+        });
+        Ok((status, turn.and_then(|t| t.error)))
+    }
+    .await;
+
+    let (status, error) = result.unwrap_or_else(|e| (JobStatus::Failed, Some(e)));
+    if let Ok(mut store) = state.batches.lock() {
+        store
+            .threads
+            .retain(|_, (batch, job)| !(batch == &batch_id && *job == index));
+    }
+    update_job(&app, &batch_id, index, |job| {
+        job.status = status;
+        job.error = error;
+        job.finished_at = Some(Utc::now());
+    });
+}
+
+async fn run_batch(
+    app: tauri::AppHandle,
+    batch_id: String,
+    request: Arc<BatchRequest>,
+    concurrency: usize,
+) {
+    let state = app.state::<AppState>();
+    let permits = Arc::new(Semaphore::new(concurrency));
+    let mut tasks = Vec::new();
+    for index in 0..request.jobs.len() {
+        let Ok(permit) = permits.clone().acquire_owned().await else {
+            break;
+        };
+        let cancelled = state
+            .batches
+            .lock()
+            .map(|store| store.is_cancelled(&batch_id))
+            .unwrap_or(true);
+        if cancelled {
+            break;
+        }
+        let (app, batch_id, request) = (app.clone(), batch_id.clone(), request.clone());
+        tasks.push(tauri::async_runtime::spawn(async move {
+            run_job(app, batch_id, index, request).await;
+            drop(permit);
+        }));
+    }
+    for task in tasks {
+        if let Err(e) = task.await {
+            log::error!("Batch job task failed: {}", e);
+        }
+    }
+
+    let batch = {
+        let Ok(mut store) = state.batches.lock() else {
+            return;
+        };
+        store.rules.remove(&batch_id);
+        let Some(batch) = store.batches.get_mut(&batch_id) else {
+            return;
+        };
+        for job in &mut batch.jobs {
+            if job.status == JobStatus::Pending {
+                job.status = JobStatus::Cancelled;
+            }
+        }
+        batch.finished_at = Some(Utc::now());
+        batch.clone()
+    };
+    let report = write_report(&batch);
+    if let Ok(mut store) = state.batches.lock()
+        && let Some(batch) = store.batches.get_mut(&batch_id)
+    {
+        match report {
+            Ok(paths) => batch.report = Some(paths),
+            Err(e) => {
+                log::error!("Failed to write batch report: {}", e);
+                batch.report_error = Some(e);
+            }
+        }
+    }
+    log::info!("Batch {} finished", batch_id);
+    emit_batch(&app, &batch_id);
+}
+
+fn render_markdown(batch: &Batch) -> String {
+    let mut md = String::new();
+    let _ = writeln!(md, "# Batch report: {}\n", batch.name);
+    let _ = writeln!(
+        md,
+        "Started {}, finished {}.\n",
+        batch.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        batch
+            .finished_at
+            .map(|at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| "-".to_string())
+    );
+    let _ = writeln!(
+        md,
+        "| # | Project | Status | Files changed | Tokens | Cost |"
+    );
+    let _ = writeln!(md, "|---|---|---|---|---|---|");
+    for (index, job) in batch.jobs.iter().enumerate() {
+        let _ = writeln!(
+            md,
+            "| {} | `{}` | {:?} | {} | {} | {} |",
+            index + 1,
+            job.spec.cwd,
+            job.status,
+            job.changed_files.len(),
+            job.usage
+                .as_ref()
+                .map(|u| u.usage.total_tokens.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            job.usage
+                .as_ref()
+                .and_then(|u| u.cost)
+                .map(|cost| format!("{:.4}", cost))
+                .unwrap_or_else(|| "-".to_string()),
+        );
+    }
+
+    for (index, job) in batch.jobs.iter().enumerate() {
+        let _ = writeln!(md, "\n## {}. `{}`\n", index + 1, job.spec.cwd);
+        let _ = writeln!(md, "- Status: {:?}", job.status);
+        if let Some(profile) = &job.spec.profile {
+            let _ = writeln!(md, "- Profile: {}", profile);
+        }
+        if let Some(thread_id) = &job.thread_id {
+            let _ = writeln!(md, "- Thread: `{}`", thread_id);
+        }
+        if let Some(error) = &job.error {
+            let _ = writeln!(md, "- Error: {}", error);
+        }
+        let _ = writeln!(md, "\n**Prompt**\n\n{}", job.spec.prompt.trim());
+        if let Some(message) = &job.final_message {
+            let _ = writeln!(md, "\n**Final message**\n\n{}", message.trim());
+        }
+        if !job.changed_files.is_empty() {
+            let _ = writeln!(md, "\n**Files changed**\n");
+            for path in &job.changed_files {
+                let _ = writeln!(md, "- `{}`", path);
+            }
+        }
+        if !job.approvals.is_empty() {
+            let _ = writeln!(md, "\n**Approvals**\n");
+            for approval in &job.approvals {
+                let _ = writeln!(
+                    md,
+                    "- {:?} `{}`: {:?}",
+                    approval.kind, approval.summary, approval.action
+                );
+            }
+        }
+    }
+    md
+}
+
+fn write_report(batch: &Batch) -> Result<BatchReportPaths, String> {
+    let dir: PathBuf = storage::get_app_data_dir()?
+        .join(BATCHES_DIR)
+        .join(&batch.id);
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let json_path = dir.join("report.json");
+    storage::save_json(&json_path, batch)?;
+    let markdown_path = dir.join("report.md");
+    std::fs::write(&markdown_path, render_markdown(batch))
+        .map_err(|e| format!("Failed to write {}: {}", markdown_path.display(), e))?;
+    Ok(BatchReportPaths {
+        markdown: markdown_path.to_string_lossy().to_string(),
+        json: json_path.to_string_lossy().to_string(),
+    })
+}
+
+#[tauri::command]
+pub async fn start_batch(
+    request: BatchRequest,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<Batch, CodexError> {
+    if request.jobs.is_empty() {
+        return Err(CodexError::config("A batch needs at least one job"));
+    }
+    for spec in &request.jobs {
+        projects::validate_directory(&spec.cwd).map_err(CodexError::config)?;
+        if spec.prompt.trim().is_empty() {
+            return Err(CodexError::config(format!(
+                "The job for {} has an empty prompt",
+                spec.cwd
+            )));
+        }
+    }
+    // Resolve the client up front so a missing codex binary fails the whole batch at once.
+    state.get_or_init_client(&app)?;
+
+    let concurrency = request
+        .concurrency
+        .unwrap_or(DEFAULT_CONCURRENCY)
+        .clamp(1, MAX_CONCURRENCY);
+    let created_at = Utc::now();
+    let batch = Batch {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: request
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Batch {}", created_at.format("%Y-%m-%d %H:%M"))),
+        concurrency,
+        jobs: request
+            .jobs
+            .iter()
+            .map(|spec| BatchJob {
+                spec: spec.clone(),
+                status: JobStatus::Pending,
+                thread_id: None,
+                turn_id: None,
+                final_message: None,
+                changed_files: Vec::new(),
+                usage: None,
+                approvals: Vec::new(),
+                error: None,
+                started_at: None,
+                finished_at: None,
+            })
+            .collect(),
+        cancelled: false,
+        created_at,
+        finished_at: None,
+        report: None,
+        report_error: None,
+    };
+    {
+        let mut store = state
+            .batches
+            .lock()
+            .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+        store
+            .rules
+            .insert(batch.id.clone(), request.approval_rules.clone());
+        store.batches.insert(batch.id.clone(), batch.clone());
+    }
+
+    log::info!("Starting batch {} with {} jobs", batch.id, batch.jobs.len());
+    tauri::async_runtime::spawn(run_batch(
+        app,
+        batch.id.clone(),
+        Arc::new(request),
+        concurrency,
+    ));
+    Ok(batch)
+}
+
+#[tauri::command]
+pub async fn list_batches(state: State<'_, AppState>) -> Result<Vec<Batch>, CodexError> {
+    let store = state
+        .batches
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    let mut batches: Vec<Batch> = store.batches.values().cloned().collect();
+    batches.sort_by_key(|batch| std::cmp::Reverse(batch.created_at));
+    Ok(batches)
+}
+
+#[tauri::command]
+pub async fn get_batch(batch_id: String, state: State<'_, AppState>) -> Result<Batch, CodexError> {
+    let store = state
+        .batches
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    store
+        .batches
+        .get(&batch_id)
+        .cloned()
+        .ok_or_else(|| CodexError::internal(format!("Batch {} not found", batch_id)))
+}
+
+/// Stops starting new jobs and interrupts the running ones.
+#[tauri::command]
+pub async fn cancel_batch(
+    batch_id: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), CodexError> {
+    let running: Vec<(String, String)> = {
+        let mut store = state
+            .batches
+            .lock()
+            .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+        let batch = store
+            .batches
+            .get_mut(&batch_id)
+            .ok_or_else(|| CodexError::internal(format!("Batch {} not found", batch_id)))?;
+        batch.cancelled = true;
+        batch
+            .jobs
+            .iter()
+            .filter(|job| job.status == JobStatus::Running)
+            .filter_map(|job| Some((job.thread_id.clone()?, job.turn_id.clone()?)))
+            .collect()
+    };
+    for (thread_id, turn_id) in running {
+        let params = serde_json::from_value(json!({ "threadId": thread_id, "turnId": turn_id }))
+            .map_err(|e| CodexError::internal(format!("Invalid interrupt parameters: {}", e)))?;
+        if let Err(e) = commands::turn_interrupt(params, state.clone()).await {
+            log::warn!("Failed to interrupt batch thread {}: {}", thread_id, e);
+        }
+    }
+    emit_batch(&app, &batch_id);
+    Ok(())
+}
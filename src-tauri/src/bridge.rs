@@ -0,0 +1,541 @@
+//! Opt-in WebSocket bridge that lets local tools (editor plugins, scripts, a phone on the LAN)
+//! drive the same threads as the GUI.
+//!
+//! Clients connect with `ws://host:port/?token=<token>` (or an `Authorization: Bearer` header)
+//! and exchange JSON messages. Requests are `{"id", "method", "params"}` and get
+//! `{"id", "result"}` or `{"id", "error": {"message"}}` back; app-server notifications and
+//! approval events are pushed as `{"method", "params"}`.
+
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
+use tauri::{Manager, State};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::{Message, http};
+
+use crate::commands::{self, CodexError};
+use crate::state::AppState;
+use crate::storage;
+
+const SETTINGS_FILE: &str = "bridge.json";
+const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 47621;
+/// Events buffered per client before a slow client starts missing some.
+const EVENT_BUFFER: usize = 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_host")]
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub token: String,
+}
+
+fn default_host() -> String {
+    DEFAULT_HOST.to_string()
+}
+
+fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+impl Default for BridgeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: default_host(),
+            port: default_port(),
+            token: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeSettingsUpdate {
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub host: String,
+    pub port: u16,
+    pub token: String,
+    pub url: Option<String>,
+    pub clients: usize,
+    pub error: Option<String>,
+}
+
+struct RunningBridge {
+    host: String,
+    port: u16,
+    address: SocketAddr,
+    token: Arc<RwLock<String>>,
+    shutdown: watch::Sender<bool>,
+    /// Bumped to drop every connected client, e.g. after the token changed.
+    disconnect: watch::Sender<u64>,
+    events: broadcast::Sender<String>,
+    clients: Arc<AtomicUsize>,
+}
+
+#[derive(Default)]
+pub struct BridgeState {
+    settings: Option<BridgeSettings>,
+    /// False while the settings file couldn't be loaded; the defaults used meanwhile are never
+    /// saved over it.
+    settings_loaded: bool,
+    running: Option<RunningBridge>,
+    last_error: Option<String>,
+}
+
+fn new_token() -> String {
+    uuid::Uuid::new_v4().simple().to_string()
+}
+
+fn settings_path() -> Result<PathBuf, String> {
+    storage::get_app_data_path(SETTINGS_FILE)
+}
+
+impl BridgeState {
+    fn settings(&mut self) -> &mut BridgeSettings {
+        if !self.settings_loaded {
+            match settings_path().and_then(|path| storage::load_json_or_set_aside(&path)) {
+                Ok(settings) => {
+                    self.settings = Some(settings);
+                    self.settings_loaded = true;
+                }
+                Err(e) => log::error!("Failed to load bridge settings: {}", e),
+            }
+        }
+        self.settings.get_or_insert_with(BridgeSettings::default)
+    }
+
+    fn save_settings(&mut self) -> Result<(), String> {
+        let settings = self.settings().clone();
+        if !self.settings_loaded {
+            return Err("Bridge settings could not be loaded, so they are not saved".to_string());
+        }
+        storage::save_json(&settings_path()?, &settings)
+    }
+
+    pub fn status(&mut self) -> BridgeStatus {
+        let running = self.running.as_ref().map(|running| {
+            (
+                format!("ws://{}/", running.address),
+                running.clients.load(Ordering::Relaxed),
+            )
+        });
+        let error = self.last_error.clone();
+        let settings = self.settings().clone();
+        BridgeStatus {
+            enabled: settings.enabled,
+            running: running.is_some(),
+            host: settings.host,
+            port: settings.port,
+            token: settings.token,
+            url: running.as_ref().map(|(url, _)| url.clone()),
+            clients: running.map(|(_, clients)| clients).unwrap_or(0),
+            error,
+        }
+    }
+
+    /// Forwards an event to every connected client; a no-op while the bridge is stopped.
+    pub fn publish(&self, event: &Value) {
+        if let Some(running) = &self.running
+            && running.clients.load(Ordering::Relaxed) > 0
+        {
+            let _ = running.events.send(event.to_string());
+        }
+    }
+
+    fn start(&mut self, app: &tauri::AppHandle) -> Result<(), String> {
+        if self.settings().token.is_empty() {
+            self.settings().token = new_token();
+            self.save_settings()?;
+        }
+        let settings = self.settings().clone();
+        if let Some(running) = &self.running
+            && running.host == settings.host
+            && running.port == settings.port
+        {
+            return Ok(());
+        }
+        self.stop();
+        if !matches!(settings.host.as_str(), "127.0.0.1" | "localhost" | "::1") {
+            log::warn!(
+                "WebSocket bridge is listening on {}, reachable from other machines",
+                settings.host
+            );
+        }
+
+        // Bind synchronously so address errors are reported to the caller.
+        let listener = std::net::TcpListener::bind((settings.host.as_str(), settings.port))
+            .map_err(|e| {
+                format!(
+                    "Failed to listen on {}:{}: {}",
+                    settings.host, settings.port, e
+                )
+            })?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure bridge socket: {}", e))?;
+        let address = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read bridge address: {}", e))?;
+
+        let (shutdown, shutdown_rx) = watch::channel(false);
+        let (disconnect, disconnect_rx) = watch::channel(0);
+        let (events, _) = broadcast::channel(EVENT_BUFFER);
+        let clients = Arc::new(AtomicUsize::new(0));
+        let token = Arc::new(RwLock::new(settings.token));
+        let server = Server {
+            app: app.clone(),
+            token: token.clone(),
+            events: events.clone(),
+            clients: clients.clone(),
+            shutdown: shutdown_rx,
+            disconnect: disconnect_rx,
+        };
+        tauri::async_runtime::spawn(async move {
+            match TcpListener::from_std(listener) {
+                Ok(listener) => server.serve(listener).await,
+                Err(e) => log::error!("Failed to start WebSocket bridge: {}", e),
+            }
+        });
+
+        log::info!("WebSocket bridge listening on ws://{}/", address);
+        self.running = Some(RunningBridge {
+            host: settings.host,
+            port: settings.port,
+            address,
+            token,
+            shutdown,
+            disconnect,
+            events,
+            clients,
+        });
+        self.last_error = None;
+        Ok(())
+    }
+
+    /// Issues a new token and drops the clients connected with the old one.
+    fn rotate_token(&mut self) -> Result<(), String> {
+        let token = new_token();
+        self.settings().token = token.clone();
+        self.save_settings()?;
+        if let Some(running) = &self.running {
+            if let Ok(mut current) = running.token.write() {
+                *current = token;
+            }
+            running
+                .disconnect
+                .send_modify(|generation| *generation += 1);
+        }
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some(running) = self.running.take() {
+            let _ = running.shutdown.send(true);
+            log::info!("WebSocket bridge on {} stopped", running.address);
+        }
+    }
+}
+
+/// Starts the bridge at launch when the user has enabled it.
+pub fn start_if_enabled(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let Ok(mut bridge) = state.bridge.lock() else {
+        return;
+    };
+    if bridge.settings().enabled
+        && let Err(e) = bridge.start(app)
+    {
+        log::error!("{}", e);
+        bridge.last_error = Some(e);
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BridgeRequest {
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Clone)]
+struct Server {
+    app: tauri::AppHandle,
+    token: Arc<RwLock<String>>,
+    events: broadcast::Sender<String>,
+    clients: Arc<AtomicUsize>,
+    shutdown: watch::Receiver<bool>,
+    disconnect: watch::Receiver<u64>,
+}
+
+/// Compares without short-circuiting so response timing doesn't leak the token.
+pub(crate) fn token_matches(candidate: &str, token: &str) -> bool {
+    candidate.len() == token.len()
+        && candidate
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn request_token(request: &Request) -> Option<String> {
+    if let Some(header) = request
+        .headers()
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        && let Some(token) = header.strip_prefix("Bearer ")
+    {
+        return Some(token.trim().to_string());
+    }
+    request.uri().query().and_then(|query| {
+        query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("token="))
+            .map(str::to_string)
+    })
+}
+
+impl Server {
+    async fn serve(self, listener: TcpListener) {
+        let mut shutdown = self.shutdown.clone();
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        let server = self.clone();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = server.handle_connection(stream).await {
+                                log::warn!("Bridge connection from {} ended: {}", peer, e);
+                            }
+                        });
+                    }
+                    Err(e) => log::warn!("Bridge failed to accept a connection: {}", e),
+                },
+                _ = shutdown.changed() => break,
+            }
+        }
+    }
+
+    async fn handle_connection(self, stream: TcpStream) -> anyhow::Result<()> {
+        let token = self
+            .token
+            .read()
+            .map(|token| token.clone())
+            .map_err(|e| anyhow::anyhow!("Failed to read bridge token: {}", e))?;
+        // The callback signature is fixed by tungstenite, including its large error type.
+        #[allow(clippy::result_large_err)]
+        let authorize = move |request: &Request, response: Response| {
+            if request_token(request).is_some_and(|candidate| token_matches(&candidate, &token)) {
+                Ok(response)
+            } else {
+                let mut denied = ErrorResponse::new(Some("invalid or missing token".to_string()));
+                *denied.status_mut() = http::StatusCode::UNAUTHORIZED;
+                Err(denied)
+            }
+        };
+        let socket = tokio_tungstenite::accept_hdr_async(stream, authorize).await?;
+        let (mut write, mut read) = socket.split();
+
+        self.clients.fetch_add(1, Ordering::Relaxed);
+        let mut events = self.events.subscribe();
+        let mut shutdown = self.shutdown.clone();
+        let mut disconnect = self.disconnect.clone();
+        disconnect.mark_unchanged();
+        let (replies_tx, mut replies) = mpsc::unbounded_channel::<String>();
+
+        let result: anyhow::Result<()> = async {
+            loop {
+                tokio::select! {
+                    message = read.next() => match message {
+                        Some(Ok(Message::Text(text))) => {
+                            let server = self.clone();
+                            let replies_tx = replies_tx.clone();
+                            let text = text.to_string();
+                            tauri::async_runtime::spawn(async move {
+                                let reply = server.dispatch(&text).await;
+                                let _ = replies_tx.send(reply.to_string());
+                            });
+                        }
+                        Some(Ok(Message::Close(_))) | None => break,
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Err(e.into()),
+                    },
+                    Some(reply) = replies.recv() => write.send(Message::text(reply)).await?,
+                    event = events.recv() => match event {
+                        Ok(event) => write.send(Message::text(event)).await?,
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            let notice = json!({ "method": "bridge/lagged", "params": { "missed": missed } });
+                            write.send(Message::text(notice.to_string())).await?;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = shutdown.changed() => {
+                        let _ = write.send(Message::Close(None)).await;
+                        break;
+                    }
+                    _ = disconnect.changed() => {
+                        let _ = write.send(Message::Close(None)).await;
+                        break;
+                    }
+                }
+            }
+            Ok(())
+        }
+        .await;
+
+        self.clients.fetch_sub(1, Ordering::Relaxed);
+        result
+    }
+
+    async fn dispatch(&self, text: &str) -> Value {
+        let request: BridgeRequest = match serde_json::from_str(text) {
+            Ok(request) => request,
+            Err(e) => {
+                return json!({ "id": null, "error": { "message": format!("Invalid request: {}", e) } });
+            }
+        };
+        match self.call(&request.method, request.params).await {
+            Ok(result) => json!({ "id": request.id, "result": result }),
+            Err(message) => json!({ "id": request.id, "error": { "message": message } }),
+        }
+    }
+
+    /// Runs a request through the same Tauri commands the GUI invokes, so validation,
+    /// checkpoints and bookkeeping behave identically.
+    async fn call(&self, method: &str, params: Value) -> Result<Value, String> {
+        fn parse<T: serde::de::DeserializeOwned>(params: &Value, key: &str) -> Result<T, String> {
+            let value = params.get(key).cloned().unwrap_or(Value::Null);
+            serde_json::from_value(value).map_err(|e| format!("Invalid {}: {}", key, e))
+        }
+        fn reply<T: Serialize>(result: Result<T, CodexError>) -> Result<Value, String> {
+            let value = result.map_err(|e| e.to_string())?;
+            serde_json::to_value(value).map_err(|e| e.to_string())
+        }
+
+        let app = self.app.clone();
+        let state: State<'_, AppState> = app.state::<AppState>();
+        match method {
+            "thread/list" => reply(
+                commands::thread_list(
+                    parse(&params, "params")?,
+                    parse(&params, "filter")?,
+                    state,
+                    app.clone(),
+                )
+                .await,
+            ),
+            "thread/start" => {
+                reply(commands::thread_start(parse(&params, "params")?, state, app.clone()).await)
+            }
+            "thread/resume" => {
+                reply(commands::thread_resume(parse(&params, "params")?, state).await)
+            }
+            "turn/start" => reply(
+                commands::turn_start(
+                    parse(&params, "params")?,
+                    parse(&params, "promptContext")?,
+                    parse(&params, "attachments")?,
+                    state,
+                    app.clone(),
+                )
+                .await,
+            ),
+            "turn/interrupt" => {
+                reply(commands::turn_interrupt(parse(&params, "params")?, state).await)
+            }
+            "approval/list" => reply(commands::list_pending_approvals(state).await),
+            "approval/respond" => {
+                reply(
+                    commands::respond_to_approval(parse(&params, "response")?, state, app.clone())
+                        .await,
+                )
+            }
+            _ => Err(format!("Unknown method {}", method)),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_bridge_status(state: State<'_, AppState>) -> Result<BridgeStatus, CodexError> {
+    let mut bridge = state
+        .bridge
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(bridge.status())
+}
+
+#[tauri::command]
+pub async fn configure_bridge(
+    update: BridgeSettingsUpdate,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<BridgeStatus, CodexError> {
+    let mut bridge = state
+        .bridge
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    let settings = bridge.settings();
+    if let Some(enabled) = update.enabled {
+        settings.enabled = enabled;
+    }
+    if let Some(host) = update
+        .host
+        .map(|h| h.trim().to_string())
+        .filter(|h| !h.is_empty())
+    {
+        settings.host = host;
+    }
+    if let Some(port) = update.port {
+        settings.port = port;
+    }
+    let enabled = settings.enabled;
+    bridge.save_settings().map_err(CodexError::config)?;
+
+    if enabled {
+        if let Err(e) = bridge.start(&app) {
+            bridge.last_error = Some(e.clone());
+            return Err(CodexError::io(e));
+        }
+    } else {
+        bridge.stop();
+    }
+    Ok(bridge.status())
+}
+
+/// Issues a new token; connected clients are dropped and must reconnect with it.
+#[tauri::command]
+pub async fn regenerate_bridge_token(
+    state: State<'_, AppState>,
+) -> Result<BridgeStatus, CodexError> {
+    let mut bridge = state
+        .bridge
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    bridge.rotate_token().map_err(CodexError::config)?;
+    Ok(bridge.status())
+}
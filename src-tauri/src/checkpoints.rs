@@ -0,0 +1,540 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use tauri::{Manager, State};
+use walkdir::WalkDir;
+
+use crate::commands::CodexError;
+use crate::sessions;
+use crate::state::AppState;
+use crate::storage;
+
+const CHECKPOINTS_FILE: &str = "checkpoints.json";
+const SHADOW_DIR: &str = "checkpoints";
+/// Working trees with more files than this are not snapshotted before turns.
+const MAX_SNAPSHOT_FILES: usize = 20_000;
+/// Working trees larger than this are not snapshotted before turns.
+const MAX_SNAPSHOT_BYTES: u64 = 256 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckpointReason {
+    /// Taken automatically before a turn was sent.
+    BeforeTurn,
+    /// Taken before a restore, so the restore itself can be undone.
+    BeforeRestore,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+    /// Commit id in the shadow object store.
+    pub id: String,
+    pub thread_id: String,
+    pub turn_id: Option<String>,
+    pub cwd: String,
+    pub reason: CheckpointReason,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointFileChange {
+    pub path: String,
+    /// Git status letter: `A`, `D`, `M`, `T`.
+    pub status: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointDiff {
+    pub checkpoint: Checkpoint,
+    pub files: Vec<CheckpointFileChange>,
+    /// Unified diff from the checkpoint to the current working tree.
+    pub patch: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    pub restored: Vec<String>,
+    pub removed: Vec<String>,
+    /// Files that could not be restored or removed, as `path: error`; the others still were.
+    pub failed: Vec<String>,
+    /// Checkpoint of the working tree as it was right before the restore.
+    pub backup: Checkpoint,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckpointStore {
+    threads: HashMap<String, Vec<Checkpoint>>,
+    thread_cwds: HashMap<String, String>,
+    #[serde(skip)]
+    loaded: bool,
+}
+
+/// A private git repository that stores snapshots of one working directory. It has its own
+/// object store and index, so the user's repository (if any) is never touched.
+struct ShadowRepo {
+    git_dir: PathBuf,
+    work_tree: PathBuf,
+}
+
+/// FNV-1a; stable across builds, unlike `DefaultHasher`, so shadow repos keep their names.
+fn path_key(path: &Path) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in path.to_string_lossy().bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect::<String>();
+    format!("{}-{:016x}", name, hash)
+}
+
+impl ShadowRepo {
+    fn open(work_tree: &Path) -> Result<ShadowRepo, String> {
+        let work_tree = work_tree
+            .canonicalize()
+            .map_err(|e| format!("Invalid working directory {}: {}", work_tree.display(), e))?;
+        if !work_tree.is_dir() {
+            return Err(format!("{} is not a directory", work_tree.display()));
+        }
+        let git_dir = storage::get_app_data_dir()?
+            .join(SHADOW_DIR)
+            .join(path_key(&work_tree));
+
+        let repo = ShadowRepo { git_dir, work_tree };
+        if !repo.git_dir.join("HEAD").exists() {
+            fs::create_dir_all(&repo.git_dir)
+                .map_err(|e| format!("Failed to create {}: {}", repo.git_dir.display(), e))?;
+            // Initialised without the work-tree environment, which `git init --bare` rejects.
+            let status = Command::new("git")
+                .args(["init", "--quiet", "--bare"])
+                .arg(&repo.git_dir)
+                .status()
+                .map_err(|e| format!("Failed to run git: {}", e))?;
+            if !status.success() {
+                return Err(format!("git init failed for {}", repo.git_dir.display()));
+            }
+            // Snapshots are only reachable from our own metadata, so never let git prune them.
+            repo.git(&["config", "gc.auto", "0"])?;
+            repo.git(&["config", "core.autocrlf", "false"])?;
+        }
+        Ok(repo)
+    }
+
+    fn git(&self, args: &[&str]) -> Result<String, String> {
+        let output = Command::new("git")
+            .args(args)
+            .env("GIT_DIR", &self.git_dir)
+            .env("GIT_WORK_TREE", &self.work_tree)
+            .env("GIT_INDEX_FILE", self.git_dir.join("index"))
+            .env("GIT_AUTHOR_NAME", "Codexia")
+            .env("GIT_AUTHOR_EMAIL", "checkpoints@codexia.local")
+            .env("GIT_COMMITTER_NAME", "Codexia")
+            .env("GIT_COMMITTER_EMAIL", "checkpoints@codexia.local")
+            .current_dir(&self.work_tree)
+            .output()
+            .map_err(|e| format!("Failed to run git: {}", e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+
+    /// Records the current state of the working tree (respecting `.gitignore`) and returns the
+    /// commit id.
+    fn snapshot(&self, message: &str) -> Result<String, String> {
+        let tree = self.write_tree()?;
+        let commit = self.git(&["commit-tree", &tree, "-m", message])?;
+        Ok(commit.trim().to_string())
+    }
+
+    /// Stages the working tree into the shadow index and writes it as a tree object.
+    fn write_tree(&self) -> Result<String, String> {
+        self.git(&["add", "--all", "."])?;
+        Ok(self.git(&["write-tree"])?.trim().to_string())
+    }
+
+    fn changes(
+        &self,
+        from: &str,
+        to: &str,
+        paths: &[String],
+    ) -> Result<Vec<CheckpointFileChange>, String> {
+        let mut args = vec![
+            "diff",
+            "--name-status",
+            "--no-renames",
+            "-z",
+            from,
+            to,
+            "--",
+        ];
+        args.extend(paths.iter().map(String::as_str));
+        let output = self.git(&args)?;
+
+        let mut fields = output.split('\0').filter(|f| !f.is_empty());
+        let mut changes = Vec::new();
+        while let (Some(status), Some(path)) = (fields.next(), fields.next()) {
+            changes.push(CheckpointFileChange {
+                path: path.to_string(),
+                status: status.to_string(),
+            });
+        }
+        Ok(changes)
+    }
+}
+
+/// Why a working tree is not snapshotted before turns, if it isn't: snapshots copy the whole
+/// tree into the shadow repository, so trees over the file or size budget are skipped. The
+/// walk doesn't know about `.gitignore`, so ignored files count towards the budget too.
+fn skip_reason(work_tree: &Path) -> Option<String> {
+    let mut files = 0;
+    let mut bytes = 0;
+    let entries = WalkDir::new(work_tree)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || entry.file_name() != ".git")
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.file_type().is_dir());
+    for entry in entries {
+        files += 1;
+        if files > MAX_SNAPSHOT_FILES {
+            return Some(format!("more than {} files", MAX_SNAPSHOT_FILES));
+        }
+        bytes += entry.metadata().map_or(0, |metadata| metadata.len());
+        if bytes > MAX_SNAPSHOT_BYTES {
+            return Some(format!(
+                "more than {} MiB",
+                MAX_SNAPSHOT_BYTES / (1024 * 1024)
+            ));
+        }
+    }
+    None
+}
+
+/// Snapshots the working tree into its shadow repository; no store lock is needed for that.
+fn take_snapshot(
+    thread_id: &str,
+    cwd: String,
+    reason: CheckpointReason,
+) -> Result<Checkpoint, String> {
+    let repo = ShadowRepo::open(Path::new(&cwd))?;
+    let id = repo.snapshot(&format!("{:?} checkpoint for thread {}", reason, thread_id))?;
+    Ok(Checkpoint {
+        id,
+        thread_id: thread_id.to_string(),
+        turn_id: None,
+        cwd,
+        reason,
+        created_at: Utc::now(),
+    })
+}
+
+/// Checkpoint taken before a turn. The store is only locked to look up the directory and to
+/// record the result, not while git copies the tree. Returns `None` when the directory is
+/// skipped, see `skip_reason`.
+pub fn snapshot_before_turn(
+    store: &Mutex<CheckpointStore>,
+    thread_id: &str,
+    cwd: Option<&str>,
+) -> Result<Option<Checkpoint>, String> {
+    let cwd = store
+        .lock()
+        .map_err(|e| format!("Failed to acquire lock: {}", e))?
+        .resolve_cwd(thread_id, cwd)?;
+    if let Some(reason) = skip_reason(Path::new(&cwd)) {
+        log::info!("Not snapshotting {} before the turn: {}", cwd, reason);
+        return Ok(None);
+    }
+    let checkpoint = take_snapshot(thread_id, cwd, CheckpointReason::BeforeTurn)?;
+    store
+        .lock()
+        .map_err(|e| format!("Failed to acquire lock: {}", e))?
+        .record(checkpoint.clone())?;
+    Ok(Some(checkpoint))
+}
+
+impl CheckpointStore {
+    fn store_path() -> Result<PathBuf, String> {
+        storage::get_app_data_path(CHECKPOINTS_FILE)
+    }
+
+    pub fn ensure_loaded(&mut self) {
+        if self.loaded {
+            return;
+        }
+        *self = Self::store_path()
+            .and_then(|path| storage::load_json::<CheckpointStore>(&path))
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to load checkpoints: {}", e);
+                CheckpointStore::default()
+            });
+        self.loaded = true;
+    }
+
+    fn save(&self) -> Result<(), String> {
+        storage::save_json(&Self::store_path()?, self)
+    }
+
+    pub fn set_thread_cwd(&mut self, thread_id: &str, cwd: &str) {
+        self.ensure_loaded();
+        if self.thread_cwds.get(thread_id).map(String::as_str) != Some(cwd) {
+            self.thread_cwds
+                .insert(thread_id.to_string(), cwd.to_string());
+            if let Err(e) = self.save() {
+                log::warn!("Failed to save checkpoints: {}", e);
+            }
+        }
+    }
+
+    /// The thread's working directory: the one it was started with here, or the one recorded
+    /// in its rollout.
+    pub fn thread_cwd(&mut self, thread_id: &str) -> Option<String> {
+        self.ensure_loaded();
+        if let Some(cwd) = self.thread_cwds.get(thread_id) {
+            return Some(cwd.clone());
+        }
+        let cwd = sessions::find_rollout_file(thread_id)
+            .ok()
+            .flatten()
+            .and_then(|path| sessions::summarize_rollout(&path).ok())
+            .and_then(|summary| summary.cwd)?;
+        self.thread_cwds.insert(thread_id.to_string(), cwd.clone());
+        Some(cwd)
+    }
+
+    fn resolve_cwd(&mut self, thread_id: &str, cwd: Option<&str>) -> Result<String, String> {
+        match cwd {
+            Some(cwd) => Ok(cwd.to_string()),
+            None => self
+                .thread_cwd(thread_id)
+                .ok_or_else(|| format!("No working directory known for thread {}", thread_id)),
+        }
+    }
+
+    fn record(&mut self, checkpoint: Checkpoint) -> Result<(), String> {
+        self.ensure_loaded();
+        self.threads
+            .entry(checkpoint.thread_id.clone())
+            .or_default()
+            .push(checkpoint);
+        self.save()
+    }
+
+    pub fn set_turn_id(&mut self, thread_id: &str, checkpoint_id: &str, turn_id: String) {
+        self.ensure_loaded();
+        if let Some(checkpoint) = self
+            .threads
+            .get_mut(thread_id)
+            .and_then(|list| list.iter_mut().rev().find(|c| c.id == checkpoint_id))
+        {
+            checkpoint.turn_id = Some(turn_id);
+            if let Err(e) = self.save() {
+                log::warn!("Failed to save checkpoints: {}", e);
+            }
+        }
+    }
+
+    pub fn list(&mut self, thread_id: &str) -> Vec<Checkpoint> {
+        self.ensure_loaded();
+        self.threads.get(thread_id).cloned().unwrap_or_default()
+    }
+
+    fn find(&mut self, thread_id: &str, checkpoint_id: &str) -> Result<Checkpoint, String> {
+        self.list(thread_id)
+            .into_iter()
+            .find(|c| c.id == checkpoint_id)
+            .ok_or_else(|| {
+                format!(
+                    "Checkpoint {} not found for thread {}",
+                    checkpoint_id, thread_id
+                )
+            })
+    }
+}
+
+fn find_checkpoint(
+    store: &Mutex<CheckpointStore>,
+    thread_id: &str,
+    checkpoint_id: &str,
+) -> Result<Checkpoint, String> {
+    store
+        .lock()
+        .map_err(|e| format!("Failed to acquire lock: {}", e))?
+        .find(thread_id, checkpoint_id)
+}
+
+/// Diff from a checkpoint to the current working tree. Git runs without the store locked.
+pub fn diff(
+    store: &Mutex<CheckpointStore>,
+    thread_id: &str,
+    checkpoint_id: &str,
+    paths: &[String],
+) -> Result<CheckpointDiff, String> {
+    let checkpoint = find_checkpoint(store, thread_id, checkpoint_id)?;
+    let repo = ShadowRepo::open(Path::new(&checkpoint.cwd))?;
+
+    // Untracked files are staged too, so they show up as additions.
+    let current = repo.write_tree()?;
+
+    let files = repo.changes(&checkpoint.id, &current, paths)?;
+    let mut args = vec![
+        "diff",
+        "--no-renames",
+        "--no-color",
+        &checkpoint.id,
+        &current,
+        "--",
+    ];
+    args.extend(paths.iter().map(String::as_str));
+    let patch = repo.git(&args)?;
+
+    Ok(CheckpointDiff {
+        checkpoint,
+        files,
+        patch,
+    })
+}
+
+/// Brings the working tree (or just `paths`) back to the checkpoint: modified and deleted
+/// files are rewritten, files created since the checkpoint are removed. A file that fails
+/// is reported in `failed` and the rest are still restored; the backup checkpoint holds
+/// the tree as it was before. Like `diff`, the store is only locked around lookups.
+pub fn restore(
+    store: &Mutex<CheckpointStore>,
+    thread_id: &str,
+    checkpoint_id: &str,
+    paths: &[String],
+) -> Result<RestoreResult, String> {
+    let checkpoint = find_checkpoint(store, thread_id, checkpoint_id)?;
+    let backup = take_snapshot(
+        thread_id,
+        checkpoint.cwd.clone(),
+        CheckpointReason::BeforeRestore,
+    )?;
+    store
+        .lock()
+        .map_err(|e| format!("Failed to acquire lock: {}", e))?
+        .record(backup.clone())?;
+    let repo = ShadowRepo::open(Path::new(&checkpoint.cwd))?;
+
+    let changes = repo.changes(&checkpoint.id, &backup.id, paths)?;
+    let mut restored = Vec::new();
+    let mut removed = Vec::new();
+    let mut failed = Vec::new();
+    for change in &changes {
+        if change.status.starts_with('A') {
+            let path = repo.work_tree.join(&change.path);
+            match fs::remove_file(&path) {
+                Ok(()) => removed.push(change.path.clone()),
+                Err(e) => failed.push(format!("{}: {}", change.path, e)),
+            }
+        } else {
+            restored.push(change.path.clone());
+        }
+    }
+
+    if !restored.is_empty() {
+        repo.git(&["read-tree", &checkpoint.id])?;
+        let mut args = vec!["checkout-index", "--force", "--"];
+        args.extend(restored.iter().map(String::as_str));
+        if repo.git(&args).is_err() {
+            // Check the files out one by one to tell which of them failed.
+            restored.retain(
+                |path| match repo.git(&["checkout-index", "--force", "--", path]) {
+                    Ok(_) => true,
+                    Err(e) => {
+                        failed.push(format!("{}: {}", path, e));
+                        false
+                    }
+                },
+            );
+        }
+    }
+
+    log::info!(
+        "Restored checkpoint {} in {}: {} files restored, {} removed, {} failed",
+        checkpoint.id,
+        checkpoint.cwd,
+        restored.len(),
+        removed.len(),
+        failed.len()
+    );
+    Ok(RestoreResult {
+        restored,
+        removed,
+        failed,
+        backup,
+    })
+}
+
+#[tauri::command]
+pub async fn list_checkpoints(
+    thread_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<Checkpoint>, CodexError> {
+    let mut store = state
+        .checkpoints
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(store.list(&thread_id))
+}
+
+#[tauri::command]
+pub async fn diff_checkpoint(
+    thread_id: String,
+    checkpoint_id: String,
+    paths: Option<Vec<String>>,
+    app: tauri::AppHandle,
+) -> Result<CheckpointDiff, CodexError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        diff(
+            &state.checkpoints,
+            &thread_id,
+            &checkpoint_id,
+            &paths.unwrap_or_default(),
+        )
+        .map_err(CodexError::io)
+    })
+    .await
+    .map_err(|e| CodexError::internal(format!("Failed to diff checkpoint: {}", e)))?
+}
+
+#[tauri::command]
+pub async fn restore_checkpoint(
+    thread_id: String,
+    checkpoint_id: String,
+    paths: Option<Vec<String>>,
+    app: tauri::AppHandle,
+) -> Result<RestoreResult, CodexError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<AppState>();
+        restore(
+            &state.checkpoints,
+            &thread_id,
+            &checkpoint_id,
+            &paths.unwrap_or_default(),
+        )
+        .map_err(CodexError::io)
+    })
+    .await
+    .map_err(|e| CodexError::internal(format!("Failed to restore checkpoint: {}", e)))?
+}
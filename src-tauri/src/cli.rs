@@ -0,0 +1,560 @@
+//! Terminal mode of the binary: `codexia-zen run [options] <prompt>` drives one turn through
+//! the same `CodexClientHandle` the GUI uses and streams what happens to stdout.
+
+use anyhow::{Context, Result, bail};
+use codex_app_server_protocol::{
+    ApprovalDecision, RequestId, ThreadResumeParams, ThreadStartParams, TurnStartParams,
+};
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::io::{BufRead, IsTerminal, Read, Write};
+use std::sync::mpsc::{Receiver, channel};
+
+use crate::codex::events::{ChannelEventSink, ClientEvent};
+use crate::codex::handles::CodexClientHandle;
+use crate::codex::types::{ApprovalRequest, ApprovalRequestKind};
+use crate::projects;
+use crate::prompts::{self, PromptContext};
+
+pub const EXIT_COMPLETED: i32 = 0;
+pub const EXIT_FAILED: i32 = 1;
+pub const EXIT_USAGE: i32 = 2;
+pub const EXIT_INTERRUPTED: i32 = 3;
+pub const EXIT_STARTUP: i32 = 4;
+
+const USAGE: &str = "\
+Usage: codexia-zen run [OPTIONS] <PROMPT>...
+
+Runs one turn headlessly and streams its progress. Use `-` as the prompt to read it from stdin.
+
+Options:
+  -C, --cwd <DIR>              Working directory of a new thread (default: current directory)
+  -r, --resume <THREAD_ID>     Send the prompt to an existing thread
+  -m, --model <MODEL>          Model for a new thread
+      --sandbox <MODE>         read-only | workspace-write | danger-full-access
+      --approval-policy <P>    untrusted | on-failure | on-request | never
+      --json                   Print every event as a JSON line instead of text
+      --approve <MODE>         ask | accept | decline (default: ask on a terminal, else decline)
+      --allow-command <PREFIX> Accept single commands starting with the words of PREFIX;
+                               never chained, piped or substituted ones (repeatable)
+      --allow-file-changes     Accept all file changes
+  -h, --help                   Show this help
+
+Exit status: 0 turn completed, 1 turn failed, 2 usage error, 3 turn interrupted,
+4 app-server could not be started or exited early.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ApproveMode {
+    Ask,
+    Accept,
+    Decline,
+}
+
+#[derive(Debug)]
+struct CliOptions {
+    cwd: Option<String>,
+    resume: Option<String>,
+    model: Option<String>,
+    sandbox: Option<String>,
+    approval_policy: Option<String>,
+    json: bool,
+    approve: Option<ApproveMode>,
+    allow_commands: Vec<String>,
+    allow_file_changes: bool,
+    prompt: String,
+}
+
+/// Whether the arguments ask for terminal mode rather than the GUI.
+pub fn is_cli_invocation(args: &[String]) -> bool {
+    args.first().is_some_and(|arg| arg == "run")
+}
+
+/// Runs terminal mode and returns the process exit status.
+pub fn run(args: Vec<String>) -> i32 {
+    let options = match parse_args(&args[1..]) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return EXIT_COMPLETED;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
+    let (tx, rx) = channel();
+    let handle = match CodexClientHandle::spawn_with_sink(Box::new(ChannelEventSink::new(tx))) {
+        Ok(handle) => handle,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            return EXIT_STARTUP;
+        }
+    };
+
+    match run_turn(&options, &handle, &rx) {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            EXIT_STARTUP
+        }
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Option<CliOptions>> {
+    let mut options = CliOptions {
+        cwd: None,
+        resume: None,
+        model: None,
+        sandbox: None,
+        approval_policy: None,
+        json: false,
+        approve: None,
+        allow_commands: Vec::new(),
+        allow_file_changes: false,
+        prompt: String::new(),
+    };
+    let mut prompt = Vec::new();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .with_context(|| format!("{} needs a value", name))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-C" | "--cwd" => options.cwd = Some(value(arg)?),
+            "-r" | "--resume" => options.resume = Some(value(arg)?),
+            "-m" | "--model" => options.model = Some(value(arg)?),
+            "--sandbox" => options.sandbox = Some(value(arg)?),
+            "--approval-policy" => options.approval_policy = Some(value(arg)?),
+            "--json" => options.json = true,
+            "--approve" => {
+                options.approve = Some(match value(arg)?.as_str() {
+                    "ask" => ApproveMode::Ask,
+                    "accept" => ApproveMode::Accept,
+                    "decline" => ApproveMode::Decline,
+                    other => bail!("unknown --approve mode '{}'", other),
+                })
+            }
+            "--allow-command" => options.allow_commands.push(value(arg)?),
+            "--allow-file-changes" => options.allow_file_changes = true,
+            "--" => prompt.extend(args.by_ref().cloned()),
+            "-" => prompt.push(arg.clone()),
+            flag if flag.starts_with('-') => bail!("unknown option '{}'", flag),
+            _ => prompt.push(arg.clone()),
+        }
+    }
+
+    options.prompt = read_prompt(&prompt, std::io::stdin())?;
+    Ok(Some(options))
+}
+
+/// Joins the prompt words, or reads the prompt from `stdin` when the only word is `-`.
+fn read_prompt(words: &[String], mut stdin: impl Read) -> Result<String> {
+    let prompt = if words.len() == 1 && words[0] == "-" {
+        let mut text = String::new();
+        stdin
+            .read_to_string(&mut text)
+            .context("failed to read the prompt from stdin")?;
+        text
+    } else {
+        words.join(" ")
+    };
+    if prompt.trim().is_empty() {
+        bail!("a prompt is required");
+    }
+    Ok(prompt)
+}
+
+fn run_turn(
+    options: &CliOptions,
+    handle: &CodexClientHandle,
+    rx: &Receiver<ClientEvent>,
+) -> Result<i32> {
+    let (thread_id, cwd) = match &options.resume {
+        Some(thread_id) => {
+            let params: ThreadResumeParams =
+                serde_json::from_value(json!({ "threadId": thread_id }))?;
+            let response = serde_json::to_value(
+                handle
+                    .thread_resume(params)
+                    .context("failed to resume thread")?,
+            )?;
+            let cwd = response
+                .pointer("/thread/cwd")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            (thread_id.clone(), cwd)
+        }
+        None => {
+            let cwd = match &options.cwd {
+                Some(cwd) => projects::validate_directory(cwd).map_err(anyhow::Error::msg)?,
+                None => std::env::current_dir().context("failed to read the current directory")?,
+            };
+            let params: ThreadStartParams = serde_json::from_value(json!({
+                "cwd": cwd.to_string_lossy(),
+                "model": options.model,
+                "sandbox": options.sandbox,
+                "approvalPolicy": options.approval_policy,
+            }))
+            .context("invalid thread options")?;
+            let response = serde_json::to_value(
+                handle
+                    .thread_start(params)
+                    .context("failed to start thread")?,
+            )?;
+            let thread_id = response
+                .pointer("/thread/id")
+                .and_then(Value::as_str)
+                .context("thread/start response has no thread id")?
+                .to_string();
+            (thread_id, Some(cwd.to_string_lossy().to_string()))
+        }
+    };
+    if !options.json {
+        eprintln!("thread {}", thread_id);
+    }
+
+    // `/name args` runs a prompt template, like in the GUI, in the thread's directory.
+    let context = PromptContext {
+        cwd,
+        ..Default::default()
+    };
+    let prompt = match prompts::expand_command(&options.prompt, &context)
+        .map_err(anyhow::Error::msg)?
+    {
+        Some(expanded) => expanded.text,
+        None => options.prompt.clone(),
+    };
+    let params: TurnStartParams = serde_json::from_value(json!({
+        "threadId": thread_id,
+        "input": [{ "type": "text", "text": prompt }],
+    }))?;
+    let response =
+        serde_json::to_value(handle.turn_start(params).context("failed to start turn")?)?;
+    let turn_id = response
+        .pointer("/turn/id")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    // Reading answers from stdin only works if the prompt didn't come from there.
+    let interactive = std::io::stdin().is_terminal() && !options.json;
+    let approve = options.approve.unwrap_or(if interactive {
+        ApproveMode::Ask
+    } else {
+        ApproveMode::Decline
+    });
+    let mut printer = TextPrinter::default();
+
+    loop {
+        let Ok(event) = rx.recv() else {
+            eprintln!("error: codex app-server exited before the turn finished");
+            return Ok(EXIT_STARTUP);
+        };
+        match event {
+            ClientEvent::Notification(notification) => {
+                if event_thread_id(&notification).is_some_and(|id| id != thread_id) {
+                    continue;
+                }
+                if options.json {
+                    print_json(&notification);
+                } else {
+                    printer.print(&notification);
+                }
+
+                let method = notification.get("method").and_then(Value::as_str);
+                let turn = notification.pointer("/params/turn");
+                let same_turn = turn_id.as_deref().is_none_or(|id| {
+                    turn.and_then(|turn| turn.get("id")).and_then(Value::as_str) == Some(id)
+                });
+                if method == Some("turn/completed") && same_turn {
+                    let status = turn
+                        .and_then(|turn| turn.get("status"))
+                        .and_then(Value::as_str)
+                        .unwrap_or("completed");
+                    return Ok(match status {
+                        "completed" => EXIT_COMPLETED,
+                        "interrupted" => EXIT_INTERRUPTED,
+                        _ => EXIT_FAILED,
+                    });
+                }
+            }
+            ClientEvent::ApprovalRequest(request) => {
+                if request.thread_id != thread_id {
+                    continue;
+                }
+                let (decision, label) = decide(&request, options, approve);
+                if options.json {
+                    print_json(&json!({ "method": "approval/request", "params": request }));
+                    print_json(&json!({
+                        "method": "approval/decision",
+                        "params": { "requestId": request.request_id, "decision": label },
+                    }));
+                } else if approve != ApproveMode::Ask {
+                    eprintln!("{} ({})", describe_approval(&request), label);
+                }
+                let is_command =
+                    matches!(request.kind, ApprovalRequestKind::CommandExecution { .. });
+                handle.respond_to_approval(
+                    RequestId::String(request.request_id.clone()),
+                    decision,
+                    is_command,
+                )?;
+            }
+        }
+    }
+}
+
+fn event_thread_id(notification: &Value) -> Option<&str> {
+    let params = notification.get("params")?;
+    params
+        .get("threadId")
+        .or_else(|| params.pointer("/thread/id"))
+        .and_then(Value::as_str)
+}
+
+fn print_json(value: &Value) {
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}", value);
+    let _ = stdout.flush();
+}
+
+fn describe_approval(request: &ApprovalRequest) -> String {
+    match &request.kind {
+        ApprovalRequestKind::CommandExecution { details, .. } => match details {
+            Some(details) => format!("run `{}` (risk: {:?})", details.command, details.risk.level),
+            None => format!("run a command (item {})", request.item_id),
+        },
+        ApprovalRequestKind::FileChange { files, .. } if !files.is_empty() => format!(
+            "change {}",
+            files
+                .iter()
+                .map(|f| f.path.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        ApprovalRequestKind::FileChange { .. } => {
+            format!("change files (item {})", request.item_id)
+        }
+    }
+}
+
+fn decide(
+    request: &ApprovalRequest,
+    options: &CliOptions,
+    approve: ApproveMode,
+) -> (ApprovalDecision, &'static str) {
+    let allowed = match &request.kind {
+        ApprovalRequestKind::CommandExecution { details, .. } => {
+            details.as_ref().is_some_and(|details| {
+                options
+                    .allow_commands
+                    .iter()
+                    .any(|prefix| details.starts_with_command(prefix))
+            })
+        }
+        ApprovalRequestKind::FileChange { .. } => options.allow_file_changes,
+    };
+    if allowed {
+        return (ApprovalDecision::Accept, "accept");
+    }
+
+    match approve {
+        ApproveMode::Accept => (ApprovalDecision::Accept, "accept"),
+        ApproveMode::Decline => (ApprovalDecision::Decline, "decline"),
+        ApproveMode::Ask if options.json => (ApprovalDecision::Decline, "decline"),
+        ApproveMode::Ask => ask(request),
+    }
+}
+
+fn ask(request: &ApprovalRequest) -> (ApprovalDecision, &'static str) {
+    eprintln!();
+    eprintln!("Approval needed: {}", describe_approval(request));
+    if let Some(reason) = &request.reason {
+        eprintln!("  reason: {}", reason);
+    }
+    match &request.kind {
+        ApprovalRequestKind::CommandExecution {
+            details: Some(details),
+            ..
+        } => {
+            if let Some(cwd) = &details.cwd {
+                eprintln!("  cwd: {}", cwd);
+            }
+            for reason in &details.risk.reasons {
+                eprintln!("  ! {}", reason);
+            }
+        }
+        ApprovalRequestKind::FileChange { files, .. } => {
+            for file in files {
+                if let Some(diff) = &file.diff {
+                    eprintln!("{}", diff);
+                }
+            }
+        }
+        _ => {}
+    }
+
+    let stdin = std::io::stdin();
+    loop {
+        eprint!("Approve? [y]es / [a]lways this session / [n]o / [c]ancel turn: ");
+        let _ = std::io::stderr().flush();
+        let mut answer = String::new();
+        if stdin.lock().read_line(&mut answer).unwrap_or(0) == 0 {
+            return (ApprovalDecision::Decline, "decline");
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => return (ApprovalDecision::Accept, "accept"),
+            "a" | "always" => return (ApprovalDecision::AcceptForSession, "acceptForSession"),
+            "n" | "no" => return (ApprovalDecision::Decline, "decline"),
+            "c" | "cancel" => return (ApprovalDecision::Cancel, "cancel"),
+            _ => continue,
+        }
+    }
+}
+
+/// Renders notifications as plain text: streamed agent messages on stdout, progress on stderr.
+#[derive(Default)]
+struct TextPrinter {
+    streamed_items: HashSet<String>,
+}
+
+impl TextPrinter {
+    fn print(&mut self, notification: &Value) {
+        let method = notification
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let Some(params) = notification.get("params") else {
+            return;
+        };
+        let item = params.get("item");
+        let item_type = item
+            .and_then(|item| item.get("type"))
+            .and_then(Value::as_str);
+        let string = |value: Option<&Value>, key: &str| {
+            value
+                .and_then(|value| value.get(key))
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        match (method, item_type) {
+            ("item/agentMessage/delta", _) => {
+                self.streamed_items.insert(string(Some(params), "itemId"));
+                print!("{}", string(Some(params), "delta"));
+                let _ = std::io::stdout().flush();
+            }
+            ("item/completed", Some("agentMessage")) => {
+                if self.streamed_items.remove(&string(item, "id")) {
+                    println!();
+                } else {
+                    println!("{}", string(item, "text"));
+                }
+            }
+            ("item/started", Some("commandExecution")) => {
+                let command = match item.and_then(|item| item.get("command")) {
+                    Some(Value::Array(argv)) => argv
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    Some(Value::String(command)) => command.clone(),
+                    _ => String::new(),
+                };
+                eprintln!("$ {}", command);
+            }
+            ("item/completed", Some("commandExecution")) => {
+                if let Some(code) = item
+                    .and_then(|item| item.get("exitCode"))
+                    .and_then(Value::as_i64)
+                    .filter(|code| *code != 0)
+                {
+                    eprintln!("  exited with {}", code);
+                }
+            }
+            ("item/completed", Some("fileChange")) => {
+                for change in item
+                    .and_then(|item| item.get("changes"))
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                {
+                    eprintln!("  edited {}", string(Some(change), "path"));
+                }
+            }
+            ("error", _) => {
+                let message = params
+                    .pointer("/error/message")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error");
+                eprintln!("error: {}", message);
+            }
+            ("turn/completed", _) => {
+                let status = params
+                    .pointer("/turn/status")
+                    .and_then(Value::as_str)
+                    .unwrap_or("completed");
+                eprintln!("turn {}", status);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn parse(list: &[&str]) -> CliOptions {
+        parse_args(&args(list)).unwrap().unwrap()
+    }
+
+    #[test]
+    fn parses_options_and_prompt_words() {
+        let options = parse(&["-m", "gpt-5", "--json", "fix", "the", "tests"]);
+        assert_eq!(options.model.as_deref(), Some("gpt-5"));
+        assert!(options.json);
+        assert_eq!(options.prompt, "fix the tests");
+    }
+
+    #[test]
+    fn double_dash_ends_the_options() {
+        let options = parse(&["--json", "--", "--model", "-x", "-"]);
+        assert!(options.json);
+        assert!(options.model.is_none());
+        assert_eq!(options.prompt, "--model -x -");
+    }
+
+    #[test]
+    fn lone_dash_reads_the_prompt_from_stdin() {
+        let prompt = read_prompt(&args(&["-"]), "from stdin\n".as_bytes()).unwrap();
+        assert_eq!(prompt, "from stdin\n");
+        assert!(read_prompt(&args(&["-"]), "  \n".as_bytes()).is_err());
+        // Only a lone dash means stdin.
+        assert_eq!(parse(&["-", "literal"]).prompt, "- literal");
+    }
+
+    #[test]
+    fn rejects_unknown_flags_and_missing_values() {
+        let error = parse_args(&args(&["--frobnicate", "hi"])).unwrap_err();
+        assert_eq!(error.to_string(), "unknown option '--frobnicate'");
+        assert!(parse_args(&args(&["-x"])).is_err());
+        assert!(parse_args(&args(&["hi", "--model"])).is_err());
+        assert!(parse_args(&args(&["--approve", "maybe", "hi"])).is_err());
+        assert!(parse_args(&args(&[])).is_err());
+    }
+
+    #[test]
+    fn help_wins_over_everything_before_it() {
+        assert!(parse_args(&args(&["hi", "--help"])).unwrap().is_none());
+    }
+}
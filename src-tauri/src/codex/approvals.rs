@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::codex::types::ApprovalRequest;
+use crate::commands::ApprovalDecisionType;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalRecord {
+    pub request: ApprovalRequest,
+    pub decision: ApprovalDecisionType,
+    pub requested_at: DateTime<Utc>,
+    pub decided_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct PendingApproval {
+    request: ApprovalRequest,
+    requested_at: DateTime<Utc>,
+}
+
+/// Approval requests the app-server is waiting on, plus the decisions made this session.
+#[derive(Debug, Default)]
+pub struct ApprovalRegistry {
+    pending: Vec<PendingApproval>,
+    history: Vec<ApprovalRecord>,
+}
+
+impl ApprovalRegistry {
+    pub fn add_pending(&mut self, request: ApprovalRequest) {
+        self.pending.push(PendingApproval {
+            request,
+            requested_at: Utc::now(),
+        });
+    }
+
+    /// Forgets the pending requests, whose app-server is gone.
+    pub fn clear_pending(&mut self) {
+        self.pending.clear();
+    }
+
+    /// Pending requests, oldest first.
+    pub fn pending(&self) -> Vec<ApprovalRequest> {
+        self.pending.iter().map(|p| p.request.clone()).collect()
+    }
+
+    pub fn resolve(
+        &mut self,
+        request_id: &str,
+        decision: ApprovalDecisionType,
+    ) -> Option<ApprovalRecord> {
+        let position = self
+            .pending
+            .iter()
+            .position(|p| p.request.request_id == request_id)?;
+        let pending = self.pending.remove(position);
+
+        let record = ApprovalRecord {
+            request: pending.request,
+            decision,
+            requested_at: pending.requested_at,
+            decided_at: Utc::now(),
+        };
+        self.history.push(record.clone());
+        Some(record)
+    }
+
+    /// Decided approvals for a thread, in the order they were answered.
+    pub fn history_for_thread(&self, thread_id: &str) -> Vec<ApprovalRecord> {
+        self.history
+            .iter()
+            .filter(|record| record.request.thread_id == thread_id)
+            .cloned()
+            .collect()
+    }
+}
@@ -1,47 +1,73 @@
 use anyhow::{Context, Result, bail};
 use codex_app_server_protocol::{
     ApprovalDecision, ClientInfo, ClientRequest, CommandExecutionRequestApprovalResponse,
-    FileChangeRequestApprovalResponse, InitializeParams, InitializeResponse, JSONRPCMessage,
-    JSONRPCRequest, JSONRPCResponse, RequestId, ServerNotification,
-    ServerRequest,
+    FileChangeRequestApprovalResponse, InitializeParams, JSONRPCMessage,
+    JSONRPCError, JSONRPCErrorError, JSONRPCNotification, JSONRPCRequest, JSONRPCResponse,
+    RequestId, ServerNotification, ServerRequest,
 };
-use serde_json::Value;
+use serde_json::{Value, json};
 use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufReader, Write};
 use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
 use std::sync::mpsc::{channel, Sender, Receiver};
-use tauri::Emitter;
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
+use crate::codex::command_analysis::CommandTracker;
+use crate::codex::error::ClientError;
+use crate::codex::events::ClientEventSink;
+use crate::codex::file_changes::FileChangeTracker;
 use crate::codex::handles::{ClientCommand, CodexClientHandle};
+use crate::codex::pidfile;
+use crate::codex::protocol::{
+    self, CompatibilityStatus, Line, ProtocolErrorKind, SharedProtocolStatus,
+};
 use crate::codex::types::ApprovalRequest;
 use crate::codex_discovery;
 
 enum EventLoopMessage {
     Command(ClientCommand),
     JsonRpcMessage(JSONRPCMessage),
+    /// A response line that had to be skipped; its request fails instead of waiting forever.
+    DroppedResponse {
+        id: String,
+        reason: String,
+    },
+    /// The app-server closed its stdout, normally because it exited.
+    ServerClosed,
 }
 
+/// JSON-RPC "method not found", returned for server requests this client can't handle.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// How long the app-server gets to exit on its own once its stdin is closed.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+
 pub(crate) struct CodexClient {
     child: Child,
-    stdin: ChildStdin,
-    app_handle: tauri::AppHandle,
+    /// `None` once closed for shutdown.
+    stdin: Option<ChildStdin>,
+    sink: Box<dyn ClientEventSink>,
     pending_responses: HashMap<String, Sender<Result<Value>>>,
     event_rx: Receiver<EventLoopMessage>,
+    file_changes: FileChangeTracker,
+    commands: CommandTracker,
+    protocol: SharedProtocolStatus,
 }
 
 impl CodexClient {
-    pub fn spawn_and_initialize(app_handle: tauri::AppHandle) -> Result<CodexClientHandle> {
+    pub fn spawn_and_initialize(sink: Box<dyn ClientEventSink>) -> Result<CodexClientHandle> {
         log::info!("CodexClient::spawn_and_initialize starting");
         let codex_bin = codex_discovery::discover_codex_command()
-            .ok_or_else(|| anyhow::anyhow!("Unable to locate codex binary. Install Codex CLI"))?;
+            .ok_or(ClientError::BinaryNotFound)?;
         let mut codex_app_server = Command::new(codex_bin)
             .arg("app-server")
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::inherit())
             .spawn()
-            .with_context(|| format!("failed to start codex app-server"))?;
+            .context("failed to start codex app-server")?;
+        pidfile::record(codex_app_server.id());
 
         let stdin = codex_app_server
             .stdin
@@ -57,16 +83,17 @@ impl CodexClient {
         let (event_tx, event_rx) = channel();
 
         // Spawn reader thread for stdout
+        let protocol = protocol::new_status();
         let event_tx_clone = event_tx.clone();
+        let reader_protocol = protocol.clone();
         let mut stdout_reader = BufReader::new(stdout);
         std::thread::spawn(move || {
             log::info!("Reader thread started");
             loop {
                 log::debug!("Reader thread waiting for message from codex server...");
-                match Self::read_jsonrpc_message_static(&mut stdout_reader) {
+                match Self::read_jsonrpc_message_static(&mut stdout_reader, &reader_protocol) {
                     Ok(message) => {
-                        log::info!("Reader thread received message: {:?}", message);
-                        if event_tx_clone.send(EventLoopMessage::JsonRpcMessage(message)).is_err() {
+                        if event_tx_clone.send(message).is_err() {
                             log::info!("Event loop closed, reader thread exiting");
                             break;
                         }
@@ -77,6 +104,10 @@ impl CodexClient {
                     }
                 }
             }
+            if let Ok(mut status) = reader_protocol.lock() {
+                status.connected = false;
+            }
+            let _ = event_tx_clone.send(EventLoopMessage::ServerClosed);
             log::info!("Reader thread exiting");
         });
 
@@ -91,10 +122,13 @@ impl CodexClient {
 
         let mut client = Self {
             child: codex_app_server,
-            stdin,
-            app_handle,
+            stdin: Some(stdin),
+            sink,
             pending_responses: HashMap::new(),
             event_rx,
+            file_changes: FileChangeTracker::default(),
+            commands: CommandTracker::default(),
+            protocol: protocol.clone(),
         };
 
         // Initialize the client synchronously
@@ -113,10 +147,10 @@ impl CodexClient {
         });
 
         log::info!("Returning CodexClientHandle");
-        Ok(CodexClientHandle::new(command_tx))
+        Ok(CodexClientHandle::new(command_tx, protocol))
     }
 
-    fn initialize(&mut self) -> Result<InitializeResponse> {
+    fn initialize(&mut self) -> Result<()> {
         let request_id = self.request_id();
         let request = ClientRequest::Initialize {
             request_id: request_id.clone(),
@@ -130,7 +164,28 @@ impl CodexClient {
         };
 
         self.write_request(&request)?;
-        self.wait_for_response_sync(&request_id)
+        // Read loosely: the user agent is all that is needed, and a server whose response
+        // doesn't match this build's type should still get a compatibility warning.
+        let response: Value = self.wait_for_response_sync(&request_id)?;
+        let user_agent = response
+            .get("userAgent")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let compatibility = protocol::check_compatibility(user_agent);
+        log::info!("codex app-server user agent: {}", user_agent);
+        if compatibility.status != CompatibilityStatus::Compatible {
+            log::warn!(
+                "codex app-server compatibility: {}",
+                compatibility.message.as_deref().unwrap_or_default()
+            );
+            if let Err(e) = self.sink.compatibility_warning(&compatibility) {
+                log::warn!("Failed to report compatibility warning: {}", e);
+            }
+        }
+        if let Ok(mut status) = self.protocol.lock() {
+            status.compatibility = Some(compatibility);
+        }
+        Ok(())
     }
 
     fn run_event_loop(&mut self) -> Result<()> {
@@ -140,16 +195,38 @@ impl CodexClient {
                 .context("Event channel closed")?;
 
             match event {
+                EventLoopMessage::Command(ClientCommand::Shutdown {
+                    interrupts,
+                    response_tx,
+                }) => {
+                    log::info!("Shutting down codex app-server");
+                    self.interrupt_turns(&interrupts);
+                    self.stop_server(SHUTDOWN_GRACE);
+                    let _ = response_tx.send(());
+                    return Ok(());
+                }
+                EventLoopMessage::ServerClosed => {
+                    log::warn!("codex app-server exited");
+                    // Dropping the handlers fails the requests still waiting for a response.
+                    self.pending_responses.clear();
+                    if let Err(e) = self.sink.server_closed() {
+                        log::warn!("Failed to report the app-server exit: {}", e);
+                    }
+                    return Ok(());
+                }
                 EventLoopMessage::Command(command) => {
                     log::info!("Event loop received command");
                     self.handle_command(command)?;
                 }
+                EventLoopMessage::DroppedResponse { id, reason } => {
+                    if let Some(tx) = self.pending_responses.remove(&id) {
+                        let _ = tx.send(Err(ClientError::UnreadableResponse { reason }.into()));
+                    }
+                }
                 EventLoopMessage::JsonRpcMessage(message) => {
                     match message {
                         JSONRPCMessage::Notification(notification) => {
-                            if let Ok(server_notification) = ServerNotification::try_from(notification.clone()) {
-                                self.emit_notification(&server_notification)?;
-                            }
+                            self.handle_notification(notification)?;
                         }
                         JSONRPCMessage::Request(request) => {
                             self.handle_server_request(request)?;
@@ -229,6 +306,46 @@ impl CodexClient {
             } => {
                 self.send_approval_response(request_id, decision, is_command_execution)?;
             }
+            ClientCommand::GetAccount { params, response_tx } => {
+                let request_id = self.request_id();
+                let request = ClientRequest::GetAccount {
+                    request_id: request_id.clone(),
+                    params,
+                };
+                self.write_request(&request)?;
+                self.register_response_handler(request_id, response_tx);
+            }
+            ClientCommand::LoginAccount { params, response_tx } => {
+                let request_id = self.request_id();
+                let request = ClientRequest::LoginAccount {
+                    request_id: request_id.clone(),
+                    params,
+                };
+                self.write_request(&request)?;
+                self.register_response_handler(request_id, response_tx);
+            }
+            ClientCommand::LogoutAccount { response_tx } => {
+                let request_id = self.request_id();
+                let request = ClientRequest::LogoutAccount {
+                    request_id: request_id.clone(),
+                    params: None,
+                };
+                self.write_request(&request)?;
+                self.register_response_handler(request_id, response_tx);
+            }
+            ClientCommand::ModelList { params, response_tx } => {
+                let request_id = self.request_id();
+                let request = ClientRequest::ModelList {
+                    request_id: request_id.clone(),
+                    params,
+                };
+                self.write_request(&request)?;
+                self.register_response_handler(request_id, response_tx);
+            }
+            // Handled by `run_event_loop`, which has to leave the loop afterwards.
+            ClientCommand::Shutdown { .. } => {
+                bail!("Shutdown reached the command handler");
+            }
         }
         log::info!("Command handled successfully");
         Ok(())
@@ -248,16 +365,51 @@ impl CodexClient {
         self.pending_responses.insert(id_str, value_tx);
 
         // Spawn a thread to deserialize and forward the response
+        let status = self.protocol.clone();
         std::thread::spawn(move || {
             if let Ok(result) = value_rx.recv() {
                 let typed_result = result.and_then(|value| {
-                    serde_json::from_value(value).context("Failed to deserialize response")
+                    serde_json::from_value::<T>(value.clone())
+                        .map_err(|e| Self::incompatible_response::<T>(&status, e, value).into())
                 });
                 let _ = response_tx.send(typed_result);
             }
         });
     }
 
+    /// Records a response that failed typed deserialization and turns it into an error that
+    /// keeps the raw JSON, so callers can still use what the app-server sent.
+    fn incompatible_response<T>(
+        status: &SharedProtocolStatus,
+        error: serde_json::Error,
+        raw: Value,
+    ) -> ClientError {
+        let response = std::any::type_name::<T>()
+            .rsplit("::")
+            .next()
+            .unwrap_or("response")
+            .to_string();
+        let server_version = status
+            .lock()
+            .ok()
+            .and_then(|status| status.compatibility.clone())
+            .and_then(|compatibility| compatibility.server_version);
+        let message = match server_version {
+            Some(version) => format!("{} (codex {} may be incompatible)", error, version),
+            None => error.to_string(),
+        };
+        protocol::record_error(
+            status,
+            ProtocolErrorKind::IncompatibleResponse,
+            format!("{}: {}", response, message),
+        );
+        ClientError::IncompatibleResponse {
+            response,
+            message,
+            raw,
+        }
+    }
+
     fn handle_response(&mut self, response: JSONRPCResponse) -> Result<()> {
         let id_str = match &response.id {
             RequestId::String(s) => s.clone(),
@@ -284,23 +436,47 @@ impl CodexClient {
         log::error!("Received error response for request_id: {}: {:?}", id_str, err);
 
         if let Some(tx) = self.pending_responses.remove(&id_str) {
-            let _ = tx.send(Err(anyhow::anyhow!("Request failed: {:?}", err)));
+            let _ = tx.send(Err(ClientError::Rpc {
+                code: err.error.code,
+                message: err.error.message,
+                data: err.error.data,
+            }
+            .into()));
         }
         Ok(())
     }
 
-    fn emit_notification(&self, notification: &ServerNotification) -> Result<()> {
-        let event_name = format!("codex:{}", notification.to_string());
-        log::info!("Emitting event: {} with payload: {:?}", event_name, notification);
-        self.app_handle
-            .emit("codex:notification", notification)
-            .context("failed to emit notification")?;
-        Ok(())
+    fn handle_notification(&mut self, notification: JSONRPCNotification) -> Result<()> {
+        match ServerNotification::try_from(notification.clone()) {
+            Ok(server_notification) => self.emit_notification(&server_notification),
+            Err(e) => {
+                // Newer app-servers send notifications this client doesn't know yet; they are
+                // passed on as they came instead of being lost.
+                protocol::record_error(
+                    &self.protocol,
+                    ProtocolErrorKind::UnknownNotification,
+                    format!("{}: {}", notification.method, e),
+                );
+                let value = json!({ "method": notification.method, "params": notification.params });
+                self.sink.raw_notification(&value)
+            }
+        }
+    }
+
+    fn emit_notification(&mut self, notification: &ServerNotification) -> Result<()> {
+        let value = serde_json::to_value(notification).context("failed to serialize notification")?;
+        self.file_changes.record_notification(&value);
+        self.commands.record_notification(&value);
+        self.sink.notification(&value)
     }
 
     fn handle_server_request(&mut self, request: JSONRPCRequest) -> Result<()> {
-        let server_request = ServerRequest::try_from(request)
-            .context("failed to deserialize ServerRequest")?;
+        let id = request.id.clone();
+        let method = request.method.clone();
+        let server_request = match ServerRequest::try_from(request) {
+            Ok(server_request) => server_request,
+            Err(e) => return self.reject_server_request(id, &method, e.to_string()),
+        };
 
         match server_request {
             ServerRequest::CommandExecutionRequestApproval { request_id, params } => {
@@ -309,6 +485,10 @@ impl CodexClient {
                     RequestId::Integer(i) => i.to_string(),
                 };
 
+                let details = serde_json::to_value(&params)
+                    .ok()
+                    .and_then(|value| self.commands.detail(&params.item_id, &value));
+
                 let approval_request = ApprovalRequest {
                     request_id: request_id_str,
                     thread_id: params.thread_id.clone(),
@@ -319,13 +499,11 @@ impl CodexClient {
                         proposed_execpolicy_amendment: params
                             .proposed_execpolicy_amendment
                             .map(|a| a.command),
+                        details,
                     },
                 };
 
-                // Emit approval request to frontend
-                self.app_handle
-                    .emit("codex://approval-request", &approval_request)
-                    .context("failed to emit approval request")?;
+                self.emit_approval_request(approval_request)?;
             }
             ServerRequest::FileChangeRequestApproval { request_id, params } => {
                 let request_id_str = match &request_id {
@@ -341,22 +519,42 @@ impl CodexClient {
                     reason: params.reason.clone(),
                     kind: crate::codex::types::ApprovalRequestKind::FileChange {
                         grant_root: params.grant_root.map(|p| p.display().to_string()),
+                        files: self.file_changes.details(&params.item_id),
                     },
                 };
 
-                // Emit approval request to frontend
-                self.app_handle
-                    .emit("codex://approval-request", &approval_request)
-                    .context("failed to emit approval request")?;
+                self.emit_approval_request(approval_request)?;
             }
             _ => {
-                bail!("received unsupported server request: {:?}", server_request);
+                return self.reject_server_request(id, &method, "not supported".to_string());
             }
         }
 
         Ok(())
     }
 
+    /// Answers a server request this client can't handle with a JSON-RPC error, so the
+    /// app-server isn't left waiting for a response.
+    fn reject_server_request(&mut self, id: RequestId, method: &str, reason: String) -> Result<()> {
+        protocol::record_error(
+            &self.protocol,
+            ProtocolErrorKind::UnsupportedRequest,
+            format!("{}: {}", method, reason),
+        );
+        self.write_jsonrpc_message(JSONRPCMessage::Error(JSONRPCError {
+            id,
+            error: JSONRPCErrorError {
+                code: METHOD_NOT_FOUND,
+                message: format!("{} is not supported by this client", method),
+                data: None,
+            },
+        }))
+    }
+
+    fn emit_approval_request(&self, approval_request: ApprovalRequest) -> Result<()> {
+        self.sink.approval_request(&approval_request)
+    }
+
     fn send_approval_response(
         &mut self,
         request_id: RequestId,
@@ -399,64 +597,169 @@ impl CodexClient {
                         }
                         JSONRPCMessage::Error(err) => {
                             if &err.id == request_id {
-                                bail!("Request failed: {:?}", err);
+                                return Err(ClientError::Rpc {
+                                    code: err.error.code,
+                                    message: err.error.message,
+                                    data: err.error.data,
+                                }
+                                .into());
                             }
                         }
                         JSONRPCMessage::Notification(notification) => {
-                            if let Ok(server_notification) = ServerNotification::try_from(notification) {
-                                let _ = self.emit_notification(&server_notification);
-                            }
+                            let _ = self.handle_notification(notification);
                         }
                         JSONRPCMessage::Request(request) => {
                             let _ = self.handle_server_request(request);
                         }
                     }
                 }
+                EventLoopMessage::DroppedResponse { id, reason } => {
+                    let expected = match request_id {
+                        RequestId::String(s) => s.clone(),
+                        RequestId::Integer(i) => i.to_string(),
+                    };
+                    if id == expected {
+                        return Err(ClientError::UnreadableResponse { reason }.into());
+                    }
+                }
                 EventLoopMessage::Command(_) => {
                     // Ignore commands during initialization
                 }
+                EventLoopMessage::ServerClosed => {
+                    return Err(ClientError::ProcessExited.into());
+                }
             }
         }
     }
 
     fn write_request(&mut self, request: &ClientRequest) -> Result<()> {
         let request_json = serde_json::to_string(request)?;
-        writeln!(self.stdin, "{}", request_json)?;
-        self.stdin.flush().context("failed to flush request")?;
+        let stdin = self.stdin()?;
+        writeln!(stdin, "{}", request_json)?;
+        stdin.flush().context("failed to flush request")?;
         Ok(())
     }
 
-    fn read_jsonrpc_message_static(stdout: &mut BufReader<ChildStdout>) -> Result<JSONRPCMessage> {
+    /// Reads the next JSON-RPC message, skipping lines that aren't one; a skipped line that
+    /// was a response is reported as dropped. Fails only when stdout can't be read or is
+    /// closed.
+    fn read_jsonrpc_message_static(
+        stdout: &mut BufReader<ChildStdout>,
+        status: &SharedProtocolStatus,
+    ) -> Result<EventLoopMessage> {
+        let dropped = |line: &[u8], reason: String| {
+            protocol::response_id(line).map(|id| EventLoopMessage::DroppedResponse { id, reason })
+        };
+        let mut line = Vec::new();
         loop {
-            let mut response_line = String::new();
-            let bytes = stdout
-                .read_line(&mut response_line)
-                .context("failed to read from codex app-server")?;
-
-            if bytes == 0 {
-                bail!("codex app-server closed stdout");
+            match protocol::read_line_capped(stdout, &mut line)
+                .context("failed to read from codex app-server")?
+            {
+                Line::EndOfStream => bail!("codex app-server closed stdout"),
+                Line::Oversized(length) => {
+                    let reason = format!(
+                        "line of {} bytes exceeds the {} byte limit",
+                        length,
+                        protocol::MAX_LINE_BYTES
+                    );
+                    protocol::record_error(
+                        status,
+                        ProtocolErrorKind::OversizedLine,
+                        format!("{}: {}", reason, protocol::preview(&line)),
+                    );
+                    match dropped(&line, reason) {
+                        Some(message) => return Ok(message),
+                        None => continue,
+                    }
+                }
+                Line::Complete => {}
             }
 
-            let trimmed = response_line.trim();
-            if trimmed.is_empty() {
+            if line.trim_ascii().is_empty() {
                 continue;
             }
 
-            let parsed: Value =
-                serde_json::from_str(trimmed).context("response was not valid JSON")?;
-            let message: JSONRPCMessage =
-                serde_json::from_value(parsed).context("response was not valid JSON-RPC message")?;
-            return Ok(message);
+            match serde_json::from_slice::<JSONRPCMessage>(&line) {
+                Ok(message) => {
+                    log::info!("Reader thread received message: {:?}", message);
+                    return Ok(EventLoopMessage::JsonRpcMessage(message));
+                }
+                Err(e) => {
+                    protocol::record_error(
+                        status,
+                        ProtocolErrorKind::MalformedLine,
+                        format!("{}: {}", e, protocol::preview(&line)),
+                    );
+                    if let Some(message) = dropped(&line, e.to_string()) {
+                        return Ok(message);
+                    }
+                }
+            }
         }
     }
 
     fn write_jsonrpc_message(&mut self, message: JSONRPCMessage) -> Result<()> {
         let payload = serde_json::to_string(&message)?;
-        writeln!(self.stdin, "{}", payload)?;
-        self.stdin.flush().context("failed to flush response")?;
+        let stdin = self.stdin()?;
+        writeln!(stdin, "{}", payload)?;
+        stdin.flush().context("failed to flush response")?;
         Ok(())
     }
 
+    fn stdin(&mut self) -> Result<&mut ChildStdin> {
+        self.stdin
+            .as_mut()
+            .ok_or_else(|| ClientError::ProcessExited.into())
+    }
+
+    /// Asks the app-server to interrupt the given `(thread_id, turn_id)` turns, without waiting
+    /// for the responses.
+    fn interrupt_turns(&mut self, turns: &[(String, String)]) {
+        for (thread_id, turn_id) in turns {
+            let params = match serde_json::from_value(
+                serde_json::json!({ "threadId": thread_id, "turnId": turn_id }),
+            ) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::warn!("Invalid interrupt parameters: {}", e);
+                    continue;
+                }
+            };
+            let request = ClientRequest::TurnInterrupt {
+                request_id: self.request_id(),
+                params,
+            };
+            if let Err(e) = self.write_request(&request) {
+                log::warn!("Failed to interrupt turn {} of thread {}: {}", turn_id, thread_id, e);
+            }
+        }
+    }
+
+    /// Closes the app-server's stdin so it can exit on its own, and kills it if it is still
+    /// running after `grace`.
+    fn stop_server(&mut self, grace: Duration) {
+        self.stdin = None;
+        let deadline = Instant::now() + grace;
+        loop {
+            match self.child.try_wait() {
+                Ok(Some(status)) => {
+                    log::info!("codex app-server exited with {}", status);
+                    break;
+                }
+                Ok(None) if Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+                _ => {
+                    log::warn!("codex app-server still running, killing it");
+                    let _ = self.child.kill();
+                    let _ = self.child.wait();
+                    break;
+                }
+            }
+        }
+        pidfile::forget(self.child.id());
+    }
+
     fn request_id(&self) -> RequestId {
         RequestId::String(Uuid::new_v4().to_string())
     }
@@ -464,7 +767,6 @@ impl CodexClient {
 
 impl Drop for CodexClient {
     fn drop(&mut self) {
-        let _ = self.child.kill();
-        let _ = self.child.wait();
+        self.stop_server(Duration::ZERO);
     }
 }
@@ -0,0 +1,60 @@
+use serde_json::Value;
+use std::fmt;
+
+/// Client failures callers need to tell apart. They travel inside `anyhow::Error`, under any
+/// added context, and `CodexError::from` recovers them.
+#[derive(Debug, Clone)]
+pub enum ClientError {
+    BinaryNotFound,
+    NotInitialized,
+    /// The app-server or the client's event loop is gone.
+    ProcessExited,
+    Timeout {
+        operation: String,
+    },
+    /// A JSON-RPC error response from the app-server.
+    Rpc {
+        code: i64,
+        message: String,
+        data: Option<Value>,
+    },
+    /// A response that doesn't match this build's protocol types, most likely because the
+    /// installed codex is older or newer than supported. `raw` is the response as received.
+    IncompatibleResponse {
+        response: String,
+        message: String,
+        raw: Value,
+    },
+    /// The response line was too long or not valid JSON-RPC and had to be skipped.
+    UnreadableResponse {
+        reason: String,
+    },
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::BinaryNotFound => {
+                write!(f, "Unable to locate codex binary. Install Codex CLI")
+            }
+            ClientError::NotInitialized => write!(f, "Codex client not initialized"),
+            ClientError::ProcessExited => write!(f, "codex app-server is not running"),
+            ClientError::Timeout { operation } => write!(f, "{} timed out", operation),
+            ClientError::Rpc { code, message, .. } => {
+                write!(f, "app-server error {}: {}", code, message)
+            }
+            ClientError::IncompatibleResponse {
+                response, message, ..
+            } => write!(
+                f,
+                "Unexpected {} from the app-server: {}",
+                response, message
+            ),
+            ClientError::UnreadableResponse { reason } => {
+                write!(f, "Unreadable response from the app-server: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
@@ -0,0 +1,222 @@
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::sync::mpsc::Sender;
+use tauri::{Emitter, Manager};
+
+use crate::batch;
+use crate::codex::protocol::Compatibility;
+use crate::codex::types::ApprovalRequest;
+use crate::history_index;
+use crate::notifications;
+use crate::state::AppState;
+use crate::tray;
+use crate::turn_queue;
+use crate::usage;
+use crate::windows;
+
+/// Receives what the app-server sends on its own initiative. The GUI forwards it to the
+/// webview and the backend services; headless front-ends consume it directly.
+pub trait ClientEventSink: Send + 'static {
+    /// `notification` is the JSON form `{"method", "params"}` of a `ServerNotification`.
+    fn notification(&self, notification: &Value) -> Result<()>;
+
+    fn approval_request(&self, request: &ApprovalRequest) -> Result<()>;
+
+    /// A notification that isn't a known `ServerNotification`, as `{"method", "params"}`.
+    /// Ignored unless the sink overrides it.
+    fn raw_notification(&self, _notification: &Value) -> Result<()> {
+        Ok(())
+    }
+
+    /// The app-server's version is outside the supported range or couldn't be determined.
+    /// Ignored unless the sink overrides it; the client logs it either way.
+    fn compatibility_warning(&self, _compatibility: &Compatibility) -> Result<()> {
+        Ok(())
+    }
+
+    /// The app-server exited or closed its output on its own. Ignored unless the sink
+    /// overrides it.
+    fn server_closed(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Sink of the Tauri app: emits to the webview and feeds the history index, usage ledger,
+/// thread activity, approval registry and desktop notifications.
+pub struct TauriEventSink {
+    app_handle: tauri::AppHandle,
+}
+
+impl TauriEventSink {
+    pub fn new(app_handle: tauri::AppHandle) -> Self {
+        Self { app_handle }
+    }
+
+    /// Feeds a notification to the backend services that track thread history.
+    fn observe_notification(&self, notification: &Value) {
+        let method = notification
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+
+        // Account changes get their own event so settings views can refresh without
+        // filtering the whole notification stream.
+        if matches!(method, "account/updated" | "account/login/completed")
+            && let Err(e) = self.app_handle.emit(
+                "codex://account-updated",
+                notification.get("params").cloned(),
+            )
+        {
+            log::warn!("Failed to emit account update: {}", e);
+        }
+
+        let state = self.app_handle.state::<AppState>();
+        if let Ok(bridge) = state.bridge.lock() {
+            bridge.publish(notification);
+        }
+        if let Ok(mut activity) = state.activity.lock() {
+            activity.record_notification(notification);
+        }
+        if let Ok(mut index) = state.history_index.lock() {
+            index.record_notification(notification);
+        }
+        if method == "turn/completed" {
+            history_index::schedule_save(&self.app_handle);
+        }
+        if let Ok(mut usage) = state.usage.lock() {
+            usage.record_notification(notification);
+        }
+        if method == "turn/completed" {
+            usage::schedule_save(&self.app_handle);
+        }
+        if matches!(method, "thread/started" | "turn/started" | "turn/completed") {
+            tray::refresh(&self.app_handle);
+        }
+        if method == "turn/completed" {
+            notifications::on_turn_completed(&self.app_handle, notification);
+            if let Some(thread_id) = notification
+                .pointer("/params/threadId")
+                .and_then(Value::as_str)
+            {
+                turn_queue::on_turn_finished(&self.app_handle, thread_id);
+            }
+        }
+    }
+}
+
+impl ClientEventSink for TauriEventSink {
+    fn notification(&self, notification: &Value) -> Result<()> {
+        log::info!(
+            "Emitting event: codex:notification with payload: {:?}",
+            notification
+        );
+        windows::emit_for_thread(
+            &self.app_handle,
+            windows::notification_thread_id(notification),
+            "codex:notification",
+            notification,
+        )
+        .context("failed to emit notification")?;
+        self.observe_notification(notification);
+        Ok(())
+    }
+
+    fn raw_notification(&self, notification: &Value) -> Result<()> {
+        // Kept off `codex:notification` so the webview's typed handlers never see it.
+        windows::emit_for_thread(
+            &self.app_handle,
+            windows::notification_thread_id(notification),
+            "codex://raw-notification",
+            notification,
+        )
+        .context("failed to emit raw notification")?;
+        if let Ok(bridge) = self.app_handle.state::<AppState>().bridge.lock() {
+            bridge.publish(notification);
+        }
+        Ok(())
+    }
+
+    fn compatibility_warning(&self, compatibility: &Compatibility) -> Result<()> {
+        // Sent to every window; `codex_status` reports it too, for windows opened later.
+        self.app_handle
+            .emit("codex://compatibility-warning", compatibility)
+            .context("failed to emit compatibility warning")
+    }
+
+    fn server_closed(&self) -> Result<()> {
+        let state = self.app_handle.state::<AppState>();
+        if let Ok(mut activity) = state.activity.lock() {
+            activity.server_stopped();
+        }
+        if let Ok(mut queue) = state.turn_queue.lock() {
+            queue.server_stopped();
+        }
+        tray::refresh(&self.app_handle);
+        Ok(())
+    }
+
+    fn approval_request(&self, request: &ApprovalRequest) -> Result<()> {
+        // Requests from batch jobs are answered by the batch's rules unless they say to ask.
+        if batch::handle_approval(&self.app_handle, request) {
+            return Ok(());
+        }
+        let state = self.app_handle.state::<AppState>();
+        if let Ok(mut approvals) = state.approvals.lock() {
+            approvals.add_pending(request.clone());
+        }
+        if let Ok(bridge) = state.bridge.lock() {
+            bridge.publish(&json!({ "method": "approval/request", "params": request }));
+        }
+
+        // Emit approval request to the window showing the thread
+        windows::emit_for_thread(
+            &self.app_handle,
+            Some(&request.thread_id),
+            "codex://approval-request",
+            request,
+        )
+        .context("failed to emit approval request")?;
+        notifications::on_approval_request(&self.app_handle, request);
+        tray::refresh(&self.app_handle);
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ClientEvent {
+    Notification(Value),
+    ApprovalRequest(Box<ApprovalRequest>),
+}
+
+/// Sink that hands every event to a channel, for front-ends without a webview.
+pub struct ChannelEventSink {
+    tx: Sender<ClientEvent>,
+}
+
+impl ChannelEventSink {
+    pub fn new(tx: Sender<ClientEvent>) -> Self {
+        Self { tx }
+    }
+}
+
+impl ClientEventSink for ChannelEventSink {
+    fn notification(&self, notification: &Value) -> Result<()> {
+        self.tx
+            .send(ClientEvent::Notification(notification.clone()))
+            .context("event receiver closed")
+    }
+
+    fn approval_request(&self, request: &ApprovalRequest) -> Result<()> {
+        self.tx
+            .send(ClientEvent::ApprovalRequest(Box::new(request.clone())))
+            .context("event receiver closed")
+    }
+
+    // The channel only carries server traffic; a headless front-end shows this on stderr.
+    fn compatibility_warning(&self, compatibility: &Compatibility) -> Result<()> {
+        if let Some(message) = &compatibility.message {
+            eprintln!("warning: {}", message);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,422 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Largest diff sent for a single file; longer diffs are cut at a line boundary.
+const MAX_FILE_DIFF_BYTES: usize = 128 * 1024;
+/// Budget for all diffs of one approval; files past it are listed without a diff.
+const MAX_TOTAL_DIFF_BYTES: usize = 512 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileChangeKind {
+    Add,
+    Delete,
+    Update,
+    Move,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileChangeDetail {
+    pub path: String,
+    pub kind: FileChangeKind,
+    /// Destination path of a move.
+    pub move_path: Option<String>,
+    /// Unified diff of the change, `None` for binary files or when over the size budget.
+    pub diff: Option<String>,
+    pub additions: usize,
+    pub deletions: usize,
+    pub binary: bool,
+    pub truncated: bool,
+}
+
+/// Remembers the changes of `fileChange` items announced by `item/started`, so the approval
+/// request that follows (which only names the item) can show what will be written.
+#[derive(Debug, Default)]
+pub struct FileChangeTracker {
+    /// Changes by item id, with the id of the turn the item belongs to.
+    items: HashMap<String, (Option<String>, Vec<FileChangeDetail>)>,
+}
+
+impl FileChangeTracker {
+    pub fn record_notification(&mut self, notification: &Value) {
+        let method = notification
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let turn_id = notification
+            .pointer("/params/turnId")
+            .or_else(|| notification.pointer("/params/turn/id"))
+            .and_then(Value::as_str);
+
+        // Items of an interrupted or failed turn may never see `item/completed`.
+        if method == "turn/completed" {
+            if let Some(turn_id) = turn_id {
+                self.items
+                    .retain(|_, (item_turn, _)| item_turn.as_deref() != Some(turn_id));
+            }
+            return;
+        }
+
+        let Some(item) = notification.pointer("/params/item") else {
+            return;
+        };
+        if item.get("type").and_then(Value::as_str) != Some("fileChange") {
+            return;
+        }
+        let Some(item_id) = item.get("id").and_then(Value::as_str) else {
+            return;
+        };
+
+        match method {
+            "item/started" => {
+                let changes = item
+                    .get("changes")
+                    .and_then(Value::as_array)
+                    .map(|changes| details_from_changes(changes))
+                    .unwrap_or_default();
+                self.items
+                    .insert(item_id.to_string(), (turn_id.map(str::to_string), changes));
+            }
+            "item/completed" => {
+                self.items.remove(item_id);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn details(&self, item_id: &str) -> Vec<FileChangeDetail> {
+        self.items
+            .get(item_id)
+            .map(|(_, details)| details.clone())
+            .unwrap_or_default()
+    }
+}
+
+fn details_from_changes(changes: &[Value]) -> Vec<FileChangeDetail> {
+    let mut budget = MAX_TOTAL_DIFF_BYTES;
+    changes
+        .iter()
+        .filter_map(|change| {
+            let path = change.get("path").and_then(Value::as_str)?.to_string();
+            let kind_value = change.get("kind");
+            let kind_name = kind_value
+                .and_then(|kind| kind.get("type").or(Some(kind)))
+                .and_then(Value::as_str)
+                .unwrap_or("update");
+            let move_path = kind_value
+                .and_then(|kind| kind.get("movePath").or_else(|| kind.get("move_path")))
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            let kind = match (kind_name, &move_path) {
+                ("add", _) => FileChangeKind::Add,
+                ("delete", _) => FileChangeKind::Delete,
+                (_, Some(_)) => FileChangeKind::Move,
+                _ => FileChangeKind::Update,
+            };
+            let raw = change
+                .get("diff")
+                .or_else(|| change.get("content"))
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+
+            Some(build_detail(path, kind, move_path, raw, &mut budget))
+        })
+        .collect()
+}
+
+fn is_binary(text: &str) -> bool {
+    text.contains('\0')
+        || text.contains("GIT binary patch")
+        || text.lines().any(|line| line.starts_with("Binary files "))
+}
+
+/// The app-server sends the full content for added and deleted files and bare hunks for
+/// updates; everything is normalised to a unified diff with file headers.
+fn to_unified_diff(path: &str, kind: FileChangeKind, move_path: Option<&str>, raw: &str) -> String {
+    let body_lines = |prefix: char| {
+        raw.lines()
+            .map(|line| format!("{}{}\n", prefix, line))
+            .collect::<String>()
+    };
+    let count = raw.lines().count();
+
+    match kind {
+        FileChangeKind::Add => format!(
+            "--- /dev/null\n+++ b/{}\n@@ -0,0 +1,{} @@\n{}",
+            path,
+            count,
+            body_lines('+')
+        ),
+        FileChangeKind::Delete => format!(
+            "--- a/{}\n+++ /dev/null\n@@ -1,{} +0,0 @@\n{}",
+            path,
+            count,
+            body_lines('-')
+        ),
+        FileChangeKind::Update | FileChangeKind::Move => {
+            if raw.starts_with("--- ") || raw.starts_with("diff ") {
+                raw.to_string()
+            } else {
+                format!(
+                    "--- a/{}\n+++ b/{}\n{}",
+                    path,
+                    move_path.unwrap_or(path),
+                    raw
+                )
+            }
+        }
+    }
+}
+
+/// Old and new line counts of a `@@ -a,b +c,d @@` hunk header; an omitted count is 1.
+fn hunk_lengths(line: &str) -> Option<(usize, usize)> {
+    let ranges = line.strip_prefix("@@ -")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(" +")?;
+    let length = |range: &str| match range.split_once(',') {
+        Some((_, count)) => count.parse().ok(),
+        None => range.parse::<usize>().ok().map(|_| 1),
+    };
+    Some((length(old)?, length(new)?))
+}
+
+/// Counts added and removed lines. Only lines inside a hunk, as far as its header says it
+/// reaches, are counted, so content lines starting with `++` or `--` aren't mistaken for
+/// file headers and headers between files aren't counted as content.
+fn count_changes(diff: &str) -> (usize, usize) {
+    let (mut additions, mut deletions) = (0, 0);
+    let (mut old_left, mut new_left) = (0, 0);
+    for line in diff.lines() {
+        if old_left == 0 && new_left == 0 {
+            if let Some((old, new)) = hunk_lengths(line) {
+                (old_left, new_left) = (old, new);
+            }
+            continue;
+        }
+        match line.chars().next() {
+            Some('+') => {
+                additions += 1;
+                new_left = new_left.saturating_sub(1);
+            }
+            Some('-') => {
+                deletions += 1;
+                old_left = old_left.saturating_sub(1);
+            }
+            // "\ No newline at end of file"
+            Some('\\') => {}
+            _ => {
+                old_left = old_left.saturating_sub(1);
+                new_left = new_left.saturating_sub(1);
+            }
+        }
+    }
+    (additions, deletions)
+}
+
+fn build_detail(
+    path: String,
+    kind: FileChangeKind,
+    move_path: Option<String>,
+    raw: &str,
+    budget: &mut usize,
+) -> FileChangeDetail {
+    let mut detail = FileChangeDetail {
+        kind,
+        move_path,
+        diff: None,
+        additions: 0,
+        deletions: 0,
+        binary: is_binary(raw),
+        truncated: false,
+        path,
+    };
+    if detail.binary {
+        return detail;
+    }
+
+    let diff = to_unified_diff(&detail.path, kind, detail.move_path.as_deref(), raw);
+    (detail.additions, detail.deletions) = count_changes(&diff);
+
+    let limit = MAX_FILE_DIFF_BYTES.min(*budget);
+    if diff.len() <= limit {
+        *budget -= diff.len();
+        detail.diff = Some(diff);
+        return detail;
+    }
+
+    detail.truncated = true;
+    // Keep whole lines only, so the frontend never renders half a hunk line.
+    let mut cut = 0;
+    for (index, _) in diff.match_indices('\n') {
+        if index + 1 > limit {
+            break;
+        }
+        cut = index + 1;
+    }
+    if cut > 0 {
+        *budget -= cut;
+        detail.diff = Some(diff[..cut].to_string());
+    }
+    detail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn detail(kind: FileChangeKind, raw: &str) -> FileChangeDetail {
+        let mut budget = MAX_TOTAL_DIFF_BYTES;
+        build_detail("src/lib.rs".to_string(), kind, None, raw, &mut budget)
+    }
+
+    fn started(turn_id: &str, item_id: &str) -> Value {
+        json!({
+            "method": "item/started",
+            "params": {
+                "threadId": "t1",
+                "turnId": turn_id,
+                "item": {
+                    "type": "fileChange",
+                    "id": item_id,
+                    "changes": [{ "path": "a.rs", "kind": { "type": "add" }, "diff": "x\n" }],
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn added_and_deleted_files_become_unified_diffs() {
+        let added = detail(FileChangeKind::Add, "fn main() {}\n++counter;\n");
+        assert_eq!(
+            added.diff.as_deref(),
+            Some("--- /dev/null\n+++ b/src/lib.rs\n@@ -0,0 +1,2 @@\n+fn main() {}\n+++counter;\n")
+        );
+        assert_eq!((added.additions, added.deletions), (2, 0));
+
+        let deleted = detail(FileChangeKind::Delete, "-- a comment\nline\n");
+        assert_eq!(
+            deleted.diff.as_deref(),
+            Some("--- a/src/lib.rs\n+++ /dev/null\n@@ -1,2 +0,0 @@\n--- a comment\n-line\n")
+        );
+        assert_eq!((deleted.additions, deleted.deletions), (0, 2));
+    }
+
+    #[test]
+    fn updates_get_file_headers_and_count_by_hunk() {
+        let raw = "@@ -1,3 +1,3 @@\n context\n--- old heading\n+++ new heading\n\\ No newline at end of file\n";
+        let update = detail(FileChangeKind::Update, raw);
+        assert_eq!(
+            update.diff.as_deref(),
+            Some(format!("--- a/src/lib.rs\n+++ b/src/lib.rs\n{}", raw).as_str())
+        );
+        assert_eq!((update.additions, update.deletions), (1, 1));
+
+        let mut budget = MAX_TOTAL_DIFF_BYTES;
+        let moved = build_detail(
+            "old.rs".to_string(),
+            FileChangeKind::Move,
+            Some("new.rs".to_string()),
+            "@@ -1 +1 @@\n-a\n+b\n",
+            &mut budget,
+        );
+        assert!(
+            moved
+                .diff
+                .unwrap()
+                .starts_with("--- a/old.rs\n+++ b/new.rs\n")
+        );
+    }
+
+    #[test]
+    fn full_diffs_are_kept_and_headers_between_files_are_not_counted() {
+        let raw = "diff --git a/a b/a\n--- a/a\n+++ b/a\n@@ -1,2 +1,2 @@\n-x\n+y\n z\n\
+                   --- a/b\n+++ b/b\n@@ -5 +5,2 @@\n q\n+r\n";
+        let update = detail(FileChangeKind::Update, raw);
+        assert_eq!(update.diff.as_deref(), Some(raw));
+        assert_eq!((update.additions, update.deletions), (2, 1));
+    }
+
+    #[test]
+    fn hunk_headers_are_parsed() {
+        assert_eq!(hunk_lengths("@@ -1,3 +2,4 @@ fn main()"), Some((3, 4)));
+        assert_eq!(hunk_lengths("@@ -7 +7 @@"), Some((1, 1)));
+        assert_eq!(hunk_lengths("@@ -0,0 +1,5 @@"), Some((0, 5)));
+        assert_eq!(hunk_lengths("@@ bare"), None);
+        assert_eq!(hunk_lengths("+@@ -1 +1 @@"), None);
+    }
+
+    #[test]
+    fn binary_content_has_no_diff() {
+        assert!(is_binary("PNG\0\0data"));
+        assert!(is_binary(
+            "diff --git a/x b/x\nGIT binary patch\nliteral 10\n"
+        ));
+        assert!(is_binary("Binary files a/x and b/x differ\n"));
+        assert!(!is_binary(
+            "@@ -1 +1 @@\n-Binary files are fine in text\n+ok\n"
+        ));
+
+        let binary = detail(FileChangeKind::Add, "\0\u{1}");
+        assert!(binary.binary);
+        assert!(binary.diff.is_none());
+        assert_eq!((binary.additions, binary.deletions), (0, 0));
+    }
+
+    #[test]
+    fn large_diffs_are_cut_at_line_boundaries() {
+        let line = format!("{}\n", "x".repeat(99));
+        let raw = line.repeat(MAX_FILE_DIFF_BYTES / 100 + 10);
+        let large = detail(FileChangeKind::Add, &raw);
+        assert!(large.truncated);
+        let diff = large.diff.unwrap();
+        assert!(diff.len() <= MAX_FILE_DIFF_BYTES);
+        assert!(diff.ends_with('\n'));
+        // The counts describe the whole change, not the part that was kept.
+        assert_eq!(large.additions, raw.lines().count());
+    }
+
+    #[test]
+    fn the_total_budget_is_shared_across_files() {
+        let content = format!("{}\n", "y".repeat(99)).repeat(MAX_FILE_DIFF_BYTES / 100 - 30);
+        let changes: Vec<Value> = (0..6)
+            .map(|i| json!({ "path": format!("f{}.rs", i), "kind": "add", "diff": content }))
+            .collect();
+        let details = details_from_changes(&changes);
+
+        assert_eq!(details.len(), 6);
+        let total: usize = details
+            .iter()
+            .filter_map(|d| d.diff.as_ref())
+            .map(String::len)
+            .sum();
+        assert!(total <= MAX_TOTAL_DIFF_BYTES);
+        assert!(!details[0].truncated);
+        assert!(details[4].truncated);
+        assert!(details[5].truncated);
+        assert!(details.iter().all(|d| d.kind == FileChangeKind::Add));
+    }
+
+    #[test]
+    fn tracked_changes_are_dropped_with_their_item_or_turn() {
+        let mut tracker = FileChangeTracker::default();
+        tracker.record_notification(&started("turn-1", "item-1"));
+        tracker.record_notification(&started("turn-1", "item-2"));
+        tracker.record_notification(&started("turn-2", "item-3"));
+        assert_eq!(tracker.details("item-1")[0].path, "a.rs");
+
+        tracker.record_notification(&json!({
+            "method": "item/completed",
+            "params": { "item": { "type": "fileChange", "id": "item-1" } },
+        }));
+        assert!(tracker.details("item-1").is_empty());
+
+        tracker.record_notification(&json!({
+            "method": "turn/completed",
+            "params": { "threadId": "t1", "turn": { "id": "turn-1", "status": "interrupted" } },
+        }));
+        assert!(tracker.details("item-2").is_empty());
+        assert_eq!(tracker.details("item-3").len(), 1);
+    }
+}
@@ -1,10 +1,20 @@
 use anyhow::{Context, Result};
 use codex_app_server_protocol::{
-    ApprovalDecision, RequestId, ThreadResumeParams, ThreadResumeResponse,
-    ThreadStartParams, ThreadStartResponse, TurnInterruptParams, TurnInterruptResponse,
-    TurnStartParams, TurnStartResponse, ThreadListParams, ThreadListResponse,
+    ApprovalDecision, GetAccountParams, GetAccountResponse, LoginAccountParams,
+    LoginAccountResponse, LogoutAccountResponse, ModelListParams, ModelListResponse, RequestId,
+    ThreadResumeParams, ThreadResumeResponse, ThreadStartParams, ThreadStartResponse,
+    TurnInterruptParams, TurnInterruptResponse, TurnStartParams, TurnStartResponse,
+    ThreadListParams, ThreadListResponse,
 };
 use std::sync::mpsc::{channel, Sender};
+use std::time::Duration;
+
+use crate::codex::error::ClientError;
+use crate::codex::events::{ClientEventSink, TauriEventSink};
+use crate::codex::protocol::{ProtocolStatus, SharedProtocolStatus};
+
+/// How long `shutdown` waits for the event loop to stop the app-server.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 
 // Commands that can be sent to the client thread
 pub(crate) enum ClientCommand {
@@ -33,11 +43,33 @@ pub(crate) enum ClientCommand {
         decision: ApprovalDecision,
         is_command_execution: bool,
     },
+    GetAccount {
+        params: GetAccountParams,
+        response_tx: Sender<Result<GetAccountResponse>>,
+    },
+    LoginAccount {
+        params: LoginAccountParams,
+        response_tx: Sender<Result<LoginAccountResponse>>,
+    },
+    LogoutAccount {
+        response_tx: Sender<Result<LogoutAccountResponse>>,
+    },
+    ModelList {
+        params: ModelListParams,
+        response_tx: Sender<Result<ModelListResponse>>,
+    },
+    /// Interrupts the given `(thread_id, turn_id)` turns, stops the app-server and ends the
+    /// event loop.
+    Shutdown {
+        interrupts: Vec<(String, String)>,
+        response_tx: Sender<()>,
+    },
 }
 
 // Handle for communicating with the client thread
 pub struct CodexClientHandle {
     command_tx: Sender<ClientCommand>,
+    protocol: SharedProtocolStatus,
 }
 
 impl Drop for CodexClientHandle {
@@ -48,8 +80,14 @@ impl Drop for CodexClientHandle {
 
 impl CodexClientHandle {
     pub fn spawn_and_initialize(app_handle: tauri::AppHandle) -> Result<Self> {
+        Self::spawn_with_sink(Box::new(TauriEventSink::new(app_handle)))
+    }
+
+    /// Starts an app-server whose notifications and approval requests go to `sink` instead of
+    /// the Tauri app.
+    pub fn spawn_with_sink(sink: Box<dyn ClientEventSink>) -> Result<Self> {
         log::info!("CodexClientHandle::spawn_and_initialize called");
-        let handle = crate::codex::client::CodexClient::spawn_and_initialize(app_handle)?;
+        let handle = crate::codex::client::CodexClient::spawn_and_initialize(sink)?;
         log::info!("CodexClientHandle created successfully");
         Ok(handle)
     }
@@ -58,9 +96,11 @@ impl CodexClientHandle {
         let (response_tx, response_rx) = channel();
         self.command_tx
             .send(ClientCommand::ThreadStart { params, response_tx })
+            .map_err(|_| ClientError::ProcessExited)
             .context("Failed to send thread_start command")?;
         response_rx
             .recv()
+            .map_err(|_| ClientError::ProcessExited)
             .context("Failed to receive thread_start response")?
     }
 
@@ -68,9 +108,11 @@ impl CodexClientHandle {
         let (response_tx, response_rx) = channel();
         self.command_tx
             .send(ClientCommand::ThreadResume { params, response_tx })
+            .map_err(|_| ClientError::ProcessExited)
             .context("Failed to send thread_resume command")?;
         response_rx
             .recv()
+            .map_err(|_| ClientError::ProcessExited)
             .context("Failed to receive thread_resume response")?
     }
 
@@ -78,9 +120,11 @@ impl CodexClientHandle {
         let (response_tx, response_rx) = channel();
         self.command_tx
             .send(ClientCommand::ThreadList { params, response_tx })
+            .map_err(|_| ClientError::ProcessExited)
             .context("Failed to send thread_list command")?;
         response_rx
             .recv()
+            .map_err(|_| ClientError::ProcessExited)
             .context("Failed to receive thread_list response")?
     }
 
@@ -88,9 +132,11 @@ impl CodexClientHandle {
         let (response_tx, response_rx) = channel();
         self.command_tx
             .send(ClientCommand::TurnStart { params, response_tx })
+            .map_err(|_| ClientError::ProcessExited)
             .context("Failed to send turn_start command")?;
         response_rx
             .recv()
+            .map_err(|_| ClientError::ProcessExited)
             .context("Failed to receive turn_start response")?
     }
 
@@ -98,9 +144,11 @@ impl CodexClientHandle {
         let (response_tx, response_rx) = channel();
         self.command_tx
             .send(ClientCommand::TurnInterrupt { params, response_tx })
+            .map_err(|_| ClientError::ProcessExited)
             .context("Failed to send turn_interrupt command")?;
         response_rx
             .recv()
+            .map_err(|_| ClientError::ProcessExited)
             .context("Failed to receive turn_interrupt response")?
     }
 
@@ -116,15 +164,96 @@ impl CodexClientHandle {
                 decision,
                 is_command_execution,
             })
+            .map_err(|_| ClientError::ProcessExited)
             .context("Failed to send respond_to_approval command")?;
         Ok(())
     }
+
+    pub fn get_account(&self, params: GetAccountParams) -> Result<GetAccountResponse> {
+        let (response_tx, response_rx) = channel();
+        self.command_tx
+            .send(ClientCommand::GetAccount { params, response_tx })
+            .map_err(|_| ClientError::ProcessExited)
+            .context("Failed to send get_account command")?;
+        response_rx
+            .recv()
+            .map_err(|_| ClientError::ProcessExited)
+            .context("Failed to receive get_account response")?
+    }
+
+    pub fn login_account(&self, params: LoginAccountParams) -> Result<LoginAccountResponse> {
+        let (response_tx, response_rx) = channel();
+        self.command_tx
+            .send(ClientCommand::LoginAccount { params, response_tx })
+            .map_err(|_| ClientError::ProcessExited)
+            .context("Failed to send login_account command")?;
+        response_rx
+            .recv()
+            .map_err(|_| ClientError::ProcessExited)
+            .context("Failed to receive login_account response")?
+    }
+
+    pub fn logout_account(&self) -> Result<LogoutAccountResponse> {
+        let (response_tx, response_rx) = channel();
+        self.command_tx
+            .send(ClientCommand::LogoutAccount { response_tx })
+            .map_err(|_| ClientError::ProcessExited)
+            .context("Failed to send logout_account command")?;
+        response_rx
+            .recv()
+            .map_err(|_| ClientError::ProcessExited)
+            .context("Failed to receive logout_account response")?
+    }
+
+    pub fn model_list(&self, params: ModelListParams) -> Result<ModelListResponse> {
+        let (response_tx, response_rx) = channel();
+        self.command_tx
+            .send(ClientCommand::ModelList { params, response_tx })
+            .map_err(|_| ClientError::ProcessExited)
+            .context("Failed to send model_list command")?;
+        response_rx
+            .recv()
+            .map_err(|_| ClientError::ProcessExited)
+            .context("Failed to receive model_list response")?
+    }
+
+    /// Whether the app-server is still connected, and the protocol errors seen so far.
+    pub fn protocol_status(&self) -> ProtocolStatus {
+        self.protocol
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_default()
+    }
+
+    /// Interrupts the running turns and stops the app-server process; the handle is unusable
+    /// afterwards.
+    pub fn shutdown(&self, interrupts: Vec<(String, String)>) -> Result<()> {
+        let (response_tx, response_rx) = channel();
+        self.command_tx
+            .send(ClientCommand::Shutdown {
+                interrupts,
+                response_tx,
+            })
+            .map_err(|_| ClientError::ProcessExited)
+            .context("Failed to send shutdown command")?;
+        response_rx
+            .recv_timeout(SHUTDOWN_TIMEOUT)
+            .map_err(|_| {
+                ClientError::Timeout {
+                    operation: "codex app-server shutdown".to_string(),
+                }
+                .into()
+            })
+    }
 }
 
 // Internal constructor for CodexClient to create handles
 impl CodexClientHandle {
-    pub(crate) fn new(command_tx: Sender<ClientCommand>) -> Self {
-        Self { command_tx }
+    pub(crate) fn new(command_tx: Sender<ClientCommand>, protocol: SharedProtocolStatus) -> Self {
+        Self {
+            command_tx,
+            protocol,
+        }
     }
 }
 
@@ -1,3 +1,10 @@
+pub mod approvals;
 pub mod client;
+pub mod command_analysis;
+pub mod error;
+pub mod events;
+pub mod file_changes;
 pub mod handles;
+pub mod pidfile;
+pub mod protocol;
 pub mod types;
@@ -0,0 +1,201 @@
+//! Record of the app-servers this app started, so ones left behind by a crash can be stopped
+//! on the next start. Each entry names the process that owns the app-server; an entry whose
+//! owner is gone but whose app-server still runs is an orphan. Entries also keep the
+//! app-server's start time, so a process that reused its pid is never taken for it.
+
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+use crate::storage;
+
+const PIDFILE: &str = "app-server-pids.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PidEntry {
+    pid: u32,
+    owner_pid: u32,
+    /// Start time as the platform reports it; entries written before it was recorded have
+    /// none and are never acted on.
+    #[serde(default)]
+    started: Option<String>,
+}
+
+/// What a running process is, as far as telling an app-server apart goes.
+struct ProcessInfo {
+    command: String,
+    started: String,
+}
+
+impl ProcessInfo {
+    /// Whether this is still the app-server the entry was written for.
+    fn is_app_server(&self, entry: &PidEntry) -> bool {
+        entry.started.as_deref() == Some(self.started.as_str())
+            && self
+                .command
+                .split_whitespace()
+                .any(|arg| arg.trim_matches('"') == "app-server")
+    }
+}
+
+fn load() -> Vec<PidEntry> {
+    storage::get_app_data_path(PIDFILE)
+        .and_then(|path| storage::load_json(&path))
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read app-server pidfile: {}", e);
+            Vec::new()
+        })
+}
+
+fn save(entries: &[PidEntry]) {
+    if let Err(e) =
+        storage::get_app_data_path(PIDFILE).and_then(|path| storage::save_json(&path, &entries))
+    {
+        log::warn!("Failed to write app-server pidfile: {}", e);
+    }
+}
+
+pub fn record(pid: u32) {
+    let started = process_info(pid).map(|info| info.started);
+    if started.is_none() {
+        log::warn!("Could not read the start time of app-server {}", pid);
+    }
+    let mut entries = load();
+    entries.retain(|entry| entry.pid != pid);
+    entries.push(PidEntry {
+        pid,
+        owner_pid: std::process::id(),
+        started,
+    });
+    save(&entries);
+}
+
+pub fn forget(pid: u32) {
+    let mut entries = load();
+    let before = entries.len();
+    entries.retain(|entry| entry.pid != pid);
+    if entries.len() != before {
+        save(&entries);
+    }
+}
+
+/// Stops app-servers whose owning process has exited, and drops entries for processes that
+/// are gone. Entries of other running instances are left alone.
+pub fn reap_orphans() {
+    let entries = load();
+    if entries.is_empty() {
+        return;
+    }
+    let mut kept = Vec::new();
+    for entry in entries {
+        if entry.owner_pid != std::process::id() && process_alive(entry.owner_pid) {
+            kept.push(entry);
+            continue;
+        }
+        // The pid may have been reused since; only stop the app-server that was recorded.
+        match process_info(entry.pid) {
+            Some(info) if info.is_app_server(&entry) => {
+                log::warn!(
+                    "Stopping orphaned codex app-server {} left by process {}",
+                    entry.pid,
+                    entry.owner_pid
+                );
+                if let Err(e) = kill_process(entry.pid) {
+                    log::error!("Failed to stop orphaned app-server {}: {}", entry.pid, e);
+                    kept.push(entry);
+                }
+            }
+            Some(_) => log::info!(
+                "Process {} is no longer the recorded app-server, leaving it alone",
+                entry.pid
+            ),
+            None => {}
+        }
+    }
+    save(&kept);
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Start time and command line of a running process. `lstart` is five words in the C locale,
+/// e.g. `Sat Oct 18 09:41:07 2026`.
+#[cfg(unix)]
+fn process_info(pid: u32) -> Option<ProcessInfo> {
+    let output = Command::new("ps")
+        .args(["-p", &pid.to_string(), "-o", "lstart=", "-o", "command="])
+        .env("LC_ALL", "C")
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut words = stdout.split_whitespace();
+    let started = words.by_ref().take(5).collect::<Vec<_>>().join(" ");
+    let command = words.collect::<Vec<_>>().join(" ");
+    (!command.is_empty()).then_some(ProcessInfo { command, started })
+}
+
+#[cfg(unix)]
+fn kill_process(pid: u32) -> Result<(), String> {
+    let output = Command::new("kill")
+        .arg(pid.to_string())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(windows)]
+fn process_alive(pid: u32) -> bool {
+    Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/FO", "CSV", "/NH"])
+        .output()
+        .is_ok_and(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|line| line.starts_with('"'))
+        })
+}
+
+/// Start time (as a FILETIME) and command line of a running process; `tasklist` shows
+/// neither, so they come from WMI.
+#[cfg(windows)]
+fn process_info(pid: u32) -> Option<ProcessInfo> {
+    let script = format!(
+        "$p = Get-CimInstance Win32_Process -Filter 'ProcessId = {}'; \
+         if ($p) {{ $p.CreationDate.ToFileTimeUtc(); $p.CommandLine }}",
+        pid
+    );
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut lines = stdout.lines();
+    let started = lines.next()?.trim().to_string();
+    let command = lines.next().unwrap_or_default().trim().to_string();
+    (output.status.success() && !started.is_empty()).then_some(ProcessInfo { command, started })
+}
+
+#[cfg(windows)]
+fn kill_process(pid: u32) -> Result<(), String> {
+    let output = Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
@@ -0,0 +1,465 @@
+//! Health of the JSON-RPC stream from the app-server. Lines that can't be read as messages
+//! and messages this client doesn't understand are skipped and counted here instead of
+//! ending the connection.
+//!
+//! The protocol types are compiled in from a codex git revision while users run whichever
+//! codex they installed, so the version the app-server reports at initialize is also checked
+//! against the range this build is known to work with.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::io::{self, BufRead};
+use std::sync::{Arc, Mutex};
+
+/// Longest line accepted from the app-server; longer ones are dropped without being buffered.
+pub const MAX_LINE_BYTES: usize = 32 * 1024 * 1024;
+/// Characters of an offending line kept for the log and the status.
+const PREVIEW_CHARS: usize = 200;
+
+/// The protocol types come from codex-app-server-protocol at git revision 9287be76 (see
+/// Cargo.lock), which belongs to the 0.63 releases. Breaking app-server changes ship in 0.x
+/// minors, so only that minor is supported; move both bounds along with the revision.
+const MIN_SERVER_VERSION: Version = (0, 63, 0);
+/// First app-server version not known to work: the next minor after the pinned revision.
+const MAX_SERVER_VERSION: Version = (0, 64, 0);
+
+type Version = (u64, u64, u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProtocolErrorKind {
+    /// Not JSON, or JSON that isn't a JSON-RPC message.
+    MalformedLine,
+    OversizedLine,
+    UnknownNotification,
+    UnsupportedRequest,
+    /// A response that doesn't match the type expected for its request.
+    IncompatibleResponse,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolError {
+    pub kind: ProtocolErrorKind,
+    pub detail: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProtocolStatus {
+    /// False once the app-server closed its stdout.
+    pub connected: bool,
+    pub malformed_lines: u64,
+    pub oversized_lines: u64,
+    pub unknown_notifications: u64,
+    pub unsupported_requests: u64,
+    pub incompatible_responses: u64,
+    pub last_error: Option<ProtocolError>,
+    /// Set once the app-server answered initialize.
+    pub compatibility: Option<Compatibility>,
+}
+
+/// Status shared by the reader thread, the event loop and the client handle.
+pub type SharedProtocolStatus = Arc<Mutex<ProtocolStatus>>;
+
+pub fn new_status() -> SharedProtocolStatus {
+    Arc::new(Mutex::new(ProtocolStatus {
+        connected: true,
+        ..ProtocolStatus::default()
+    }))
+}
+
+/// Logs and counts a protocol error.
+pub fn record_error(status: &SharedProtocolStatus, kind: ProtocolErrorKind, detail: String) {
+    log::warn!("codex app-server protocol error ({:?}): {}", kind, detail);
+    let Ok(mut status) = status.lock() else {
+        return;
+    };
+    match kind {
+        ProtocolErrorKind::MalformedLine => status.malformed_lines += 1,
+        ProtocolErrorKind::OversizedLine => status.oversized_lines += 1,
+        ProtocolErrorKind::UnknownNotification => status.unknown_notifications += 1,
+        ProtocolErrorKind::UnsupportedRequest => status.unsupported_requests += 1,
+        ProtocolErrorKind::IncompatibleResponse => status.incompatible_responses += 1,
+    }
+    status.last_error = Some(ProtocolError {
+        kind,
+        detail,
+        at: Utc::now(),
+    });
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompatibilityStatus {
+    Compatible,
+    /// Outside the supported range.
+    Unsupported,
+    /// No release version in the user agent, e.g. a development build.
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Compatibility {
+    pub status: CompatibilityStatus,
+    pub user_agent: String,
+    pub server_version: Option<String>,
+    pub supported_range: String,
+    /// What to tell the user; `None` when compatible.
+    pub message: Option<String>,
+}
+
+fn format_version((major, minor, patch): Version) -> String {
+    format!("{}.{}.{}", major, minor, patch)
+}
+
+/// Release version of a user agent like `codex_cli_rs/0.63.0 (Mac OS 15.1.0; arm64) vscode`.
+/// Pre-release suffixes are ignored; `0.0.0`, which local builds report, counts as none.
+fn parse_server_version(user_agent: &str) -> Option<Version> {
+    let product = user_agent.split_whitespace().next()?;
+    let (_, version) = product.rsplit_once('/')?;
+    let release = version.split(['-', '+']).next()?;
+    let mut parts = release.split('.').map(|part| part.parse::<u64>().ok());
+    let version = (parts.next()??, parts.next()??, parts.next()??);
+    (version != (0, 0, 0)).then_some(version)
+}
+
+/// Checks the user agent the app-server reported at initialize.
+pub fn check_compatibility(user_agent: &str) -> Compatibility {
+    let supported_range = format!(
+        ">= {}, < {}",
+        format_version(MIN_SERVER_VERSION),
+        format_version(MAX_SERVER_VERSION)
+    );
+    let version = parse_server_version(user_agent);
+    let (status, message) = match version {
+        Some(version) if (MIN_SERVER_VERSION..MAX_SERVER_VERSION).contains(&version) => {
+            (CompatibilityStatus::Compatible, None)
+        }
+        Some(version) if version < MIN_SERVER_VERSION => (
+            CompatibilityStatus::Unsupported,
+            Some(format!(
+                "codex {} is older than this app supports ({}). Update Codex CLI; until then \
+                 some requests may fail.",
+                format_version(version),
+                supported_range
+            )),
+        ),
+        Some(version) => (
+            CompatibilityStatus::Unsupported,
+            Some(format!(
+                "codex {} is newer than this app was built for ({}). Some responses may not \
+                 be understood until the app is updated.",
+                format_version(version),
+                supported_range
+            )),
+        ),
+        None => (
+            CompatibilityStatus::Unknown,
+            Some(format!(
+                "Could not tell the codex version from \"{}\"; compatibility with this app \
+                 ({}) is not checked.",
+                user_agent, supported_range
+            )),
+        ),
+    };
+    Compatibility {
+        status,
+        user_agent: user_agent.to_string(),
+        server_version: version.map(format_version),
+        supported_range,
+        message,
+    }
+}
+
+/// Start of an offending line, for error details.
+pub fn preview(line: &[u8]) -> String {
+    let text = String::from_utf8_lossy(line);
+    let text = text.trim();
+    if text.chars().count() <= PREVIEW_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(PREVIEW_CHARS).collect();
+    format!("{}…", cut)
+}
+
+/// Result of reading one line with `read_line_capped`.
+pub enum Line {
+    Complete,
+    /// Longer than `MAX_LINE_BYTES`; holds the first `MAX_LINE_BYTES` and the full length.
+    Oversized(usize),
+    EndOfStream,
+}
+
+/// Reads the next line into `line`, without its line ending (`\n` or `\r\n`). At most
+/// `MAX_LINE_BYTES` are buffered; the rest of a longer line is read and discarded.
+pub fn read_line_capped(reader: &mut impl BufRead, line: &mut Vec<u8>) -> io::Result<Line> {
+    line.clear();
+    let mut total = 0;
+    let mut ends_with_cr = false;
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let (chunk, used, done) = match available.iter().position(|&byte| byte == b'\n') {
+            Some(end) => (&available[..end], end + 1, true),
+            None if available.is_empty() && total == 0 => return Ok(Line::EndOfStream),
+            None => (available, available.len(), available.is_empty()),
+        };
+        if let Some(&last) = chunk.last() {
+            ends_with_cr = last == b'\r';
+        }
+        let room = MAX_LINE_BYTES.saturating_sub(line.len());
+        line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        total += chunk.len();
+        reader.consume(used);
+        if done {
+            if ends_with_cr {
+                total -= 1;
+                line.truncate(total);
+            }
+            return Ok(if total > MAX_LINE_BYTES {
+                Line::Oversized(total)
+            } else {
+                Line::Complete
+            });
+        }
+    }
+}
+
+fn skip_whitespace(line: &[u8], mut pos: usize) -> usize {
+    while line.get(pos).is_some_and(u8::is_ascii_whitespace) {
+        pos += 1;
+    }
+    pos
+}
+
+/// End of the JSON string starting at `pos`, past its closing quote.
+fn skip_string(line: &[u8], mut pos: usize) -> Option<usize> {
+    pos += 1;
+    loop {
+        match line.get(pos)? {
+            b'"' => return Some(pos + 1),
+            b'\\' => pos += 2,
+            _ => pos += 1,
+        }
+    }
+}
+
+/// End of the JSON value starting at `pos`; `None` if the line ends first.
+fn skip_value(line: &[u8], mut pos: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    loop {
+        match line.get(pos)? {
+            b'"' => pos = skip_string(line, pos)?,
+            b'{' | b'[' => {
+                depth += 1;
+                pos += 1;
+            }
+            b'}' | b']' if depth > 0 => {
+                depth -= 1;
+                pos += 1;
+            }
+            b',' | b'}' | b']' if depth == 0 => return Some(pos),
+            byte if byte.is_ascii_whitespace() && depth == 0 => return Some(pos),
+            _ => pos += 1,
+        }
+        if depth == 0 && matches!(line.get(pos - 1), Some(b'"' | b'}' | b']')) {
+            return Some(pos);
+        }
+    }
+}
+
+/// The id of the JSON-RPC response in `line`, which may be cut short or not parse as a
+/// message. Top-level members are scanned in order, so the id is found when it and the
+/// `result` or `error` key come before the cut; requests and notifications give `None`.
+pub fn response_id(line: &[u8]) -> Option<String> {
+    let mut pos = skip_whitespace(line, 0);
+    if line.get(pos) != Some(&b'{') {
+        return None;
+    }
+    pos += 1;
+    let mut id = None;
+    let mut is_response = false;
+    loop {
+        pos = skip_whitespace(line, pos);
+        if line.get(pos) != Some(&b'"') {
+            return None;
+        }
+        let key_end = skip_string(line, pos)?;
+        let key = &line[pos + 1..key_end - 1];
+        pos = skip_whitespace(line, key_end);
+        if line.get(pos) != Some(&b':') {
+            return None;
+        }
+        pos = skip_whitespace(line, pos + 1);
+        match key {
+            b"method" => return None,
+            b"result" | b"error" => is_response = true,
+            _ => {}
+        }
+        if is_response && id.is_some() {
+            return id;
+        }
+        let value_end = skip_value(line, pos)?;
+        if key == b"id" {
+            id = match serde_json::from_slice(&line[pos..value_end]).ok()? {
+                serde_json::Value::String(id) => Some(id),
+                serde_json::Value::Number(id) => Some(id.to_string()),
+                _ => return None,
+            };
+        }
+        if is_response && id.is_some() {
+            return id;
+        }
+        pos = skip_whitespace(line, value_end);
+        if line.get(pos) != Some(&b',') {
+            return None;
+        }
+        pos += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    fn read_all(data: Vec<u8>, capacity: usize) -> Vec<(Vec<u8>, Option<usize>)> {
+        let mut reader = BufReader::with_capacity(capacity, Cursor::new(data));
+        let mut line = Vec::new();
+        let mut lines = Vec::new();
+        loop {
+            match read_line_capped(&mut reader, &mut line).unwrap() {
+                Line::Complete => lines.push((line.clone(), None)),
+                Line::Oversized(length) => lines.push((line.clone(), Some(length))),
+                Line::EndOfStream => return lines,
+            }
+        }
+    }
+
+    #[test]
+    fn line_of_exactly_the_cap_is_complete() {
+        let mut data = vec![b'a'; MAX_LINE_BYTES];
+        data.extend_from_slice(b"\nnext\n");
+        let lines = read_all(data, 64 * 1024);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].0.len(), MAX_LINE_BYTES);
+        assert_eq!(lines[0].1, None);
+        assert_eq!(lines[1], (b"next".to_vec(), None));
+    }
+
+    #[test]
+    fn line_over_the_cap_is_cut() {
+        let mut data = vec![b'a'; MAX_LINE_BYTES + 1];
+        data.extend_from_slice(b"\r\nnext\n");
+        let lines = read_all(data, 64 * 1024);
+        assert_eq!(lines[0].0.len(), MAX_LINE_BYTES);
+        assert_eq!(lines[0].1, Some(MAX_LINE_BYTES + 1));
+        assert_eq!(lines[1], (b"next".to_vec(), None));
+    }
+
+    #[test]
+    fn crlf_endings_are_stripped() {
+        // A small buffer splits `\r` and `\n` across reads.
+        let lines = read_all(b"{\"id\":1}\r\nabcdef\r\n\r\n".to_vec(), 7);
+        assert_eq!(
+            lines,
+            vec![
+                (b"{\"id\":1}".to_vec(), None),
+                (b"abcdef".to_vec(), None),
+                (Vec::new(), None),
+            ]
+        );
+
+        let mut data = vec![b'a'; MAX_LINE_BYTES];
+        data.extend_from_slice(b"\r\n");
+        let lines = read_all(data, 64 * 1024);
+        assert_eq!(lines[0].0.len(), MAX_LINE_BYTES);
+        assert_eq!(lines[0].1, None);
+    }
+
+    #[test]
+    fn last_line_without_newline() {
+        assert_eq!(
+            read_all(b"first\nlast".to_vec(), 3),
+            vec![(b"first".to_vec(), None), (b"last".to_vec(), None)]
+        );
+        assert_eq!(
+            read_all(b"last\r".to_vec(), 3),
+            vec![(b"last".to_vec(), None)]
+        );
+        assert!(read_all(Vec::new(), 3).is_empty());
+    }
+
+    #[test]
+    fn server_versions_from_user_agents() {
+        assert_eq!(
+            parse_server_version("codex_cli_rs/0.63.0 (Mac OS 15.1.0; arm64) vscode/1.2.3"),
+            Some((0, 63, 0))
+        );
+        assert_eq!(
+            parse_server_version("codex_cli_rs/0.64.0-alpha.3 (Ubuntu 24.4.0; x86_64)"),
+            Some((0, 64, 0))
+        );
+        assert_eq!(
+            parse_server_version("codex_cli_rs/0.63.1+build.7"),
+            Some((0, 63, 1))
+        );
+        assert_eq!(parse_server_version("codex_cli_rs/0.0.0 (dev)"), None);
+        assert_eq!(parse_server_version("codex"), None);
+        assert_eq!(parse_server_version(""), None);
+        assert_eq!(parse_server_version("codex_cli_rs/latest"), None);
+    }
+
+    #[test]
+    fn compatibility_follows_the_pinned_minor() {
+        let status = |ua: &str| check_compatibility(ua).status;
+        assert_eq!(
+            status("codex_cli_rs/0.63.0"),
+            CompatibilityStatus::Compatible
+        );
+        assert_eq!(
+            status("codex_cli_rs/0.63.9-alpha.1"),
+            CompatibilityStatus::Compatible
+        );
+        assert_eq!(
+            status("codex_cli_rs/0.62.4"),
+            CompatibilityStatus::Unsupported
+        );
+        assert_eq!(
+            status("codex_cli_rs/0.64.0"),
+            CompatibilityStatus::Unsupported
+        );
+        assert_eq!(status("codex_cli_rs/0.0.0"), CompatibilityStatus::Unknown);
+    }
+
+    #[test]
+    fn response_ids_of_unreadable_lines() {
+        assert_eq!(
+            response_id(br#"{"id":7,"result":{"thread":"#),
+            Some("7".to_string())
+        );
+        assert_eq!(
+            response_id(br#" { "id" : "abc", "error": {"code": -1"#),
+            Some("abc".to_string())
+        );
+        assert_eq!(
+            response_id(br#"{"result":{"a":[1,{"b":"}"}]},"id":3} trailing"#),
+            Some("3".to_string())
+        );
+        // Requests and notifications, and lines cut before the id.
+        assert_eq!(
+            response_id(br#"{"id":4,"method":"item/commandExecution"#),
+            None
+        );
+        assert_eq!(response_id(br#"{"method":"turn/started","params":{"#), None);
+        assert_eq!(response_id(br#"{"result":{"thread":{"id":"t"#), None);
+        assert_eq!(response_id(br#"{"id":5"#), None);
+        assert_eq!(response_id(b"not json"), None);
+    }
+}
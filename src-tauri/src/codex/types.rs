@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use crate::codex::command_analysis::CommandDetail;
+use crate::codex::file_changes::FileChangeDetail;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ApprovalRequest {
@@ -17,8 +20,14 @@ pub struct ApprovalRequest {
 pub enum ApprovalRequestKind {
     CommandExecution {
         proposed_execpolicy_amendment: Option<Vec<String>>,
+        /// The command, its parsed actions and risk assessment, when known.
+        #[serde(default)]
+        details: Option<CommandDetail>,
     },
     FileChange {
         grant_root: Option<String>,
+        /// Changes of the item awaiting approval, when its `item/started` was seen.
+        #[serde(default)]
+        files: Vec<FileChangeDetail>,
     },
 }
@@ -1,26 +1,153 @@
+use crate::codex::error::ClientError;
 use crate::codex::handles::CodexClientHandle;
+use crate::codex::protocol::ProtocolStatus;
+use crate::codex::types::ApprovalRequest;
 use crate::state::AppState;
 use anyhow::Result;
 use codex_app_server_protocol::{
-    ApprovalDecision, ExecPolicyAmendment, RequestId, ThreadListParams, ThreadListResponse,
+    ApprovalDecision, ExecPolicyAmendment, GetAccountParams, GetAccountResponse,
+    LoginAccountParams, LoginAccountResponse, LogoutAccountResponse, RequestId,
+    ThreadListParams, ThreadListResponse,
     ThreadResumeParams, ThreadResumeResponse, ThreadStartParams, ThreadStartResponse,
     TurnInterruptParams, TurnInterruptResponse, TurnStartParams, TurnStartResponse,
 };
-use log::{debug, error, info};
+use crate::checkpoints;
+use crate::projects;
+use crate::prompts::{self, PromptContext};
+use crate::thread_metadata::{self, ThreadMetadata, ThreadMetadataFilter};
+use crate::tray;
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
-use tauri::State;
-
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tauri::{Manager, State};
+
+/// Error of the Codex commands, tagged with a stable `kind` so the frontend can tell failures
+/// apart. Every kind carries a readable `message`; JSON-RPC errors keep the server's code and
+/// data.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct CodexError {
-    pub message: String,
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum CodexError {
+    BinaryNotFound {
+        message: String,
+    },
+    NotInitialized {
+        message: String,
+    },
+    ProcessExited {
+        message: String,
+    },
+    Timeout {
+        message: String,
+    },
+    Rpc {
+        code: i64,
+        message: String,
+        #[serde(default)]
+        data: Option<Value>,
+    },
+    /// The response didn't match the expected type; `raw` is the JSON the app-server sent.
+    IncompatibleResponse {
+        message: String,
+        raw: Value,
+    },
+    Config {
+        message: String,
+    },
+    Io {
+        message: String,
+    },
+    Internal {
+        message: String,
+    },
+}
+
+impl CodexError {
+    pub fn config(message: impl Into<String>) -> Self {
+        CodexError::Config {
+            message: message.into(),
+        }
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        CodexError::Io {
+            message: message.into(),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        CodexError::Internal {
+            message: message.into(),
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            CodexError::BinaryNotFound { message }
+            | CodexError::NotInitialized { message }
+            | CodexError::ProcessExited { message }
+            | CodexError::Timeout { message }
+            | CodexError::Rpc { message, .. }
+            | CodexError::IncompatibleResponse { message, .. }
+            | CodexError::Config { message }
+            | CodexError::Io { message }
+            | CodexError::Internal { message } => message,
+        }
+    }
+
+    /// The string at `pointer` in the JSON of an incompatible response. The request may
+    /// still have taken effect, so callers use this to find the thread or turn it created.
+    pub fn recovered_id(&self, pointer: &str) -> Option<String> {
+        match self {
+            CodexError::IncompatibleResponse { raw, .. } => {
+                raw.pointer(pointer).and_then(Value::as_str).map(str::to_string)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for CodexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
 }
 
 impl From<anyhow::Error> for CodexError {
     fn from(err: anyhow::Error) -> Self {
-        CodexError {
-            message: err.to_string(),
+        // The whole chain, so context added on the way up isn't lost.
+        let message = format!("{:#}", err);
+        for cause in err.chain() {
+            if let Some(client) = cause.downcast_ref::<ClientError>() {
+                return match client.clone() {
+                    ClientError::BinaryNotFound => CodexError::BinaryNotFound { message },
+                    ClientError::NotInitialized => CodexError::NotInitialized { message },
+                    ClientError::ProcessExited => CodexError::ProcessExited { message },
+                    ClientError::Timeout { .. } => CodexError::Timeout { message },
+                    ClientError::Rpc {
+                        code,
+                        message,
+                        data,
+                    } => CodexError::Rpc {
+                        code,
+                        message,
+                        data,
+                    },
+                    ClientError::IncompatibleResponse { raw, .. } => {
+                        CodexError::IncompatibleResponse { message, raw }
+                    }
+                    ClientError::UnreadableResponse { .. } => CodexError::Internal { message },
+                };
+            }
+            if cause.is::<std::io::Error>() {
+                return CodexError::Io { message };
+            }
+            if cause.is::<toml::de::Error>() {
+                return CodexError::Config { message };
+            }
         }
+        CodexError::Internal { message }
     }
 }
 
@@ -50,6 +177,160 @@ pub async fn codex_initialize(
     Ok(())
 }
 
+/// Interrupts running turns and stops the app-server.
+#[tauri::command]
+pub async fn codex_shutdown(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), CodexError> {
+    info!("Shutting down Codex client");
+    state.shutdown_client()?;
+    tray::refresh(&app);
+    Ok(())
+}
+
+/// Stops the app-server and starts a fresh one. Threads have to be resumed afterwards.
+#[tauri::command]
+pub async fn codex_restart(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<(), CodexError> {
+    info!("Restarting Codex client");
+    state.shutdown_client()?;
+    state.get_or_init_client(&app)?;
+    tray::refresh(&app);
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodexStatus {
+    pub initialized: bool,
+    /// Connection state and protocol error counts of the running client.
+    pub protocol: Option<ProtocolStatus>,
+}
+
+#[tauri::command]
+pub async fn codex_status(state: State<'_, AppState>) -> Result<CodexStatus, CodexError> {
+    let client = state
+        .codex_client
+        .lock()
+        .map_err(|e| CodexError::Internal {
+            message: format!("Failed to acquire lock: {}", e),
+        })?;
+    let protocol = client.as_ref().map(CodexClientHandle::protocol_status);
+    Ok(CodexStatus {
+        initialized: protocol.is_some(),
+        protocol,
+    })
+}
+
+/// Checks the working directory before the app-server is involved and fills in the project's
+/// defaults for whatever the caller left unset. Returns the canonical project directory.
+fn prepare_thread_start(
+    params: ThreadStartParams,
+    state: &AppState,
+) -> Result<(ThreadStartParams, Option<PathBuf>)> {
+    let mut value = serde_json::to_value(&params)?;
+    let Some(cwd) = value.get("cwd").and_then(|cwd| cwd.as_str()).map(str::to_string) else {
+        warn!("thread_start without cwd, the app-server will use its own working directory");
+        return Ok((params, None));
+    };
+    let dir = projects::validate_directory(&cwd).map_err(anyhow::Error::msg)?;
+
+    let defaults = state
+        .projects
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to acquire lock: {}", e))?
+        .defaults_for(&dir);
+    let Some(defaults) = defaults else {
+        debug!("{} is not a registered project", dir.display());
+        return Ok((params, Some(dir)));
+    };
+
+    projects::apply_defaults(&mut value, &defaults);
+    match serde_json::from_value(value) {
+        Ok(with_defaults) => Ok((with_defaults, Some(dir))),
+        Err(e) => {
+            warn!("Ignoring project defaults for {}: {}", dir.display(), e);
+            Ok((params, Some(dir)))
+        }
+    }
+}
+
+/// Expands a prompt template invoked as `/name ...` in the turn's first text input. The
+/// working directory defaults to the one the thread runs in.
+fn expand_turn_prompt(
+    params: TurnStartParams,
+    context: Option<PromptContext>,
+    state: &AppState,
+) -> Result<TurnStartParams> {
+    let mut value = serde_json::to_value(&params)?;
+    let Some(text) = value
+        .pointer_mut("/input")
+        .and_then(|input| input.as_array_mut())
+        .and_then(|input| {
+            input
+                .iter_mut()
+                .find(|item| item.get("type").and_then(|t| t.as_str()) == Some("text"))
+        })
+        .and_then(|item| item.get_mut("text"))
+    else {
+        return Ok(params);
+    };
+    if !text.as_str().is_some_and(|text| text.trim_start().starts_with('/')) {
+        return Ok(params);
+    }
+
+    let mut context = context.unwrap_or_default();
+    if context.cwd.is_none() {
+        context.cwd = params_cwd(&params).or_else(|| {
+            state
+                .checkpoints
+                .lock()
+                .ok()
+                .and_then(|mut checkpoints| checkpoints.thread_cwd(&params.thread_id))
+        });
+    }
+    let Some(expanded) = prompts::expand_command(text.as_str().unwrap_or_default(), &context)
+        .map_err(anyhow::Error::msg)?
+    else {
+        return Ok(params);
+    };
+    debug!("Expanded prompt /{:?} for thread {}", expanded.name, params.thread_id);
+    *text = serde_json::Value::String(expanded.text);
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Appends the staged attachments to the turn's input.
+fn attach_files(
+    params: TurnStartParams,
+    attachment_ids: &[String],
+    state: &AppState,
+) -> Result<TurnStartParams> {
+    if attachment_ids.is_empty() {
+        return Ok(params);
+    }
+    let items = state
+        .attachments
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to acquire lock: {}", e))?
+        .input_items(&params.thread_id, attachment_ids)
+        .map_err(anyhow::Error::msg)?;
+    let mut value = serde_json::to_value(&params)?;
+    let Some(input) = value.get_mut("input").and_then(|input| input.as_array_mut()) else {
+        anyhow::bail!("turn/start parameters have no input list");
+    };
+    input.extend(items);
+    Ok(serde_json::from_value(value)?)
+}
+
+fn params_cwd(params: &TurnStartParams) -> Option<String> {
+    serde_json::to_value(params)
+        .ok()
+        .and_then(|value| value.get("cwd").and_then(|cwd| cwd.as_str().map(str::to_string)))
+}
+
 #[tauri::command]
 pub async fn thread_start(
     params: ThreadStartParams,
@@ -58,21 +339,77 @@ pub async fn thread_start(
 ) -> Result<ThreadStartResponse, CodexError> {
     info!("thread_start called with params: {:?}", params);
 
+    let (params, project_dir) = prepare_thread_start(params, &state).map_err(|e| {
+        error!("thread_start rejected: {}", e);
+        e
+    })?;
+
     let handle = state.get_or_init_client(&app).map_err(|e| {
         error!("Failed to get or initialize client: {}", e);
         e
     })?;
 
     info!("Calling handle.thread_start");
-    let response = handle.thread_start(params).map_err(|e| {
-        error!("thread_start execution failed: {}", e);
-        e
-    })?;
+    let cwd = params.cwd.clone();
+    let response = match handle.thread_start(params) {
+        Ok(response) => response,
+        Err(e) => {
+            error!("thread_start execution failed: {}", e);
+            let e = CodexError::from(e);
+            // An unreadable response doesn't mean the thread wasn't started.
+            if let Some(thread_id) = e.recovered_id("/thread/id") {
+                let model = e.recovered_id("/model");
+                let project_dir = project_dir.as_deref();
+                record_started_thread(&state, project_dir, Some(&thread_id), cwd, model);
+            }
+            return Err(e);
+        }
+    };
+
+    let thread_id = serde_json::to_value(&response.thread)
+        .ok()
+        .and_then(|thread| thread.get("id").and_then(|id| id.as_str().map(str::to_string)));
+    record_started_thread(
+        &state,
+        project_dir.as_deref(),
+        thread_id.as_deref(),
+        cwd,
+        Some(response.model.clone()),
+    );
 
     info!("thread_start completed successfully, thread_id: {:?}", response.thread);
     Ok(response)
 }
 
+/// Bookkeeping for a thread the app-server has started: the project is touched, usage and
+/// checkpoints are attributed to the directory it was started in, and it's tracked as loaded.
+fn record_started_thread(
+    state: &AppState,
+    project_dir: Option<&Path>,
+    thread_id: Option<&str>,
+    cwd: Option<String>,
+    model: Option<String>,
+) {
+    if let Some(dir) = project_dir
+        && let Ok(mut projects) = state.projects.lock()
+    {
+        projects.touch(dir);
+    }
+
+    let Some(thread_id) = thread_id else {
+        return;
+    };
+    if let (Some(cwd), Ok(mut checkpoints)) = (&cwd, state.checkpoints.lock()) {
+        checkpoints.set_thread_cwd(thread_id, cwd);
+    }
+    if let Ok(mut usage) = state.usage.lock() {
+        usage.set_thread_context(thread_id, cwd, model);
+    }
+    if let Ok(mut activity) = state.activity.lock() {
+        activity.track(thread_id);
+    }
+}
+
 #[tauri::command]
 pub async fn thread_resume(
     params: ThreadResumeParams,
@@ -90,53 +427,205 @@ pub async fn thread_resume(
         e
     })?;
 
+    // Resumed threads may predate the usage ledger; remember where their usage belongs.
+    if let Ok(thread) = serde_json::to_value(&response.thread)
+        && let Some(thread_id) = thread.get("id").and_then(Value::as_str)
+        && let Ok(mut usage) = state.usage.lock()
+    {
+        let cwd = thread.get("cwd").and_then(Value::as_str).map(str::to_string);
+        usage.set_thread_context(thread_id, cwd, Some(response.model.clone()));
+    }
+
     info!("thread_resume completed successfully");
     Ok(response)
 }
 
+/// Threads `thread_list` tries to collect when the caller sets no limit.
+const DEFAULT_THREAD_LIST_LIMIT: usize = 25;
+/// Server pages `thread_list` reads at most per call while filling the limit.
+const MAX_THREAD_LIST_PAGES: usize = 10;
+
+/// `ThreadListResponse` with the local metadata of every returned thread merged in.
+///
+/// Threads not matching the metadata filter are dropped, and further server pages are read
+/// until `limit` threads match or `MAX_THREAD_LIST_PAGES` pages were read; `nextCursor`
+/// continues after the last page read. Pinned threads come first within the returned batch
+/// only, not across batches.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadListResult {
+    #[serde(flatten)]
+    pub response: ThreadListResponse,
+    pub metadata: HashMap<String, ThreadMetadata>,
+}
+
 #[tauri::command]
 pub async fn thread_list(
     params: ThreadListParams,
+    filter: Option<ThreadMetadataFilter>,
     state: State<'_, AppState>,
     app: tauri::AppHandle,
-) -> Result<ThreadListResponse, CodexError> {
+) -> Result<ThreadListResult, CodexError> {
     debug!("thread_list called with params: {:?}", params);
-
-    let handle = state.get_or_init_client(&app).map_err(|e| {
+    if params.cursor.is_none() {
+        thread_metadata::schedule_prune(&app);
+    }
+    let wanted = params
+        .limit
+        .map_or(DEFAULT_THREAD_LIST_LIMIT, |limit| limit as usize);
+    let filter = filter.unwrap_or_default();
+
+    // Each page holds the client lock only for its own request, so other commands get
+    // through between pages; filtering happens after it is released.
+    drop(state.get_or_init_client(&app).map_err(|e| {
         error!("thread_list failed: {}", e);
         e
-    })?;
+    })?);
+    let fetch_page = |params: ThreadListParams| -> Result<ThreadListResponse, CodexError> {
+        let mut page = state.get_client()?.thread_list(params).map_err(|e| {
+            error!("thread_list execution failed: {}", e);
+            e
+        })?;
+        let mut store = state
+            .thread_metadata
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire lock: {}", e))?;
+        page.data.retain(|thread| store.matches(&thread.id, &filter));
+        Ok(page)
+    };
+    let mut response = fetch_page(params.clone())?;
+    let mut pages = 1;
+    while response.data.len() < wanted
+        && pages < MAX_THREAD_LIST_PAGES
+        && let Some(cursor) = response.next_cursor.take()
+    {
+        let mut next = params.clone();
+        next.cursor = Some(cursor);
+        let mut page = fetch_page(next)?;
+        response.data.append(&mut page.data);
+        response.next_cursor = page.next_cursor;
+        pages += 1;
+    }
 
-    let response = handle.thread_list(params).map_err(|e| {
-        error!("thread_list execution failed: {}", e);
-        e
-    })?;
+    let mut store = state
+        .thread_metadata
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to acquire lock: {}", e))?;
+    let metadata: HashMap<String, ThreadMetadata> = response
+        .data
+        .iter()
+        .map(|thread| (thread.id.clone(), store.get(&thread.id)))
+        .collect();
+    // Pinned threads first; the server order is kept otherwise.
+    response
+        .data
+        .sort_by_key(|thread| !metadata.get(&thread.id).is_some_and(|m| m.pinned));
 
     info!("thread_list completed successfully");
-    Ok(response)
+    Ok(ThreadListResult { response, metadata })
 }
 
 #[tauri::command]
 pub async fn turn_start(
     params: TurnStartParams,
+    prompt_context: Option<PromptContext>,
+    attachments: Option<Vec<String>>,
     state: State<'_, AppState>,
+    app: tauri::AppHandle,
 ) -> Result<TurnStartResponse, CodexError> {
     debug!("turn_start called with params: {:?}", params);
 
-    let handle = state.get_client().map_err(|e| {
+    let attachments = attachments.unwrap_or_default();
+    let params = expand_turn_prompt(params, prompt_context, &state)
+        .and_then(|params| attach_files(params, &attachments, &state))
+        .map_err(|e| {
+            error!("turn_start rejected: {}", e);
+            e
+        })?;
+
+    let thread_id = params.thread_id.clone();
+    let cwd = params_cwd(&params);
+    drop(state.get_client().map_err(|e| {
         error!("turn_start failed: {}", e);
         e
-    })?;
+    })?);
+
+    // Snapshot the workspace first so the turn can be rolled back. A skipped or failed
+    // snapshot is logged but never blocks the turn.
+    let snapshot = {
+        let app = app.clone();
+        let thread_id = thread_id.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            checkpoints::snapshot_before_turn(
+                &app.state::<AppState>().checkpoints,
+                &thread_id,
+                cwd.as_deref(),
+            )
+        })
+        .await
+    };
+    let checkpoint = match snapshot {
+        Ok(Ok(checkpoint)) => checkpoint,
+        Ok(Err(e)) => {
+            error!("Failed to create checkpoint for thread {}: {}", thread_id, e);
+            None
+        }
+        Err(e) => {
+            error!("Checkpoint task for thread {} failed: {}", thread_id, e);
+            None
+        }
+    };
 
-    let response = handle.turn_start(params).map_err(|e| {
-        error!("turn_start execution failed: {}", e);
+    let handle = state.get_client().map_err(|e| {
+        error!("turn_start failed: {}", e);
         e
     })?;
 
+    let response = match handle.turn_start(params) {
+        Ok(response) => response,
+        Err(e) => {
+            error!("turn_start execution failed: {}", e);
+            let e = CodexError::from(e);
+            // The turn may be running even though its response couldn't be read.
+            if let Some(turn_id) = e.recovered_id("/turn/id") {
+                record_started_turn(&state, &thread_id, &attachments, checkpoint, Some(turn_id));
+            }
+            return Err(e);
+        }
+    };
+
+    let turn_id = serde_json::to_value(&response)
+        .ok()
+        .and_then(|value| value.pointer("/turn/id").and_then(|id| id.as_str().map(str::to_string)));
+    record_started_turn(&state, &thread_id, &attachments, checkpoint, turn_id);
+
     info!("turn_start completed successfully");
     Ok(response)
 }
 
+/// Bookkeeping for a turn the app-server has started: its attachments count as sent and the
+/// checkpoint taken before it is bound to its id.
+fn record_started_turn(
+    state: &AppState,
+    thread_id: &str,
+    attachments: &[String],
+    checkpoint: Option<checkpoints::Checkpoint>,
+    turn_id: Option<String>,
+) {
+    if !attachments.is_empty()
+        && let Ok(mut store) = state.attachments.lock()
+    {
+        store.mark_sent(thread_id, attachments);
+    }
+
+    if let Some(checkpoint) = checkpoint
+        && let Some(turn_id) = turn_id
+        && let Ok(mut checkpoints) = state.checkpoints.lock()
+    {
+        checkpoints.set_turn_id(thread_id, &checkpoint.id, turn_id);
+    }
+}
+
 #[tauri::command]
 pub async fn turn_interrupt(
     params: TurnInterruptParams,
@@ -180,6 +669,7 @@ pub enum ApprovalDecisionType {
 pub async fn respond_to_approval(
     response: ApprovalResponse,
     state: State<'_, AppState>,
+    app: tauri::AppHandle,
 ) -> Result<(), CodexError> {
     debug!("respond_to_approval called with request_id: {}, decision: {:?}",
            response.request_id, response.decision);
@@ -189,7 +679,7 @@ pub async fn respond_to_approval(
         e
     })?;
 
-    let decision = match response.decision {
+    let decision = match response.decision.clone() {
         ApprovalDecisionType::Accept => ApprovalDecision::Accept,
         ApprovalDecisionType::AcceptForSession => ApprovalDecision::AcceptForSession,
         ApprovalDecisionType::AcceptWithExecpolicyAmendment { execpolicy_amendment } => {
@@ -210,6 +700,94 @@ pub async fn respond_to_approval(
         e
     })?;
 
+    if let Ok(bridge) = state.bridge.lock() {
+        bridge.publish(&serde_json::json!({
+            "method": "approval/resolved",
+            "params": { "requestId": response.request_id, "decision": response.decision },
+        }));
+    }
+    if let Ok(mut approvals) = state.approvals.lock() {
+        approvals.resolve(&response.request_id, response.decision);
+    }
+    tray::refresh(&app);
+
     info!("respond_to_approval completed successfully for request_id: {}", response.request_id);
     Ok(())
 }
+
+#[tauri::command]
+pub async fn list_pending_approvals(
+    state: State<'_, AppState>,
+) -> Result<Vec<ApprovalRequest>, CodexError> {
+    let approvals = state
+        .approvals
+        .lock()
+        .map_err(|e| anyhow::anyhow!("Failed to acquire lock: {}", e))?;
+    Ok(approvals.pending())
+}
+
+#[tauri::command]
+pub async fn account_read(
+    params: GetAccountParams,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<GetAccountResponse, CodexError> {
+    debug!("account_read called with params: {:?}", params);
+
+    let handle = state.get_or_init_client(&app).map_err(|e| {
+        error!("account_read failed: {}", e);
+        e
+    })?;
+
+    let response = handle.get_account(params).map_err(|e| {
+        error!("account_read execution failed: {}", e);
+        e
+    })?;
+
+    info!("account_read completed successfully");
+    Ok(response)
+}
+
+#[tauri::command]
+pub async fn account_login(
+    params: LoginAccountParams,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<LoginAccountResponse, CodexError> {
+    // The params may carry an API key, so they are deliberately not logged.
+    debug!("account_login called");
+
+    let handle = state.get_or_init_client(&app).map_err(|e| {
+        error!("account_login failed: {}", e);
+        e
+    })?;
+
+    let response = handle.login_account(params).map_err(|e| {
+        error!("account_login execution failed: {}", e);
+        e
+    })?;
+
+    info!("account_login completed successfully");
+    Ok(response)
+}
+
+#[tauri::command]
+pub async fn account_logout(
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<LogoutAccountResponse, CodexError> {
+    debug!("account_logout called");
+
+    let handle = state.get_or_init_client(&app).map_err(|e| {
+        error!("account_logout failed: {}", e);
+        e
+    })?;
+
+    let response = handle.logout_account().map_err(|e| {
+        error!("account_logout execution failed: {}", e);
+        e
+    })?;
+
+    info!("account_logout completed successfully");
+    Ok(response)
+}
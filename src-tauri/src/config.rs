@@ -18,11 +18,29 @@ pub struct ModelProviderConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileConfig {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub model_provider: Option<String>,
+    #[serde(default)]
+    pub model_reasoning_effort: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct CodexConfig {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub model_provider: Option<String>,
+    #[serde(default)]
+    pub model_reasoning_effort: Option<String>,
     #[serde(default)]
     pub projects: HashMap<String, ProjectConfig>,
     #[serde(default)]
     pub model_providers: HashMap<String, ModelProviderConfig>,
+    #[serde(default)]
+    pub profiles: HashMap<String, ProfileConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,19 +49,26 @@ pub struct Project {
     pub trust_level: String,
 }
 
-pub fn get_config_path() -> Result<PathBuf, String> {
+/// Resolves the Codex home directory, honouring `CODEX_HOME` like the Codex CLI does.
+pub fn get_codex_home() -> Result<PathBuf, String> {
+    if let Ok(explicit) = std::env::var("CODEX_HOME")
+        && !explicit.is_empty()
+    {
+        return Ok(PathBuf::from(explicit));
+    }
     let home_dir = dirs::home_dir().ok_or("Could not find home directory")?;
-    Ok(home_dir.join(".codex").join("config.toml"))
+    Ok(home_dir.join(".codex"))
+}
+
+pub fn get_config_path() -> Result<PathBuf, String> {
+    Ok(get_codex_home()?.join("config.toml"))
 }
 
-fn load_codex_config() -> Result<CodexConfig, String> {
+pub fn load_codex_config() -> Result<CodexConfig, String> {
     let config_path = get_config_path()?;
 
     if !config_path.exists() {
-        return Ok(CodexConfig {
-            projects: HashMap::new(),
-            model_providers: HashMap::new(),
-        });
+        return Ok(CodexConfig::default());
     }
 
     let content = fs::read_to_string(&config_path)
@@ -0,0 +1,881 @@
+use chrono::{DateTime, Utc};
+use codex_app_server_protocol::ThreadResumeParams;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::State;
+use tauri_plugin_dialog::DialogExt;
+
+use crate::codex::approvals::ApprovalRecord;
+use crate::commands::CodexError;
+use crate::sessions::{self, RolloutEntry, SessionGitInfo};
+use crate::state::AppState;
+
+const TITLE_MAX_CHARS: usize = 72;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    fn filter_name(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Html => "HTML",
+            ExportFormat::Json => "JSON",
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    #[serde(default)]
+    pub include_reasoning: bool,
+    #[serde(default = "default_true")]
+    pub include_command_output: bool,
+    #[serde(default = "default_true")]
+    pub include_diffs: bool,
+    #[serde(default = "default_true")]
+    pub include_approvals: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportThreadParams {
+    pub thread_id: String,
+    #[serde(flatten)]
+    pub options: ExportOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportResult {
+    pub path: String,
+    pub format: ExportFormat,
+    pub bytes: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ExportedItem {
+    UserMessage {
+        text: String,
+    },
+    AgentMessage {
+        text: String,
+    },
+    Reasoning {
+        text: String,
+    },
+    Command {
+        command: String,
+        output: Option<String>,
+    },
+    FileChange {
+        paths: Vec<String>,
+        diff: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedTurn {
+    pub index: usize,
+    pub items: Vec<ExportedItem>,
+}
+
+/// Normalized view of a thread, independent of whether it came from a rollout or the app-server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedThread {
+    pub id: String,
+    pub title: String,
+    pub cwd: Option<String>,
+    pub model: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub git: Option<SessionGitInfo>,
+    pub turns: Vec<ExportedTurn>,
+    pub approvals: Vec<ApprovalRecord>,
+}
+
+impl ExportedThread {
+    fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            title: String::new(),
+            cwd: None,
+            model: None,
+            created_at: None,
+            git: None,
+            turns: Vec::new(),
+            approvals: Vec::new(),
+        }
+    }
+
+    fn current_turn(&mut self) -> &mut ExportedTurn {
+        if self.turns.is_empty() {
+            self.start_turn();
+        }
+        self.turns.last_mut().unwrap()
+    }
+
+    fn start_turn(&mut self) {
+        let index = self.turns.len() + 1;
+        self.turns.push(ExportedTurn {
+            index,
+            items: Vec::new(),
+        });
+    }
+
+    fn finish(mut self) -> Self {
+        self.turns.retain(|turn| !turn.items.is_empty());
+        let first_prompt =
+            self.turns
+                .iter()
+                .flat_map(|turn| &turn.items)
+                .find_map(|item| match item {
+                    ExportedItem::UserMessage { text } => Some(text.clone()),
+                    _ => None,
+                });
+        self.title = match first_prompt {
+            Some(prompt) => {
+                let line = prompt.lines().next().unwrap_or_default().trim();
+                if line.chars().count() > TITLE_MAX_CHARS {
+                    format!(
+                        "{}…",
+                        line.chars().take(TITLE_MAX_CHARS).collect::<String>()
+                    )
+                } else {
+                    line.to_string()
+                }
+            }
+            None => format!("Codex thread {}", self.id),
+        };
+        self
+    }
+}
+
+fn thread_from_rollout(thread_id: &str, path: &Path) -> Result<ExportedThread, String> {
+    let mut thread = ExportedThread::new(thread_id);
+    // Tool outputs arrive as separate records; remember where each call landed.
+    let mut calls: HashMap<String, (usize, usize)> = HashMap::new();
+
+    for record in sessions::read_rollout(path)? {
+        match record.entry {
+            RolloutEntry::Meta { cwd, git, .. } => {
+                thread.cwd = cwd;
+                thread.git = git;
+                thread.created_at = record.timestamp;
+            }
+            RolloutEntry::TurnContext { cwd, model } => {
+                thread.cwd = thread.cwd.or(cwd);
+                thread.model = thread.model.or(model);
+            }
+            RolloutEntry::UserMessage(text) if !sessions::is_injected_context(&text) => {
+                thread.start_turn();
+                thread
+                    .current_turn()
+                    .items
+                    .push(ExportedItem::UserMessage { text });
+            }
+            RolloutEntry::AgentMessage(text) => {
+                thread
+                    .current_turn()
+                    .items
+                    .push(ExportedItem::AgentMessage { text });
+            }
+            RolloutEntry::Reasoning(text) => {
+                thread
+                    .current_turn()
+                    .items
+                    .push(ExportedItem::Reasoning { text });
+            }
+            RolloutEntry::Command { call_id, command } => {
+                let turn = thread.current_turn();
+                turn.items.push(ExportedItem::Command {
+                    command,
+                    output: None,
+                });
+                if let Some(call_id) = call_id {
+                    calls.insert(call_id, (thread.turns.len() - 1, turn_len(&thread) - 1));
+                }
+            }
+            RolloutEntry::Patch { paths, patch, .. } => {
+                thread.current_turn().items.push(ExportedItem::FileChange {
+                    paths,
+                    diff: Some(patch),
+                });
+            }
+            RolloutEntry::CommandOutput { call_id, output } => {
+                let Some((turn_index, item_index)) = call_id.and_then(|id| calls.get(&id).copied())
+                else {
+                    continue;
+                };
+                if let Some(ExportedItem::Command { output: slot, .. }) = thread
+                    .turns
+                    .get_mut(turn_index)
+                    .and_then(|turn| turn.items.get_mut(item_index))
+                {
+                    *slot = Some(output);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(thread.finish())
+}
+
+fn turn_len(thread: &ExportedThread) -> usize {
+    thread.turns.last().map_or(0, |turn| turn.items.len())
+}
+
+/// Builds the export from the `Thread` returned by `thread/resume`, for threads whose
+/// rollout file is not available locally.
+fn thread_from_app_server(thread_id: &str, thread_value: &Value) -> ExportedThread {
+    let mut thread = ExportedThread::new(thread_id);
+    thread.cwd = thread_value
+        .get("cwd")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let turns = thread_value
+        .get("turns")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    for turn in turns {
+        thread.start_turn();
+        let items = turn
+            .get("items")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        for item in items {
+            if let Some(item) = exported_item_from_value(&item) {
+                thread.current_turn().items.push(item);
+            }
+        }
+    }
+
+    thread.finish()
+}
+
+fn exported_item_from_value(item: &Value) -> Option<ExportedItem> {
+    let text_of = |key: &str| item.get(key).and_then(Value::as_str).map(str::to_string);
+    let joined = |key: &str| {
+        item.get(key).and_then(Value::as_array).map(|parts| {
+            parts
+                .iter()
+                .filter_map(|part| part.as_str().or_else(|| part.get("text")?.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+    };
+
+    match item.get("type").and_then(Value::as_str)? {
+        "userMessage" => joined("content").map(|text| ExportedItem::UserMessage { text }),
+        "agentMessage" => text_of("text").map(|text| ExportedItem::AgentMessage { text }),
+        "reasoning" => joined("summary").map(|text| ExportedItem::Reasoning { text }),
+        "commandExecution" => Some(ExportedItem::Command {
+            command: match item.get("command") {
+                Some(Value::Array(argv)) => sessions::display_command(
+                    &argv
+                        .iter()
+                        .filter_map(|part| part.as_str().map(str::to_string))
+                        .collect::<Vec<_>>(),
+                ),
+                _ => text_of("command").unwrap_or_default(),
+            },
+            output: text_of("aggregatedOutput"),
+        }),
+        "fileChange" => {
+            let changes = item.get("changes").and_then(Value::as_array)?;
+            let paths = changes
+                .iter()
+                .filter_map(|change| change.get("path").and_then(Value::as_str))
+                .map(str::to_string)
+                .collect();
+            let diff = changes
+                .iter()
+                .filter_map(|change| change.get("diff").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join("\n");
+            Some(ExportedItem::FileChange {
+                paths,
+                diff: (!diff.is_empty()).then_some(diff),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Drops the parts of the thread the user chose not to export.
+fn apply_options(mut thread: ExportedThread, options: &ExportOptions) -> ExportedThread {
+    for turn in &mut thread.turns {
+        turn.items.retain(|item| {
+            options.include_reasoning || !matches!(item, ExportedItem::Reasoning { .. })
+        });
+        for item in &mut turn.items {
+            match item {
+                ExportedItem::Command { output, .. } if !options.include_command_output => {
+                    *output = None;
+                }
+                ExportedItem::FileChange { diff, .. } if !options.include_diffs => {
+                    *diff = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    if !options.include_approvals {
+        thread.approvals.clear();
+    }
+    thread
+}
+
+fn decision_label(record: &ApprovalRecord) -> String {
+    serde_json::to_value(&record.decision)
+        .ok()
+        .and_then(|value| match value {
+            Value::String(label) => Some(label),
+            Value::Object(map) => map.keys().next().cloned(),
+            _ => None,
+        })
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn approval_subject(record: &ApprovalRecord) -> String {
+    serde_json::to_value(&record.request.kind)
+        .ok()
+        .and_then(|kind| kind.get("type").and_then(Value::as_str).map(str::to_string))
+        .unwrap_or_else(|| "approval".to_string())
+}
+
+/// Picks a code fence longer than any backtick run inside the content.
+fn fence(content: &str) -> String {
+    let mut longest = 0;
+    let mut current = 0;
+    for c in content.chars() {
+        if c == '`' {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn code_block(out: &mut String, language: &str, content: &str) {
+    let fence = fence(content);
+    out.push_str(&format!(
+        "{}{}\n{}\n{}\n\n",
+        fence,
+        language,
+        content.trim_end(),
+        fence
+    ));
+}
+
+pub fn render_markdown(thread: &ExportedThread) -> String {
+    let mut out = format!("# {}\n\n", thread.title);
+    out.push_str(&format!("- **Thread:** `{}`\n", thread.id));
+    if let Some(cwd) = &thread.cwd {
+        out.push_str(&format!("- **Directory:** `{}`\n", cwd));
+    }
+    if let Some(model) = &thread.model {
+        out.push_str(&format!("- **Model:** `{}`\n", model));
+    }
+    if let Some(created_at) = &thread.created_at {
+        out.push_str(&format!("- **Started:** {}\n", created_at.to_rfc3339()));
+    }
+    if let Some(branch) = thread.git.as_ref().and_then(|git| git.branch.as_ref()) {
+        out.push_str(&format!("- **Branch:** `{}`\n", branch));
+    }
+    out.push('\n');
+
+    for turn in &thread.turns {
+        out.push_str(&format!("## Turn {}\n\n", turn.index));
+        for item in &turn.items {
+            match item {
+                ExportedItem::UserMessage { text } => {
+                    out.push_str("**User**\n\n");
+                    for line in text.lines() {
+                        out.push_str(&format!("> {}\n", line));
+                    }
+                    out.push('\n');
+                }
+                ExportedItem::AgentMessage { text } => {
+                    out.push_str(&format!("**Codex**\n\n{}\n\n", text.trim_end()));
+                }
+                ExportedItem::Reasoning { text } => {
+                    out.push_str(&format!(
+                        "<details><summary>Reasoning</summary>\n\n{}\n\n</details>\n\n",
+                        text.trim_end()
+                    ));
+                }
+                ExportedItem::Command { command, output } => {
+                    code_block(&mut out, "sh", &format!("$ {}", command));
+                    if let Some(output) = output.as_ref().filter(|o| !o.trim().is_empty()) {
+                        code_block(&mut out, "text", output);
+                    }
+                }
+                ExportedItem::FileChange { paths, diff } => {
+                    let list = paths
+                        .iter()
+                        .map(|path| format!("`{}`", path))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    out.push_str(&format!("**Changed files:** {}\n\n", list));
+                    if let Some(diff) = diff {
+                        code_block(&mut out, "diff", diff);
+                    }
+                }
+            }
+        }
+    }
+
+    if !thread.approvals.is_empty() {
+        out.push_str("## Approvals\n\n");
+        for record in &thread.approvals {
+            out.push_str(&format!(
+                "- {} {}: **{}**{}\n",
+                record.decided_at.to_rfc3339(),
+                approval_subject(record),
+                decision_label(record),
+                record
+                    .request
+                    .reason
+                    .as_ref()
+                    .map(|reason| format!(" — {}", reason))
+                    .unwrap_or_default()
+            ));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const HTML_STYLE: &str = "body{font-family:-apple-system,BlinkMacSystemFont,'Segoe UI',sans-serif;max-width:900px;margin:2rem auto;padding:0 1rem;color:#1f2328;line-height:1.5}\
+h1{font-size:1.6rem}h2{font-size:1.2rem;border-bottom:1px solid #d0d7de;padding-bottom:.3rem;margin-top:2rem}\
+.meta{color:#59636e;font-size:.9rem}.msg{white-space:pre-wrap;padding:.75rem 1rem;border-radius:8px;margin:.75rem 0}\
+.user{background:#ddf4ff}.agent{background:#f6f8fa}.reasoning{color:#59636e;font-style:italic}\
+pre{background:#0d1117;color:#e6edf3;padding:.75rem;border-radius:6px;overflow-x:auto;font-size:.85rem}\
+.add{color:#3fb950}.del{color:#f85149}.label{font-weight:600;font-size:.8rem;text-transform:uppercase;color:#59636e}";
+
+fn render_diff_html(diff: &str) -> String {
+    diff.lines()
+        .map(|line| {
+            let escaped = escape_html(line);
+            if line.starts_with('+') && !line.starts_with("+++") {
+                format!("<span class=\"add\">{}</span>", escaped)
+            } else if line.starts_with('-') && !line.starts_with("---") {
+                format!("<span class=\"del\">{}</span>", escaped)
+            } else {
+                escaped
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn render_html(thread: &ExportedThread) -> String {
+    let mut body = format!(
+        "<h1>{}</h1>\n<div class=\"meta\">",
+        escape_html(&thread.title)
+    );
+    body.push_str(&format!("Thread <code>{}</code>", escape_html(&thread.id)));
+    if let Some(cwd) = &thread.cwd {
+        body.push_str(&format!(" · <code>{}</code>", escape_html(cwd)));
+    }
+    if let Some(model) = &thread.model {
+        body.push_str(&format!(" · {}", escape_html(model)));
+    }
+    if let Some(created_at) = &thread.created_at {
+        body.push_str(&format!(" · {}", created_at.to_rfc3339()));
+    }
+    body.push_str("</div>\n");
+
+    for turn in &thread.turns {
+        body.push_str(&format!("<h2>Turn {}</h2>\n", turn.index));
+        for item in &turn.items {
+            match item {
+                ExportedItem::UserMessage { text } => body.push_str(&format!(
+                    "<div class=\"label\">User</div><div class=\"msg user\">{}</div>\n",
+                    escape_html(text)
+                )),
+                ExportedItem::AgentMessage { text } => body.push_str(&format!(
+                    "<div class=\"label\">Codex</div><div class=\"msg agent\">{}</div>\n",
+                    escape_html(text)
+                )),
+                ExportedItem::Reasoning { text } => body.push_str(&format!(
+                    "<details><summary>Reasoning</summary><div class=\"msg reasoning\">{}</div></details>\n",
+                    escape_html(text)
+                )),
+                ExportedItem::Command { command, output } => {
+                    body.push_str(&format!("<pre>$ {}</pre>\n", escape_html(command)));
+                    if let Some(output) = output.as_ref().filter(|o| !o.trim().is_empty()) {
+                        body.push_str(&format!(
+                            "<details><summary>Output</summary><pre>{}</pre></details>\n",
+                            escape_html(output)
+                        ));
+                    }
+                }
+                ExportedItem::FileChange { paths, diff } => {
+                    let list = paths
+                        .iter()
+                        .map(|path| format!("<code>{}</code>", escape_html(path)))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    body.push_str(&format!("<div class=\"label\">Changed files</div><div>{}</div>\n", list));
+                    if let Some(diff) = diff {
+                        body.push_str(&format!("<pre>{}</pre>\n", render_diff_html(diff)));
+                    }
+                }
+            }
+        }
+    }
+
+    if !thread.approvals.is_empty() {
+        body.push_str("<h2>Approvals</h2>\n<ul>\n");
+        for record in &thread.approvals {
+            body.push_str(&format!(
+                "<li>{} {}: <strong>{}</strong>{}</li>\n",
+                record.decided_at.to_rfc3339(),
+                escape_html(&approval_subject(record)),
+                escape_html(&decision_label(record)),
+                record
+                    .request
+                    .reason
+                    .as_ref()
+                    .map(|reason| format!(" — {}", escape_html(reason)))
+                    .unwrap_or_default()
+            ));
+        }
+        body.push_str("</ul>\n");
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(&thread.title),
+        HTML_STYLE,
+        body
+    )
+}
+
+pub fn render(thread: &ExportedThread, format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Markdown => Ok(render_markdown(thread)),
+        ExportFormat::Html => Ok(render_html(thread)),
+        ExportFormat::Json => serde_json::to_string_pretty(thread)
+            .map_err(|e| format!("Failed to serialize thread: {}", e)),
+    }
+}
+
+/// Loads a thread for export: from its rollout file when present, otherwise from the
+/// app-server if the thread is already loaded there. Exporting never loads a thread.
+pub fn load_thread(thread_id: &str, state: &AppState) -> Result<ExportedThread, CodexError> {
+    let mut thread = match sessions::find_rollout_file(thread_id).map_err(CodexError::io)? {
+        Some(path) => thread_from_rollout(thread_id, &path).map_err(CodexError::io)?,
+        None => {
+            let loaded = state
+                .activity
+                .lock()
+                .map(|activity| activity.is_tracked(thread_id))
+                .unwrap_or(false);
+            if !loaded {
+                return Err(CodexError::io(format!(
+                    "No rollout file found for thread {}",
+                    thread_id
+                )));
+            }
+            // Resuming a loaded thread only returns its current state.
+            let handle = state.get_client()?;
+            let params: ThreadResumeParams = serde_json::from_value(
+                serde_json::json!({ "threadId": thread_id }),
+            )
+            .map_err(|e| {
+                CodexError::internal(format!("Failed to build thread_resume params: {}", e))
+            })?;
+            let response = handle.thread_resume(params)?;
+            let value = serde_json::to_value(&response).map_err(|e| {
+                CodexError::internal(format!("Failed to read thread_resume response: {}", e))
+            })?;
+            thread_from_app_server(thread_id, value.get("thread").unwrap_or(&Value::Null))
+        }
+    };
+
+    if let Ok(approvals) = state.approvals.lock() {
+        thread.approvals = approvals.history_for_thread(thread_id);
+    }
+    Ok(thread)
+}
+
+fn default_file_name(thread: &ExportedThread, format: ExportFormat) -> String {
+    let slug: String = thread
+        .title
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .take(8)
+        .collect::<Vec<_>>()
+        .join("-");
+    let slug = if slug.is_empty() {
+        thread.id.clone()
+    } else {
+        slug
+    };
+    format!("{}.{}", slug, format.extension())
+}
+
+#[tauri::command]
+pub async fn export_thread(
+    params: ExportThreadParams,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<Option<ExportResult>, CodexError> {
+    let format = params.options.format;
+    let thread = apply_options(load_thread(&params.thread_id, &state)?, &params.options);
+    let content = render(&thread, format).map_err(CodexError::internal)?;
+
+    // The file is only ever written where the user chose in the save dialog.
+    let (picked_tx, picked_rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_file_name(default_file_name(&thread, format))
+        .add_filter(format.filter_name(), &[format.extension()])
+        .save_file(move |picked| {
+            let _ = picked_tx.send(picked);
+        });
+    let path = match picked_rx.await {
+        Ok(Some(file_path)) => file_path
+            .into_path()
+            .map_err(|e| CodexError::internal(format!("Invalid export path: {}", e)))?,
+        // The user cancelled the save dialog.
+        Ok(None) | Err(_) => return Ok(None),
+    };
+
+    fs::write(&path, &content)
+        .map_err(|e| CodexError::io(format!("Failed to write {}: {}", path.display(), e)))?;
+    log::info!("Exported thread {} to {}", params.thread_id, path.display());
+
+    Ok(Some(ExportResult {
+        path: path.display().to_string(),
+        format,
+        bytes: content.len(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_thread() -> ExportedThread {
+        let mut thread = ExportedThread::new("thread-1");
+        thread.cwd = Some("/work/app".to_string());
+        thread.model = Some("gpt-5".to_string());
+        thread.start_turn();
+        let items = &mut thread.current_turn().items;
+        items.push(ExportedItem::UserMessage {
+            text: "Fix the build\nIt fails on CI".to_string(),
+        });
+        items.push(ExportedItem::Reasoning {
+            text: "Look at the logs".to_string(),
+        });
+        items.push(ExportedItem::Command {
+            command: "cargo build".to_string(),
+            output: Some("error: ```oops```".to_string()),
+        });
+        items.push(ExportedItem::FileChange {
+            paths: vec!["src/lib.rs".to_string()],
+            diff: Some("-old\n+new".to_string()),
+        });
+        items.push(ExportedItem::AgentMessage {
+            text: "Fixed.".to_string(),
+        });
+        thread.finish()
+    }
+
+    fn options(format: ExportFormat) -> ExportOptions {
+        ExportOptions {
+            format,
+            include_reasoning: false,
+            include_command_output: true,
+            include_diffs: true,
+            include_approvals: true,
+        }
+    }
+
+    #[test]
+    fn title_is_the_first_prompt_line() {
+        assert_eq!(sample_thread().title, "Fix the build");
+
+        let mut thread = ExportedThread::new("thread-2");
+        thread.start_turn();
+        thread.start_turn();
+        thread.current_turn().items.push(ExportedItem::UserMessage {
+            text: "x".repeat(100),
+        });
+        let thread = thread.finish();
+        assert_eq!(thread.turns.len(), 1, "empty turns are dropped");
+        assert_eq!(thread.title.chars().count(), TITLE_MAX_CHARS + 1);
+        assert!(thread.title.ends_with('…'));
+        assert_eq!(ExportedThread::new("t").finish().title, "Codex thread t");
+    }
+
+    #[test]
+    fn markdown_quotes_prompts_and_fences_output() {
+        let markdown = render_markdown(&sample_thread());
+        assert!(markdown.starts_with("# Fix the build\n\n- **Thread:** `thread-1`\n"));
+        assert!(markdown.contains("- **Directory:** `/work/app`\n- **Model:** `gpt-5`\n"));
+        assert!(
+            markdown.contains("## Turn 1\n\n**User**\n\n> Fix the build\n> It fails on CI\n\n")
+        );
+        assert!(markdown.contains("```sh\n$ cargo build\n```\n\n"));
+        // Output containing a triple backtick gets a longer fence.
+        assert!(markdown.contains("````text\nerror: ```oops```\n````\n\n"));
+        assert!(markdown.contains("**Changed files:** `src/lib.rs`\n\n```diff\n-old\n+new\n```"));
+        assert!(markdown.contains("**Codex**\n\nFixed.\n\n"));
+        assert!(!markdown.contains("## Approvals"));
+    }
+
+    #[test]
+    fn options_drop_the_excluded_parts() {
+        let thread = apply_options(sample_thread(), &options(ExportFormat::Markdown));
+        let markdown = render_markdown(&thread);
+        assert!(!markdown.contains("Reasoning"));
+
+        let mut all = options(ExportFormat::Markdown);
+        all.include_reasoning = true;
+        all.include_command_output = false;
+        all.include_diffs = false;
+        let markdown = render_markdown(&apply_options(sample_thread(), &all));
+        assert!(markdown.contains("<details><summary>Reasoning</summary>\n\nLook at the logs\n"));
+        assert!(!markdown.contains("oops"));
+        assert!(!markdown.contains("```diff"));
+        assert!(markdown.contains("**Changed files:** `src/lib.rs`"));
+    }
+
+    #[test]
+    fn json_keeps_the_item_types() {
+        let thread = apply_options(sample_thread(), &options(ExportFormat::Json));
+        let json: Value =
+            serde_json::from_str(&render(&thread, ExportFormat::Json).unwrap()).unwrap();
+        assert_eq!(json["id"], "thread-1");
+        assert_eq!(json["title"], "Fix the build");
+        assert_eq!(json["cwd"], "/work/app");
+        let items = json["turns"][0]["items"].as_array().unwrap();
+        let types: Vec<_> = items
+            .iter()
+            .map(|item| item["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            ["userMessage", "command", "fileChange", "agentMessage"]
+        );
+        assert_eq!(items[1]["output"], "error: ```oops```");
+        assert_eq!(items[2]["paths"][0], "src/lib.rs");
+        assert_eq!(json["turns"][0]["index"], 1);
+    }
+
+    #[test]
+    fn html_is_escaped() {
+        let mut thread = ExportedThread::new("thread-3");
+        thread.start_turn();
+        thread.current_turn().items.push(ExportedItem::UserMessage {
+            text: "<script>alert('x')</script>".to_string(),
+        });
+        let html = render_html(&thread.finish());
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;"));
+    }
+
+    #[test]
+    fn app_server_items_are_normalized() {
+        let thread = thread_from_app_server(
+            "thread-4",
+            &serde_json::json!({
+                "cwd": "/work",
+                "turns": [{
+                    "items": [
+                        { "type": "userMessage", "content": [{ "type": "text", "text": "hi" }] },
+                        { "type": "commandExecution", "command": ["bash", "-lc", "ls -a"], "aggregatedOutput": "." },
+                        { "type": "fileChange", "changes": [{ "path": "a.rs", "diff": "+x" }] },
+                        { "type": "somethingNew" }
+                    ]
+                }]
+            }),
+        );
+        assert_eq!(thread.cwd.as_deref(), Some("/work"));
+        let items = &thread.turns[0].items;
+        assert_eq!(items.len(), 3);
+        assert!(matches!(&items[0], ExportedItem::UserMessage { text } if text == "hi"));
+        assert!(
+            matches!(&items[1], ExportedItem::Command { command, output } if command == "ls -a" && output.as_deref() == Some("."))
+        );
+        assert!(
+            matches!(&items[2], ExportedItem::FileChange { paths, diff } if paths == &["a.rs"] && diff.as_deref() == Some("+x"))
+        );
+    }
+
+    #[test]
+    fn file_name_is_a_slug_of_the_title() {
+        assert_eq!(
+            default_file_name(&sample_thread(), ExportFormat::Markdown),
+            "fix-the-build.md"
+        );
+        let untitled = ExportedThread {
+            title: "!!!".to_string(),
+            ..ExportedThread::new("thread-5")
+        };
+        assert_eq!(
+            default_file_name(&untitled, ExportFormat::Json),
+            "thread-5.json"
+        );
+    }
+}
@@ -0,0 +1,947 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::Manager;
+
+use crate::commands::CodexError;
+use crate::sessions::{self, RolloutEntry};
+use crate::state::AppState;
+use crate::storage;
+
+const INDEX_FILE: &str = "history-index.json";
+const INDEX_VERSION: u32 = 2;
+const MAX_DOCUMENT_CHARS: usize = 4000;
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+/// Delay before changes from live notifications are written, so a burst of turns ends in a
+/// single write.
+const SAVE_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HistoryDocumentKind {
+    UserMessage,
+    AgentMessage,
+    Command,
+    FilePath,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryDocument {
+    pub thread_id: String,
+    pub turn_id: Option<String>,
+    pub turn_index: Option<usize>,
+    pub kind: HistoryDocumentKind,
+    pub text: String,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// How far a rollout file has been indexed, so refreshes only read appended lines.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RolloutCursor {
+    offset: u64,
+    thread_id: Option<String>,
+    turn_index: Option<usize>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryIndex {
+    version: u32,
+    rollouts: HashMap<PathBuf, RolloutCursor>,
+    documents: Vec<HistoryDocument>,
+    #[serde(skip)]
+    postings: HashMap<String, Vec<(usize, u32)>>,
+    #[serde(skip)]
+    fingerprints: HashSet<u64>,
+    /// Highest turn index indexed per thread.
+    #[serde(skip)]
+    last_turns: HashMap<String, usize>,
+    /// Turn of each thread seen last in live notifications, as `(turn_id, turn_index)`. The
+    /// index is `None` while the thread's rollout hasn't been indexed.
+    #[serde(skip)]
+    live_turns: HashMap<String, (String, Option<usize>)>,
+    /// Live documents without a turn index, by `unplaced_key`, oldest first; the rollout
+    /// copy of each fills its index in instead of being added again.
+    #[serde(skip)]
+    unplaced: HashMap<u64, Vec<usize>>,
+    #[serde(skip)]
+    loaded: bool,
+    #[serde(skip)]
+    dirty: bool,
+    #[serde(skip)]
+    save_scheduled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHistoryParams {
+    pub query: String,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub thread_id: Option<String>,
+    #[serde(default)]
+    pub kinds: Option<Vec<HistoryDocumentKind>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryHit {
+    pub thread_id: String,
+    pub turn_id: Option<String>,
+    pub turn_index: Option<usize>,
+    pub kind: HistoryDocumentKind,
+    pub snippet: String,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryIndexStats {
+    pub documents: usize,
+    pub rollouts: usize,
+    pub terms: usize,
+}
+
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Identity of a document: the same text in another turn is a separate document, while the
+/// copies read from the rollout and from live notifications collapse into one.
+fn fingerprint(document: &HistoryDocument) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    document.thread_id.hash(&mut hasher);
+    document.turn_index.hash(&mut hasher);
+    document.kind.hash(&mut hasher);
+    document.text.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn unplaced_key(document: &HistoryDocument) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    document.thread_id.hash(&mut hasher);
+    document.kind.hash(&mut hasher);
+    document.text.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn note_turn(last_turns: &mut HashMap<String, usize>, thread_id: &str, turn_index: usize) {
+    let last = last_turns
+        .entry(thread_id.to_string())
+        .or_insert(turn_index);
+    *last = (*last).max(turn_index);
+}
+
+/// Documents read from the part of a rollout appended since `from`, the offset the index
+/// had recorded when the file was read.
+struct RolloutUpdate {
+    path: PathBuf,
+    from: u64,
+    cursor: RolloutCursor,
+    documents: Vec<HistoryDocument>,
+}
+
+/// Reads the lines appended to every rollout since its cursor. Runs without the index locked.
+fn scan_rollouts(cursors: &HashMap<PathBuf, RolloutCursor>) -> Result<Vec<RolloutUpdate>, String> {
+    let mut updates = Vec::new();
+    for path in sessions::find_rollout_files()? {
+        let cursor = cursors.get(&path).cloned().unwrap_or_default();
+        match scan_rollout(path.clone(), cursor) {
+            Ok(Some(update)) => updates.push(update),
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to index {}: {}", path.display(), e),
+        }
+    }
+    Ok(updates)
+}
+
+fn scan_rollout(path: PathBuf, mut cursor: RolloutCursor) -> Result<Option<RolloutUpdate>, String> {
+    let from = cursor.offset;
+    let len = std::fs::metadata(&path)
+        .map_err(|e| format!("Failed to stat {}: {}", path.display(), e))?
+        .len();
+    if cursor.offset > len {
+        // The file was rewritten; index it again from the start.
+        cursor = RolloutCursor::default();
+    }
+    if cursor.offset == len {
+        return Ok(None);
+    }
+
+    let mut file =
+        File::open(&path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    file.seek(SeekFrom::Start(cursor.offset))
+        .map_err(|e| format!("Failed to seek {}: {}", path.display(), e))?;
+    let mut reader = BufReader::new(file);
+
+    let mut documents = Vec::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        let bytes = reader
+            .read_line(&mut line)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        // Leave a partially written last line for the next refresh.
+        if bytes == 0 || !line.ends_with('\n') {
+            break;
+        }
+        cursor.offset += bytes as u64;
+
+        if let Some(record) = sessions::parse_rollout_line(&line) {
+            rollout_documents(&mut cursor, record.entry, record.timestamp, &mut documents);
+        }
+    }
+
+    Ok(Some(RolloutUpdate {
+        path,
+        from,
+        cursor,
+        documents,
+    }))
+}
+
+fn rollout_documents(
+    cursor: &mut RolloutCursor,
+    entry: RolloutEntry,
+    timestamp: Option<DateTime<Utc>>,
+    documents: &mut Vec<HistoryDocument>,
+) {
+    if let RolloutEntry::Meta { id, .. } = &entry {
+        cursor.thread_id = Some(id.clone());
+        return;
+    }
+    let Some(thread_id) = cursor.thread_id.clone() else {
+        return;
+    };
+
+    let mut push = |kind, text: String, turn_index| {
+        documents.push(HistoryDocument {
+            thread_id: thread_id.clone(),
+            turn_id: None,
+            turn_index,
+            kind,
+            text,
+            timestamp,
+        });
+    };
+
+    match entry {
+        RolloutEntry::UserMessage(text) if !sessions::is_injected_context(&text) => {
+            // Every message the user typed opens a new turn in the rollout.
+            let turn_index = cursor.turn_index.map_or(0, |index| index + 1);
+            cursor.turn_index = Some(turn_index);
+            push(HistoryDocumentKind::UserMessage, text, Some(turn_index));
+        }
+        RolloutEntry::AgentMessage(text) => {
+            push(HistoryDocumentKind::AgentMessage, text, cursor.turn_index);
+        }
+        RolloutEntry::Command { command, .. } => {
+            push(HistoryDocumentKind::Command, command, cursor.turn_index);
+        }
+        RolloutEntry::Patch { paths, .. } => {
+            for path in paths {
+                push(HistoryDocumentKind::FilePath, path, cursor.turn_index);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl HistoryIndex {
+    fn index_path() -> Result<PathBuf, String> {
+        storage::get_app_data_path(INDEX_FILE)
+    }
+
+    /// Loads the persisted index on first use; a missing or outdated file starts a fresh index.
+    pub fn ensure_loaded(&mut self) {
+        if self.loaded {
+            return;
+        }
+
+        let loaded = Self::index_path()
+            .and_then(|path| storage::load_json::<HistoryIndex>(&path))
+            .unwrap_or_else(|e| {
+                log::warn!("Discarding unreadable history index: {}", e);
+                HistoryIndex::default()
+            });
+
+        *self = if loaded.version == INDEX_VERSION {
+            loaded
+        } else {
+            HistoryIndex::default()
+        };
+        self.version = INDEX_VERSION;
+        self.loaded = true;
+        self.rebuild_postings();
+    }
+
+    fn rebuild_postings(&mut self) {
+        self.postings.clear();
+        self.fingerprints.clear();
+        self.last_turns.clear();
+        self.unplaced.clear();
+        for doc_id in 0..self.documents.len() {
+            self.index_document(doc_id);
+        }
+    }
+
+    fn index_document(&mut self, doc_id: usize) {
+        let document = &self.documents[doc_id];
+        self.fingerprints.insert(fingerprint(document));
+        match document.turn_index {
+            Some(turn_index) => note_turn(&mut self.last_turns, &document.thread_id, turn_index),
+            None if document.turn_id.is_some() => self
+                .unplaced
+                .entry(unplaced_key(document))
+                .or_default()
+                .push(doc_id),
+            None => {}
+        }
+
+        let mut term_counts: HashMap<String, u32> = HashMap::new();
+        for token in tokenize(&document.text) {
+            *term_counts.entry(token).or_default() += 1;
+        }
+        for (term, count) in term_counts {
+            self.postings.entry(term).or_default().push((doc_id, count));
+        }
+    }
+
+    /// Gives the oldest live document with the same text the turn index of its rollout copy.
+    fn place(&mut self, document: &HistoryDocument) -> bool {
+        let key = unplaced_key(document);
+        let Some(ids) = self.unplaced.get_mut(&key) else {
+            return false;
+        };
+        let doc_id = ids.remove(0);
+        if ids.is_empty() {
+            self.unplaced.remove(&key);
+        }
+        self.fingerprints
+            .remove(&fingerprint(&self.documents[doc_id]));
+        self.documents[doc_id].turn_index = document.turn_index;
+        self.fingerprints
+            .insert(fingerprint(&self.documents[doc_id]));
+        if let Some(turn_index) = document.turn_index {
+            note_turn(&mut self.last_turns, &document.thread_id, turn_index);
+        }
+        // Later items of the same live turn can take the index right away.
+        if let Some((live_turn, live_index)) = self.live_turns.get_mut(&document.thread_id)
+            && self.documents[doc_id].turn_id.as_ref() == Some(live_turn)
+        {
+            *live_index = document.turn_index;
+        }
+        self.dirty = true;
+        true
+    }
+
+    /// Adds a document unless the same text was already indexed for that turn of the thread.
+    pub fn add_document(&mut self, mut document: HistoryDocument) {
+        if document.text.trim().is_empty() {
+            return;
+        }
+        if document.text.chars().count() > MAX_DOCUMENT_CHARS {
+            document.text = document.text.chars().take(MAX_DOCUMENT_CHARS).collect();
+        }
+
+        if self.fingerprints.contains(&fingerprint(&document)) {
+            return;
+        }
+        // A rollout document whose live copy arrived before the rollout was indexed.
+        if document.turn_id.is_none() && self.place(&document) {
+            return;
+        }
+
+        self.documents.push(document);
+        self.index_document(self.documents.len() - 1);
+        self.dirty = true;
+    }
+
+    /// Cursors of the indexed rollouts, for `scan_rollouts`.
+    fn rollout_cursors(&mut self) -> HashMap<PathBuf, RolloutCursor> {
+        self.ensure_loaded();
+        self.rollouts.clone()
+    }
+
+    /// Adds what `scan_rollouts` read. A rollout indexed by someone else in the meantime
+    /// (its cursor moved) is skipped, its documents are already in.
+    fn merge(&mut self, updates: Vec<RolloutUpdate>) {
+        self.ensure_loaded();
+        for update in updates {
+            let current = self.rollouts.get(&update.path).map_or(0, |c| c.offset);
+            if current != update.from {
+                continue;
+            }
+            for document in update.documents {
+                self.add_document(document);
+            }
+            self.rollouts.insert(update.path, update.cursor);
+            self.dirty = true;
+        }
+    }
+
+    /// Indexes completed items from a live `codex:notification` payload.
+    pub fn record_notification(&mut self, notification: &Value) {
+        if notification.get("method").and_then(Value::as_str) != Some("item/completed") {
+            return;
+        }
+        let Some(params) = notification.get("params") else {
+            return;
+        };
+        let (Some(thread_id), Some(item)) = (
+            params.get("threadId").and_then(Value::as_str),
+            params.get("item"),
+        ) else {
+            return;
+        };
+        self.ensure_loaded();
+
+        let Some(turn_id) = params.get("turnId").and_then(Value::as_str) else {
+            return;
+        };
+
+        // Counted like the rollout counts turns, so both sources give a turn the same index.
+        let user_text =
+            (item.get("type").and_then(Value::as_str) == Some("userMessage")).then(|| {
+                item.get("content")
+                    .and_then(Value::as_array)
+                    .map(|content| {
+                        content
+                            .iter()
+                            .filter_map(|part| part.get("text").and_then(Value::as_str))
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    })
+                    .unwrap_or_default()
+            });
+        if let Some(text) = &user_text
+            && !sessions::is_injected_context(text)
+            && self
+                .live_turns
+                .get(thread_id)
+                .is_none_or(|(live_turn, _)| live_turn != turn_id)
+        {
+            // Unknown until the thread's rollout is indexed, if it hasn't been yet.
+            let turn_index = match self.live_turns.get(thread_id) {
+                Some((_, Some(index))) => Some(index + 1),
+                Some((_, None)) => None,
+                None => self.last_turns.get(thread_id).map(|index| index + 1),
+            };
+            self.live_turns
+                .insert(thread_id.to_string(), (turn_id.to_string(), turn_index));
+        }
+        let turn_index = self
+            .live_turns
+            .get(thread_id)
+            .filter(|(live_turn, _)| live_turn == turn_id)
+            .and_then(|(_, turn_index)| *turn_index);
+
+        let mut push = |kind, text: String| {
+            self.add_document(HistoryDocument {
+                thread_id: thread_id.to_string(),
+                turn_id: Some(turn_id.to_string()),
+                turn_index,
+                kind,
+                text,
+                timestamp: Some(Utc::now()),
+            });
+        };
+
+        match item.get("type").and_then(Value::as_str) {
+            Some("userMessage") => {
+                if let Some(text) = user_text
+                    && !sessions::is_injected_context(&text)
+                {
+                    push(HistoryDocumentKind::UserMessage, text);
+                }
+            }
+            Some("agentMessage") => {
+                if let Some(text) = item.get("text").and_then(Value::as_str) {
+                    push(HistoryDocumentKind::AgentMessage, text.to_string());
+                }
+            }
+            Some("commandExecution") => {
+                let command = match item.get("command") {
+                    Some(Value::String(command)) => Some(command.clone()),
+                    Some(Value::Array(argv)) => Some(sessions::display_command(
+                        &argv
+                            .iter()
+                            .filter_map(|part| part.as_str().map(str::to_string))
+                            .collect::<Vec<_>>(),
+                    )),
+                    _ => None,
+                };
+                if let Some(command) = command {
+                    push(HistoryDocumentKind::Command, command);
+                }
+            }
+            Some("fileChange") => {
+                let paths = item
+                    .get("changes")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|change| change.get("path").and_then(Value::as_str));
+                for path in paths {
+                    push(HistoryDocumentKind::FilePath, path.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Ranks documents containing every query term with a length-normalised tf-idf score,
+    /// boosting documents that contain the query as an exact phrase.
+    pub fn search(&self, params: &SearchHistoryParams) -> Vec<HistoryHit> {
+        let terms: Vec<String> = tokenize(&params.query).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let total_docs = self.documents.len().max(1) as f64;
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for (position, term) in terms.iter().enumerate() {
+            let Some(postings) = self.postings.get(term) else {
+                return Vec::new();
+            };
+            let idf = (total_docs / postings.len() as f64).ln() + 1.0;
+
+            let mut next_scores = HashMap::new();
+            for (doc_id, count) in postings {
+                if position > 0 && !scores.contains_key(doc_id) {
+                    continue;
+                }
+                let length = self.documents[*doc_id].text.len().max(1) as f64;
+                let weight = (*count as f64).sqrt() * idf / length.ln().max(1.0);
+                let previous = scores.get(doc_id).copied().unwrap_or(0.0);
+                next_scores.insert(*doc_id, previous + weight);
+            }
+            scores = next_scores;
+        }
+
+        let phrase = params.query.trim().to_lowercase();
+        let mut hits: Vec<HistoryHit> = scores
+            .into_iter()
+            .filter_map(|(doc_id, mut score)| {
+                let document = &self.documents[doc_id];
+                if params
+                    .thread_id
+                    .as_ref()
+                    .is_some_and(|thread_id| thread_id != &document.thread_id)
+                {
+                    return None;
+                }
+                if params
+                    .kinds
+                    .as_ref()
+                    .is_some_and(|kinds| !kinds.contains(&document.kind))
+                {
+                    return None;
+                }
+                if terms.len() > 1 && document.text.to_lowercase().contains(&phrase) {
+                    score *= 2.0;
+                }
+                Some(HistoryHit {
+                    thread_id: document.thread_id.clone(),
+                    turn_id: document.turn_id.clone(),
+                    turn_index: document.turn_index,
+                    kind: document.kind,
+                    snippet: sessions::make_snippet(&document.text, &terms[0]),
+                    timestamp: document.timestamp,
+                    score,
+                })
+            })
+            .collect();
+
+        hits.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| b.timestamp.cmp(&a.timestamp))
+        });
+        hits.truncate(params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT));
+        hits
+    }
+
+    pub fn stats(&self) -> HistoryIndexStats {
+        HistoryIndexStats {
+            documents: self.documents.len(),
+            rollouts: self.rollouts.len(),
+            terms: self.postings.len(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = HistoryIndex {
+            version: INDEX_VERSION,
+            loaded: true,
+            dirty: true,
+            ..HistoryIndex::default()
+        };
+    }
+
+    /// Serializes the index if it changed, so it can be written without holding the lock.
+    fn take_snapshot(&mut self) -> Result<Option<(PathBuf, Vec<u8>)>, String> {
+        if !self.dirty {
+            return Ok(None);
+        }
+        let content = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize history index: {}", e))?;
+        self.dirty = false;
+        Ok(Some((Self::index_path()?, content)))
+    }
+}
+
+/// Saves the index a little later on the async runtime. Called from the client's event loop,
+/// which must not wait for the write.
+pub fn schedule_save(app: &tauri::AppHandle) {
+    {
+        let state = app.state::<AppState>();
+        let Ok(mut index) = state.history_index.lock() else {
+            return;
+        };
+        if !index.dirty || index.save_scheduled {
+            return;
+        }
+        index.save_scheduled = true;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_DELAY).await;
+        let state = app.state::<AppState>();
+        let snapshot = match state.history_index.lock() {
+            Ok(mut index) => {
+                index.save_scheduled = false;
+                index.take_snapshot()
+            }
+            Err(_) => return,
+        };
+        let result = snapshot.and_then(|snapshot| match snapshot {
+            Some((path, content)) => storage::write_atomic(&path, &content),
+            None => Ok(()),
+        });
+        if let Err(e) = result {
+            log::warn!("Failed to save history index: {}", e);
+            if let Ok(mut index) = state.history_index.lock() {
+                index.dirty = true;
+            }
+        }
+    });
+}
+
+/// Indexes what was appended to the rollouts since the last refresh. The files are read
+/// and parsed without the index locked, since the client's event loop takes that lock for
+/// every notification; the results are merged under it and saved later.
+async fn refresh(app: &tauri::AppHandle) -> Result<(), CodexError> {
+    let state = app.state::<AppState>();
+    let cursors = state
+        .history_index
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?
+        .rollout_cursors();
+    let updates = tauri::async_runtime::spawn_blocking(move || scan_rollouts(&cursors))
+        .await
+        .map_err(|e| CodexError::internal(format!("Failed to read rollouts: {}", e)))?
+        .map_err(CodexError::io)?;
+    state
+        .history_index
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?
+        .merge(updates);
+    schedule_save(app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn search_history(
+    params: SearchHistoryParams,
+    app: tauri::AppHandle,
+) -> Result<Vec<HistoryHit>, CodexError> {
+    refresh(&app).await?;
+    let hits = app
+        .state::<AppState>()
+        .history_index
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?
+        .search(&params);
+    Ok(hits)
+}
+
+#[tauri::command]
+pub async fn rebuild_history_index(app: tauri::AppHandle) -> Result<HistoryIndexStats, CodexError> {
+    app.state::<AppState>()
+        .history_index
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?
+        .clear();
+    refresh(&app).await?;
+    let stats = app
+        .state::<AppState>()
+        .history_index
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?
+        .stats();
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn index() -> HistoryIndex {
+        HistoryIndex {
+            version: INDEX_VERSION,
+            loaded: true,
+            ..Default::default()
+        }
+    }
+
+    fn meta(id: &str) -> RolloutEntry {
+        RolloutEntry::Meta {
+            id: id.to_string(),
+            cwd: None,
+            model_provider: None,
+            originator: None,
+            cli_version: None,
+            git: None,
+        }
+    }
+
+    fn read_rollout(entries: Vec<RolloutEntry>) -> Vec<HistoryDocument> {
+        let mut cursor = RolloutCursor::default();
+        let mut documents = Vec::new();
+        for entry in entries {
+            rollout_documents(&mut cursor, entry, None, &mut documents);
+        }
+        documents
+    }
+
+    fn completed(thread_id: &str, turn_id: &str, item: Value) -> Value {
+        json!({
+            "method": "item/completed",
+            "params": { "threadId": thread_id, "turnId": turn_id, "item": item },
+        })
+    }
+
+    fn user_message(text: &str) -> Value {
+        json!({ "type": "userMessage", "content": [{ "type": "text", "text": text }] })
+    }
+
+    fn agent_message(text: &str) -> Value {
+        json!({ "type": "agentMessage", "text": text })
+    }
+
+    fn search(index: &HistoryIndex, query: &str) -> Vec<HistoryHit> {
+        index.search(&SearchHistoryParams {
+            query: query.to_string(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn user_messages_open_rollout_turns() {
+        let documents = read_rollout(vec![
+            RolloutEntry::UserMessage("before the meta line".to_string()),
+            meta("t1"),
+            RolloutEntry::UserMessage("<environment_context>cwd</environment_context>".to_string()),
+            RolloutEntry::UserMessage("first".to_string()),
+            RolloutEntry::AgentMessage("answer".to_string()),
+            RolloutEntry::UserMessage("second".to_string()),
+            RolloutEntry::Command {
+                call_id: None,
+                command: "cargo test".to_string(),
+            },
+            RolloutEntry::Patch {
+                patch: String::new(),
+                paths: vec!["src/a.rs".to_string(), "src/b.rs".to_string()],
+            },
+        ]);
+
+        let turns: Vec<_> = documents
+            .iter()
+            .map(|d| (d.kind, d.text.as_str(), d.turn_index))
+            .collect();
+        assert_eq!(
+            turns,
+            vec![
+                (HistoryDocumentKind::UserMessage, "first", Some(0)),
+                (HistoryDocumentKind::AgentMessage, "answer", Some(0)),
+                (HistoryDocumentKind::UserMessage, "second", Some(1)),
+                (HistoryDocumentKind::Command, "cargo test", Some(1)),
+                (HistoryDocumentKind::FilePath, "src/a.rs", Some(1)),
+                (HistoryDocumentKind::FilePath, "src/b.rs", Some(1)),
+            ]
+        );
+        assert!(documents.iter().all(|d| d.thread_id == "t1"));
+    }
+
+    #[test]
+    fn the_same_text_is_indexed_once_per_turn() {
+        let mut index = index();
+        for document in read_rollout(vec![
+            meta("t1"),
+            RolloutEntry::UserMessage("run it".to_string()),
+            RolloutEntry::UserMessage("run it".to_string()),
+        ]) {
+            index.add_document(document.clone());
+            index.add_document(document);
+        }
+        assert_eq!(index.stats().documents, 2);
+
+        // The live copy of an indexed turn is the same document.
+        index.record_notification(&completed("t1", "turn-2", user_message("again")));
+        index.add_document(HistoryDocument {
+            thread_id: "t1".to_string(),
+            turn_id: None,
+            turn_index: Some(2),
+            kind: HistoryDocumentKind::UserMessage,
+            text: "again".to_string(),
+            timestamp: None,
+        });
+        assert_eq!(index.stats().documents, 3);
+        assert_eq!(search(&index, "again")[0].turn_index, Some(2));
+    }
+
+    #[test]
+    fn live_documents_of_an_unindexed_thread_take_the_rollout_index() {
+        let mut index = index();
+        index.record_notification(&completed("t1", "turn-a", user_message("hello there")));
+        index.record_notification(&completed("t1", "turn-a", agent_message("hi")));
+        let hits = search(&index, "hello");
+        assert_eq!(hits[0].turn_id.as_deref(), Some("turn-a"));
+        assert_eq!(hits[0].turn_index, None);
+
+        let path = PathBuf::from("rollout-t1.jsonl");
+        let documents = read_rollout(vec![
+            meta("t1"),
+            RolloutEntry::UserMessage("hello there".to_string()),
+            RolloutEntry::AgentMessage("hi".to_string()),
+        ]);
+        index.merge(vec![RolloutUpdate {
+            path: path.clone(),
+            from: 0,
+            cursor: RolloutCursor {
+                offset: 10,
+                thread_id: Some("t1".to_string()),
+                turn_index: Some(0),
+            },
+            documents,
+        }]);
+
+        assert_eq!(index.stats().documents, 2);
+        for query in ["hello", "hi"] {
+            let hits = search(&index, query);
+            assert_eq!(hits.len(), 1);
+            assert_eq!(hits[0].turn_id.as_deref(), Some("turn-a"));
+            assert_eq!(hits[0].turn_index, Some(0));
+        }
+
+        // The rest of the live turn and the next one are placed right away.
+        index.record_notification(&completed("t1", "turn-a", agent_message("done")));
+        index.record_notification(&completed("t1", "turn-b", user_message("next")));
+        assert_eq!(search(&index, "done")[0].turn_index, Some(0));
+        assert_eq!(search(&index, "next")[0].turn_index, Some(1));
+
+        // A rollout that moved on since it was read is not merged twice.
+        index.merge(vec![RolloutUpdate {
+            path,
+            from: 0,
+            cursor: RolloutCursor::default(),
+            documents: read_rollout(vec![
+                meta("t1"),
+                RolloutEntry::UserMessage("stale".to_string()),
+            ]),
+        }]);
+        assert!(search(&index, "stale").is_empty());
+    }
+
+    #[test]
+    fn search_requires_every_term_and_boosts_phrases() {
+        let mut index = index();
+        let texts = [
+            "build the release binary",
+            "release notes mention the build",
+            "build only",
+        ];
+        for (turn, text) in texts.iter().enumerate() {
+            index.add_document(HistoryDocument {
+                thread_id: "t1".to_string(),
+                turn_id: None,
+                turn_index: Some(turn),
+                kind: HistoryDocumentKind::AgentMessage,
+                text: text.to_string(),
+                timestamp: None,
+            });
+        }
+
+        let hits = search(&index, "Release Binary");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].turn_index, Some(0));
+
+        let hits = search(&index, "the release");
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].turn_index, Some(0));
+        assert!(hits[0].score > hits[1].score * 1.5);
+
+        assert!(search(&index, "build missing").is_empty());
+        assert!(search(&index, "  ").is_empty());
+    }
+
+    #[test]
+    fn search_applies_filters_and_limit() {
+        let mut index = index();
+        for (thread_id, kind) in [
+            ("t1", HistoryDocumentKind::Command),
+            ("t1", HistoryDocumentKind::UserMessage),
+            ("t2", HistoryDocumentKind::Command),
+        ] {
+            index.add_document(HistoryDocument {
+                thread_id: thread_id.to_string(),
+                turn_id: None,
+                turn_index: Some(0),
+                kind,
+                text: "cargo fmt".to_string(),
+                timestamp: None,
+            });
+        }
+
+        let hits = index.search(&SearchHistoryParams {
+            query: "cargo".to_string(),
+            thread_id: Some("t1".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|hit| hit.thread_id == "t1"));
+
+        let hits = index.search(&SearchHistoryParams {
+            query: "cargo".to_string(),
+            kinds: Some(vec![HistoryDocumentKind::Command]),
+            ..Default::default()
+        });
+        assert_eq!(hits.len(), 2);
+        assert!(
+            hits.iter()
+                .all(|hit| hit.kind == HistoryDocumentKind::Command)
+        );
+
+        let hits = index.search(&SearchHistoryParams {
+            query: "cargo".to_string(),
+            limit: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(hits.len(), 1);
+    }
+}
@@ -1,10 +1,31 @@
+use tauri::Manager;
 use tauri_plugin_log::log;
 
+mod activity;
+mod attachments;
+mod batch;
+mod bridge;
+mod checkpoints;
+pub mod cli;
 mod codex;
 mod codex_discovery;
 mod commands;
 mod config;
+mod export;
+mod history_index;
+pub mod mcp;
+mod models;
+mod notifications;
+mod projects;
+mod prompts;
+mod sessions;
 mod state;
+mod storage;
+mod thread_metadata;
+mod tray;
+mod turn_queue;
+mod usage;
+mod windows;
 
 use state::AppState;
 
@@ -14,24 +35,118 @@ pub fn run() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(
             tauri_plugin_log::Builder::new()
                 .level(log::LevelFilter::Info)
                 .build(),
         )
         .manage(AppState::new())
-        .setup(|_app| Ok(()))
+        .on_window_event(|window, event| match event {
+            tauri::WindowEvent::Destroyed => {
+                windows::on_window_destroyed(window.app_handle(), window.label());
+            }
+            tauri::WindowEvent::Focused(true) => {
+                notifications::on_window_focused(window.app_handle(), window.label());
+            }
+            _ => {}
+        })
+        .setup(|app| {
+            attachments::remove_stale_attachments();
+            codex::pidfile::reap_orphans();
+            bridge::start_if_enabled(app.handle());
+            mcp::start_if_enabled(app.handle());
+            if let Err(e) = tray::init(app.handle()) {
+                log::warn!("Failed to create tray icon: {}", e);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             config::read_codex_config,
             config::read_providers,
+            projects::list_projects,
+            projects::add_project,
+            projects::remove_project,
+            projects::update_project_defaults,
+            prompts::list_prompts,
+            prompts::expand_prompt,
+            sessions::list_sessions,
+            sessions::search_sessions,
+            sessions::read_session,
+            history_index::search_history,
+            history_index::rebuild_history_index,
+            export::export_thread,
+            attachments::stage_attachment,
+            attachments::list_attachments,
+            attachments::remove_attachment,
+            attachments::clear_attachments,
+            checkpoints::list_checkpoints,
+            checkpoints::diff_checkpoint,
+            checkpoints::restore_checkpoint,
+            thread_metadata::get_thread_metadata,
+            thread_metadata::update_thread_metadata,
+            thread_metadata::list_thread_tags,
+            thread_metadata::prune_thread_metadata,
             commands::codex_initialize,
+            commands::codex_shutdown,
+            commands::codex_restart,
+            commands::codex_status,
             commands::thread_start,
             commands::thread_resume,
             commands::thread_list,
             commands::turn_start,
             commands::turn_interrupt,
+            turn_queue::enqueue_turn,
+            turn_queue::list_turn_queue,
+            turn_queue::retry_turn_queue,
+            turn_queue::reorder_turn_queue,
+            turn_queue::update_queued_turn,
+            turn_queue::remove_queued_turn,
+            batch::start_batch,
+            batch::list_batches,
+            batch::get_batch,
+            batch::cancel_batch,
+            windows::open_thread_window,
+            windows::get_window_threads,
+            windows::subscribe_window_threads,
+            windows::unsubscribe_window_threads,
+            windows::list_windows,
             commands::respond_to_approval,
+            commands::list_pending_approvals,
+            commands::account_read,
+            commands::account_login,
+            commands::account_logout,
+            models::list_models,
+            bridge::get_bridge_status,
+            bridge::configure_bridge,
+            bridge::regenerate_bridge_token,
+            mcp::get_mcp_server_status,
+            mcp::configure_mcp_server,
+            notifications::get_notification_settings,
+            notifications::update_notification_settings,
+            usage::get_usage_report,
+            usage::get_price_table,
+            usage::update_price_table,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                let state = app.state::<AppState>();
+                if let Err(e) = state.shutdown_client() {
+                    log::error!("Failed to stop codex app-server on exit: {}", e);
+                }
+                if let Ok(mut attachments) = state.attachments.lock()
+                    && let Err(e) = attachments.clear_all()
+                {
+                    log::warn!("Failed to remove attachments on exit: {}", e);
+                }
+                // Changes from the last seconds are still waiting for their delayed save.
+                if let Ok(mut usage) = state.usage.lock()
+                    && let Err(e) = usage.save_if_dirty()
+                {
+                    log::warn!("Failed to save usage ledger on exit: {}", e);
+                }
+            }
+        });
 }
@@ -1,6 +1,34 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+/// Release builds on Windows start without a console, so `run` and `mcp` print through the
+/// console of the terminal they were started from.
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // Fails when there is no parent console, e.g. when started from Explorer; output then
+    // goes wherever the standard handles already point.
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if codexia_zen_lib::cli::is_cli_invocation(&args) {
+        attach_parent_console();
+        std::process::exit(codexia_zen_lib::cli::run(args));
+    }
+    if codexia_zen_lib::mcp::is_stdio_invocation(&args) {
+        attach_parent_console();
+        std::process::exit(codexia_zen_lib::mcp::run_stdio());
+    }
     codexia_zen_lib::run()
 }
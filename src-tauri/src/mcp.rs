@@ -0,0 +1,802 @@
+//! MCP server that lets other agents delegate work to Codex threads managed by Codexia.
+//!
+//! The server runs inside the app over the streamable HTTP transport (`POST /mcp`, JSON
+//! responses only) so every thread it starts goes through the app's own `CodexClientHandle`
+//! and approval requests land in the GUI like any other. `codexia-zen mcp` is a stdio relay
+//! to that endpoint for clients that can only spawn a process.
+
+use axum::Router;
+use axum::extract::State as AxumState;
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::io::{BufRead, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{Manager, State};
+use tokio::sync::watch;
+
+use crate::activity::{self, TurnState};
+use crate::bridge;
+use crate::codex::types::ApprovalRequestKind;
+use crate::commands::{self, CodexError};
+use crate::state::AppState;
+use crate::storage;
+
+const SETTINGS_FILE: &str = "mcp.json";
+const HOST: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 47622;
+const ENDPOINT: &str = "/mcp";
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+const DEFAULT_WAIT_SECS: u64 = 600;
+const MAX_WAIT_SECS: u64 = 3600;
+const DEFAULT_THREAD_LIMIT: u64 = 20;
+/// Settings an MCP caller may pick for a thread. Anything more permissive would let the caller
+/// run commands nobody in Codexia approved, so other values fall back to the project's defaults.
+const MCP_SANDBOXES: [&str; 2] = ["read-only", "workspace-write"];
+const MCP_APPROVAL_POLICIES: [&str; 2] = ["untrusted", "on-request"];
+
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const PARSE_ERROR: i64 = -32700;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub token: String,
+}
+
+fn default_port() -> u16 {
+    DEFAULT_PORT
+}
+
+impl Default for McpSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_port(),
+            token: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpSettingsUpdate {
+    #[serde(default)]
+    pub enabled: Option<bool>,
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerStatus {
+    pub enabled: bool,
+    pub running: bool,
+    pub port: u16,
+    pub token: String,
+    pub url: Option<String>,
+    /// Command line for MCP clients that only speak stdio.
+    pub stdio_command: String,
+    pub error: Option<String>,
+}
+
+struct RunningServer {
+    address: SocketAddr,
+    shutdown: watch::Sender<bool>,
+}
+
+#[derive(Default)]
+pub struct McpServerState {
+    settings: Option<McpSettings>,
+    running: Option<RunningServer>,
+    last_error: Option<String>,
+}
+
+fn settings_path() -> Result<PathBuf, String> {
+    storage::get_app_data_path(SETTINGS_FILE)
+}
+
+fn load_settings() -> McpSettings {
+    settings_path()
+        .and_then(|path| storage::load_json::<McpSettings>(&path))
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to load MCP settings: {}", e);
+            McpSettings::default()
+        })
+}
+
+impl McpServerState {
+    fn settings(&mut self) -> &mut McpSettings {
+        self.settings.get_or_insert_with(load_settings)
+    }
+
+    fn save_settings(&mut self) -> Result<(), String> {
+        let settings = self.settings().clone();
+        storage::save_json(&settings_path()?, &settings)
+    }
+
+    pub fn status(&mut self) -> McpServerStatus {
+        let url = self
+            .running
+            .as_ref()
+            .map(|running| format!("http://{}{}", running.address, ENDPOINT));
+        let error = self.last_error.clone();
+        let settings = self.settings().clone();
+        let stdio_command = std::env::current_exe()
+            .map(|exe| format!("{} mcp", exe.display()))
+            .unwrap_or_else(|_| "codexia-zen mcp".to_string());
+        McpServerStatus {
+            enabled: settings.enabled,
+            running: url.is_some(),
+            port: settings.port,
+            token: settings.token,
+            url,
+            stdio_command,
+            error,
+        }
+    }
+
+    fn start(&mut self, app: &tauri::AppHandle) -> Result<(), String> {
+        if self.settings().token.is_empty() {
+            self.settings().token = uuid::Uuid::new_v4().simple().to_string();
+            self.save_settings()?;
+        }
+        let settings = self.settings().clone();
+        if let Some(running) = &self.running
+            && running.address.port() == settings.port
+        {
+            return Ok(());
+        }
+        self.stop();
+
+        // Bind synchronously so address errors are reported to the caller.
+        let listener = std::net::TcpListener::bind((HOST, settings.port))
+            .map_err(|e| format!("Failed to listen on {}:{}: {}", HOST, settings.port, e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("Failed to configure MCP socket: {}", e))?;
+        let address = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read MCP server address: {}", e))?;
+
+        let (shutdown, mut shutdown_rx) = watch::channel(false);
+        let server = McpServer {
+            app: app.clone(),
+            token: settings.token,
+        };
+        let router = Router::new()
+            .route(ENDPOINT, post(handle_post).get(method_not_allowed))
+            .with_state(server);
+        tauri::async_runtime::spawn(async move {
+            let listener = match tokio::net::TcpListener::from_std(listener) {
+                Ok(listener) => listener,
+                Err(e) => {
+                    log::error!("Failed to start MCP server: {}", e);
+                    return;
+                }
+            };
+            let shutdown = async move {
+                let _ = shutdown_rx.changed().await;
+            };
+            if let Err(e) = axum::serve(listener, router)
+                .with_graceful_shutdown(shutdown)
+                .await
+            {
+                log::error!("MCP server stopped: {}", e);
+            }
+        });
+
+        log::info!("MCP server listening on http://{}{}", address, ENDPOINT);
+        self.running = Some(RunningServer { address, shutdown });
+        self.last_error = None;
+        Ok(())
+    }
+
+    fn stop(&mut self) {
+        if let Some(running) = self.running.take() {
+            let _ = running.shutdown.send(true);
+            log::info!("MCP server on {} stopped", running.address);
+        }
+    }
+}
+
+/// Starts the MCP server at launch when the user has enabled it.
+pub fn start_if_enabled(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let Ok(mut server) = state.mcp.lock() else {
+        return;
+    };
+    if server.settings().enabled
+        && let Err(e) = server.start(app)
+    {
+        log::error!("{}", e);
+        server.last_error = Some(e);
+    }
+}
+
+#[derive(Clone)]
+struct McpServer {
+    app: tauri::AppHandle,
+    token: String,
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+async fn method_not_allowed() -> StatusCode {
+    // No server-initiated stream: every result is returned in the POST response.
+    StatusCode::METHOD_NOT_ALLOWED
+}
+
+async fn handle_post(
+    AxumState(server): AxumState<McpServer>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let authorized = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| bridge::token_matches(token.trim(), &server.token));
+    if !authorized {
+        return (StatusCode::UNAUTHORIZED, "invalid or missing token").into_response();
+    }
+
+    let message: Value = match serde_json::from_str(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            let error = RpcError::new(PARSE_ERROR, format!("Invalid JSON: {}", e));
+            return axum::Json(error_response(Value::Null, error)).into_response();
+        }
+    };
+    match server.handle_message(message).await {
+        Some(response) => axum::Json(response).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+fn tool_result(value: Value, is_error: bool) -> Value {
+    let text = serde_json::to_string_pretty(&value).unwrap_or_default();
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "structuredContent": value,
+        "isError": is_error,
+    })
+}
+
+fn tool_definitions() -> Value {
+    let wait = json!({
+        "type": "boolean",
+        "description": "Wait for the turn to finish and return its result (default true)",
+    });
+    let timeout = json!({
+        "type": "integer",
+        "description": "Seconds to wait before returning a still-running turn (default 600)",
+    });
+    json!([
+        {
+            "name": "list_threads",
+            "description": "List recent Codex threads, optionally only those of one directory.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "cwd": { "type": "string", "description": "Only threads started in this directory" },
+                    "limit": { "type": "integer", "description": "Maximum number of threads (default 20)" },
+                },
+            },
+        },
+        {
+            "name": "start_thread",
+            "description": "Start a Codex thread in a directory, optionally sending a first prompt. \
+                Commands and edits that need approval wait for the Codexia user to approve them; \
+                settings that would skip approvals are not available.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "cwd": { "type": "string", "description": "Absolute path of the project directory" },
+                    "prompt": { "type": "string", "description": "First instruction for the thread" },
+                    "model": { "type": "string" },
+                    "sandbox": { "type": "string", "enum": MCP_SANDBOXES },
+                    "approvalPolicy": { "type": "string", "enum": MCP_APPROVAL_POLICIES },
+                    "wait": wait,
+                    "timeoutSeconds": timeout,
+                },
+                "required": ["cwd"],
+            },
+        },
+        {
+            "name": "send_turn",
+            "description": "Send an instruction to an existing thread.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "threadId": { "type": "string" },
+                    "prompt": { "type": "string" },
+                    "wait": wait,
+                    "timeoutSeconds": timeout,
+                },
+                "required": ["threadId", "prompt"],
+            },
+        },
+        {
+            "name": "get_thread_snapshot",
+            "description": "Report whether a thread is busy, the outcome of its recent turns and \
+                the approvals it is waiting on.",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "threadId": { "type": "string" } },
+                "required": ["threadId"],
+            },
+        },
+    ])
+}
+
+fn string_arg(arguments: &Value, key: &str) -> Option<String> {
+    arguments
+        .get(key)
+        .and_then(Value::as_str)
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+fn required_arg(arguments: &Value, key: &str) -> Result<String, RpcError> {
+    string_arg(arguments, key)
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Missing argument '{}'", key)))
+}
+
+impl McpServer {
+    /// Handles one JSON-RPC message; notifications get no response.
+    async fn handle_message(&self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return id
+                .map(|id| error_response(id, RpcError::new(INVALID_REQUEST, "Missing method")));
+        };
+        let id = id?;
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let result = match method {
+            "initialize" => Ok(Self::initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => self.call_tool(&params).await,
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method {}", method),
+            )),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    fn initialize(params: &Value) -> Value {
+        let requested = params.get("protocolVersion").and_then(Value::as_str);
+        let version = requested
+            .filter(|version| PROTOCOL_VERSIONS.contains(version))
+            .unwrap_or(PROTOCOL_VERSIONS[0]);
+        json!({
+            "protocolVersion": version,
+            "capabilities": { "tools": { "listChanged": false } },
+            "serverInfo": { "name": "codexia-zen", "version": env!("CARGO_PKG_VERSION") },
+            "instructions": "Runs coding tasks in Codex threads managed by Codexia. Approval \
+                requests are answered by the Codexia user, so a turn may wait for them.",
+        })
+    }
+
+    async fn call_tool(&self, params: &Value) -> Result<Value, RpcError> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing tool name"))?;
+        let arguments = params.get("arguments").cloned().unwrap_or(json!({}));
+        let result = match name {
+            "list_threads" => self.list_threads(&arguments).await,
+            "start_thread" => self.start_thread(&arguments).await,
+            "send_turn" => {
+                let thread_id = required_arg(&arguments, "threadId")?;
+                let prompt = required_arg(&arguments, "prompt")?;
+                self.send_turn(&thread_id, &prompt, &arguments).await
+            }
+            "get_thread_snapshot" => {
+                let thread_id = required_arg(&arguments, "threadId")?;
+                self.thread_snapshot(&thread_id)
+            }
+            _ => {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    format!("Unknown tool {}", name),
+                ));
+            }
+        };
+        // Failures of the task itself are tool errors the calling model can read and act on.
+        Ok(match result {
+            Ok(value) => tool_result(value, false),
+            Err(message) => tool_result(json!({ "error": message }), true),
+        })
+    }
+
+    async fn list_threads(&self, arguments: &Value) -> Result<Value, String> {
+        let cwd = string_arg(arguments, "cwd");
+        let limit = arguments
+            .get("limit")
+            .and_then(Value::as_u64)
+            .unwrap_or(DEFAULT_THREAD_LIMIT);
+        // Fetch extra threads when filtering so the page still has `limit` entries.
+        let fetch = if cwd.is_some() { limit * 5 } else { limit };
+        let params = serde_json::from_value(json!({ "limit": fetch }))
+            .map_err(|e| format!("Invalid thread list parameters: {}", e))?;
+
+        let state = self.app.state::<AppState>();
+        let result = commands::thread_list(params, None, state.clone(), self.app.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+        let result = serde_json::to_value(result).map_err(|e| e.to_string())?;
+        let activity = state
+            .activity
+            .lock()
+            .map_err(|e| format!("Failed to acquire lock: {}", e))?;
+        let threads: Vec<Value> = result
+            .get("data")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|thread| {
+                cwd.as_deref()
+                    .is_none_or(|cwd| thread.get("cwd").and_then(Value::as_str) == Some(cwd))
+            })
+            .take(limit as usize)
+            .map(|thread| {
+                let id = thread.get("id").and_then(Value::as_str).unwrap_or_default();
+                json!({
+                    "threadId": id,
+                    "preview": thread.get("preview"),
+                    "cwd": thread.get("cwd"),
+                    "createdAt": thread.get("createdAt"),
+                    "running": activity.snapshot(id).is_some_and(|snapshot| snapshot.running),
+                })
+            })
+            .collect();
+        Ok(json!({ "threads": threads }))
+    }
+
+    async fn start_thread(&self, arguments: &Value) -> Result<Value, String> {
+        let cwd = string_arg(arguments, "cwd").ok_or("Missing argument 'cwd'")?;
+        let restricted = |key: &str, allowed: &[&str]| {
+            let value = string_arg(arguments, key)?;
+            if allowed.contains(&value.as_str()) {
+                Some(value)
+            } else {
+                log::warn!("Ignoring {} '{}' from an MCP client", key, value);
+                None
+            }
+        };
+        let params = serde_json::from_value(json!({
+            "cwd": cwd,
+            "model": string_arg(arguments, "model"),
+            "sandbox": restricted("sandbox", &MCP_SANDBOXES),
+            "approvalPolicy": restricted("approvalPolicy", &MCP_APPROVAL_POLICIES),
+        }))
+        .map_err(|e| format!("Invalid thread options: {}", e))?;
+
+        let state = self.app.state::<AppState>();
+        let response = commands::thread_start(params, state.clone(), self.app.clone())
+            .await
+            .map_err(|e| match e.recovered_id("/thread/id") {
+                Some(thread_id) => format!("{} (thread {} was started)", e, thread_id),
+                None => e.to_string(),
+            })?;
+        let thread_id = serde_json::to_value(&response.thread)
+            .ok()
+            .and_then(|thread| thread.get("id").and_then(Value::as_str).map(str::to_string))
+            .ok_or("thread/start response has no thread id")?;
+
+        match string_arg(arguments, "prompt") {
+            Some(prompt) => self.send_turn(&thread_id, &prompt, arguments).await,
+            None => Ok(json!({ "threadId": thread_id })),
+        }
+    }
+
+    async fn send_turn(
+        &self,
+        thread_id: &str,
+        prompt: &str,
+        arguments: &Value,
+    ) -> Result<Value, String> {
+        let state = self.app.state::<AppState>();
+        let tracked = state
+            .activity
+            .lock()
+            .map(|activity| activity.is_tracked(thread_id))
+            .unwrap_or(false);
+        // Threads from earlier sessions have to be loaded before they accept turns.
+        if !tracked {
+            let params = serde_json::from_value(json!({ "threadId": thread_id }))
+                .map_err(|e| format!("Invalid thread id: {}", e))?;
+            commands::thread_resume(params, state.clone())
+                .await
+                .map_err(|e| e.to_string())?;
+            if let Ok(mut activity) = state.activity.lock() {
+                activity.track(thread_id);
+            }
+        }
+
+        let mut changes = state
+            .activity
+            .lock()
+            .map_err(|e| format!("Failed to acquire lock: {}", e))?
+            .subscribe();
+        let params = serde_json::from_value(json!({
+            "threadId": thread_id,
+            "input": [{ "type": "text", "text": prompt }],
+        }))
+        .map_err(|e| format!("Invalid turn parameters: {}", e))?;
+        let response = commands::turn_start(params, None, None, state.clone(), self.app.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+        let turn_id = serde_json::to_value(&response)
+            .ok()
+            .and_then(|value| {
+                value
+                    .pointer("/turn/id")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+            })
+            .ok_or("turn/start response has no turn id")?;
+
+        if !arguments
+            .get("wait")
+            .and_then(Value::as_bool)
+            .unwrap_or(true)
+        {
+            return Ok(
+                json!({ "threadId": thread_id, "turnId": turn_id, "state": TurnState::InProgress }),
+            );
+        }
+        let timeout = Duration::from_secs(
+            arguments
+                .get("timeoutSeconds")
+                .and_then(Value::as_u64)
+                .unwrap_or(DEFAULT_WAIT_SECS)
+                .min(MAX_WAIT_SECS),
+        );
+        let turn =
+            activity::wait_for_turn(&state.activity, &mut changes, thread_id, &turn_id, timeout)
+                .await;
+        if let Some(turn) = &turn
+            && turn.state != TurnState::InProgress
+        {
+            return Ok(json!({ "threadId": thread_id, "turn": turn }));
+        }
+        Ok(json!({
+            "threadId": thread_id,
+            "turnId": turn_id,
+            "state": TurnState::InProgress,
+            "turn": turn,
+            "pendingApprovals": self.pending_approvals(thread_id),
+            "note": "The turn is still running; poll get_thread_snapshot for its result.",
+        }))
+    }
+
+    fn pending_approvals(&self, thread_id: &str) -> Vec<Value> {
+        let state = self.app.state::<AppState>();
+        let Ok(approvals) = state.approvals.lock() else {
+            return Vec::new();
+        };
+        approvals
+            .pending()
+            .into_iter()
+            .filter(|request| request.thread_id == thread_id)
+            .map(|request| {
+                let (kind, detail) = match &request.kind {
+                    ApprovalRequestKind::CommandExecution { details, .. } => (
+                        "commandExecution",
+                        details.as_ref().map(|details| json!(details.command)),
+                    ),
+                    ApprovalRequestKind::FileChange { files, .. } => (
+                        "fileChange",
+                        Some(json!(
+                            files.iter().map(|file| &file.path).collect::<Vec<_>>()
+                        )),
+                    ),
+                };
+                json!({
+                    "requestId": request.request_id,
+                    "turnId": request.turn_id,
+                    "kind": kind,
+                    "reason": request.reason,
+                    "detail": detail,
+                })
+            })
+            .collect()
+    }
+
+    fn thread_snapshot(&self, thread_id: &str) -> Result<Value, String> {
+        let state = self.app.state::<AppState>();
+        let snapshot = state
+            .activity
+            .lock()
+            .map_err(|e| format!("Failed to acquire lock: {}", e))?
+            .snapshot(thread_id);
+        let pending = self.pending_approvals(thread_id);
+        Ok(match snapshot {
+            Some(snapshot) => json!({
+                "threadId": thread_id,
+                "loaded": true,
+                "running": snapshot.running,
+                "turns": snapshot.turns,
+                "updatedAt": snapshot.updated_at,
+                "pendingApprovals": pending,
+            }),
+            None => json!({
+                "threadId": thread_id,
+                "loaded": false,
+                "running": false,
+                "turns": [],
+                "pendingApprovals": pending,
+            }),
+        })
+    }
+}
+
+/// Whether the arguments ask for the stdio relay rather than the GUI.
+pub fn is_stdio_invocation(args: &[String]) -> bool {
+    args.first().is_some_and(|arg| arg == "mcp")
+}
+
+/// Relays newline-delimited JSON-RPC between stdin/stdout and the running app's MCP endpoint.
+/// Returns the process exit status.
+pub fn run_stdio() -> i32 {
+    let settings = load_settings();
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match relay(&settings, &line) {
+            Ok(reply) => reply,
+            Err(e) => {
+                eprintln!("codexia-zen mcp: {}", e);
+                let id = serde_json::from_str::<Value>(&line)
+                    .ok()
+                    .and_then(|message| message.get("id").cloned());
+                // Notifications get no reply, even when they couldn't be delivered.
+                id.map(|id| error_response(id, RpcError::new(-32000, e)).to_string())
+            }
+        };
+        if let Some(reply) = reply
+            && (writeln!(stdout, "{}", reply).is_err() || stdout.flush().is_err())
+        {
+            return 1;
+        }
+    }
+    0
+}
+
+/// Posts one message to the local endpoint; `None` when the server accepted a notification.
+fn relay(settings: &McpSettings, message: &str) -> Result<Option<String>, String> {
+    if !settings.enabled {
+        return Err("The Codexia MCP server is disabled; enable it in Codexia's settings".into());
+    }
+    let mut stream = TcpStream::connect((HOST, settings.port)).map_err(|e| {
+        format!(
+            "Codexia is not running or not listening on port {}: {}",
+            settings.port, e
+        )
+    })?;
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}:{}\r\nAuthorization: Bearer {}\r\n\
+         Content-Type: application/json\r\nAccept: application/json, text/event-stream\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        ENDPOINT,
+        HOST,
+        settings.port,
+        settings.token,
+        message.len(),
+        message
+    );
+    stream
+        .write_all(request.as_bytes())
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    let mut response = String::new();
+    stream
+        .read_to_string(&mut response)
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or("Malformed HTTP response")?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or("Malformed HTTP status line")?;
+    match status {
+        200 => Ok(Some(body.trim().to_string())),
+        202 => Ok(None),
+        401 => Err("The MCP token was rejected".into()),
+        _ => Err(format!(
+            "MCP endpoint answered HTTP {}: {}",
+            status,
+            body.trim()
+        )),
+    }
+}
+
+#[tauri::command]
+pub async fn get_mcp_server_status(
+    state: State<'_, AppState>,
+) -> Result<McpServerStatus, CodexError> {
+    let mut server = state
+        .mcp
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(server.status())
+}
+
+#[tauri::command]
+pub async fn configure_mcp_server(
+    update: McpSettingsUpdate,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<McpServerStatus, CodexError> {
+    let mut server = state
+        .mcp
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    let settings = server.settings();
+    if let Some(enabled) = update.enabled {
+        settings.enabled = enabled;
+    }
+    if let Some(port) = update.port {
+        settings.port = port;
+    }
+    let enabled = settings.enabled;
+    server.save_settings().map_err(CodexError::config)?;
+
+    if enabled {
+        if let Err(e) = server.start(&app) {
+            server.last_error = Some(e.clone());
+            return Err(CodexError::io(e));
+        }
+    } else {
+        server.stop();
+    }
+    Ok(server.status())
+}
@@ -0,0 +1,315 @@
+use chrono::{DateTime, Utc};
+use codex_app_server_protocol::ModelListParams;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use tauri::State;
+
+use crate::codex::handles::CodexClientHandle;
+use crate::commands::CodexError;
+use crate::config::{self, CodexConfig};
+use crate::state::AppState;
+
+const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+/// Upper bound on `model/list` pages, in case a server keeps returning a cursor.
+const MAX_PAGES: usize = 20;
+const OPENAI_PROVIDER: &str = "openai";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ModelSource {
+    AppServer,
+    Config,
+    Profile,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReasoningEffortOption {
+    pub effort: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogModel {
+    pub id: String,
+    pub model: String,
+    pub provider: String,
+    pub display_name: String,
+    pub description: Option<String>,
+    pub supported_reasoning_efforts: Vec<ReasoningEffortOption>,
+    pub default_reasoning_effort: Option<String>,
+    pub is_default: bool,
+    pub source: ModelSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCatalog {
+    pub models: Vec<CatalogModel>,
+    /// Models grouped by provider id, in catalog order.
+    pub providers: BTreeMap<String, Vec<String>>,
+    pub default_model: Option<String>,
+    pub default_provider: String,
+    pub default_reasoning_effort: Option<String>,
+    pub fetched_at: DateTime<Utc>,
+    /// Set when the app-server could not be queried and only local models are listed.
+    pub app_server_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ModelCatalogCache {
+    entry: Option<(Instant, ModelCatalog)>,
+}
+
+impl ModelCatalogCache {
+    pub fn get_fresh(&self) -> Option<ModelCatalog> {
+        self.entry
+            .as_ref()
+            .filter(|(fetched, _)| fetched.elapsed() < CACHE_TTL)
+            .map(|(_, catalog)| catalog.clone())
+    }
+
+    pub fn store(&mut self, catalog: ModelCatalog) {
+        self.entry = Some((Instant::now(), catalog));
+    }
+}
+
+fn string_field(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn model_from_value(value: &Value) -> Option<CatalogModel> {
+    let model = string_field(value, "model").or_else(|| string_field(value, "id"))?;
+    let supported_reasoning_efforts = value
+        .get("supportedReasoningEfforts")
+        .and_then(Value::as_array)
+        .map(|options| {
+            options
+                .iter()
+                .filter_map(|option| match option {
+                    Value::String(effort) => Some(ReasoningEffortOption {
+                        effort: effort.clone(),
+                        description: None,
+                    }),
+                    _ => Some(ReasoningEffortOption {
+                        effort: string_field(option, "reasoningEffort")
+                            .or_else(|| string_field(option, "effort"))?,
+                        description: string_field(option, "description"),
+                    }),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(CatalogModel {
+        id: string_field(value, "id").unwrap_or_else(|| model.clone()),
+        display_name: string_field(value, "displayName").unwrap_or_else(|| model.clone()),
+        description: string_field(value, "description").filter(|d| !d.is_empty()),
+        provider: OPENAI_PROVIDER.to_string(),
+        supported_reasoning_efforts,
+        default_reasoning_effort: string_field(value, "defaultReasoningEffort"),
+        is_default: value
+            .get("isDefault")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        source: ModelSource::AppServer,
+        model,
+    })
+}
+
+/// Pages through `model/list` until the server stops returning a cursor.
+fn fetch_app_server_models(handle: &CodexClientHandle) -> anyhow::Result<Vec<CatalogModel>> {
+    let mut models = Vec::new();
+    let mut cursor: Option<String> = None;
+
+    for _ in 0..MAX_PAGES {
+        let params: ModelListParams =
+            serde_json::from_value(serde_json::json!({ "cursor": cursor }))?;
+        let response = serde_json::to_value(handle.model_list(params)?)?;
+
+        let page = response
+            .get("data")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        models.extend(page.iter().filter_map(model_from_value));
+
+        cursor = string_field(&response, "nextCursor");
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    Ok(models)
+}
+
+fn local_model(model: &str, provider: &str, effort: Option<String>, source: ModelSource) -> CatalogModel {
+    CatalogModel {
+        id: format!("{}/{}", provider, model),
+        model: model.to_string(),
+        provider: provider.to_string(),
+        display_name: model.to_string(),
+        description: None,
+        supported_reasoning_efforts: Vec::new(),
+        default_reasoning_effort: effort,
+        is_default: false,
+        source,
+    }
+}
+
+/// Models configured locally: `[profiles.*]` in config.toml and the legacy
+/// `~/.codex/profile.json` provider → models map.
+fn custom_models(config: &CodexConfig) -> Vec<CatalogModel> {
+    let mut models = Vec::new();
+
+    for profile in config.profiles.values() {
+        if let Some(model) = &profile.model {
+            let provider = profile
+                .model_provider
+                .clone()
+                .or_else(|| config.model_provider.clone())
+                .unwrap_or_else(|| OPENAI_PROVIDER.to_string());
+            models.push(local_model(
+                model,
+                &provider,
+                profile.model_reasoning_effort.clone(),
+                ModelSource::Config,
+            ));
+        }
+    }
+
+    if let Some(model) = &config.model {
+        let provider = config
+            .model_provider
+            .clone()
+            .unwrap_or_else(|| OPENAI_PROVIDER.to_string());
+        models.push(local_model(
+            model,
+            &provider,
+            config.model_reasoning_effort.clone(),
+            ModelSource::Config,
+        ));
+    }
+
+    let legacy_profile = config::get_codex_home()
+        .ok()
+        .map(|home| home.join("profile.json"))
+        .filter(|path| path.exists())
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| {
+            serde_json::from_str::<HashMap<String, Vec<String>>>(&content)
+                .map_err(|e| log::warn!("Ignoring unreadable profile.json: {}", e))
+                .ok()
+        });
+    for (provider, provider_models) in legacy_profile.unwrap_or_default() {
+        for model in provider_models {
+            models.push(local_model(&model, &provider, None, ModelSource::Profile));
+        }
+    }
+
+    models
+}
+
+fn build_catalog(
+    app_server_models: Vec<CatalogModel>,
+    app_server_error: Option<String>,
+    config: &CodexConfig,
+) -> ModelCatalog {
+    let mut models = app_server_models;
+
+    // App-server entries win over local ones for the same provider and model, since they
+    // carry the reasoning efforts the model actually accepts.
+    for custom in custom_models(config) {
+        if !models
+            .iter()
+            .any(|m| m.provider == custom.provider && m.model == custom.model)
+        {
+            models.push(custom);
+        }
+    }
+
+    let default_provider = config
+        .model_provider
+        .clone()
+        .unwrap_or_else(|| OPENAI_PROVIDER.to_string());
+    let default_model = config.model.clone().or_else(|| {
+        models
+            .iter()
+            .find(|m| m.is_default)
+            .map(|m| m.model.clone())
+    });
+    if let Some(default_model) = &default_model {
+        for model in &mut models {
+            model.is_default = model.provider == default_provider && &model.model == default_model;
+        }
+    }
+    let default_reasoning_effort = config.model_reasoning_effort.clone().or_else(|| {
+        models
+            .iter()
+            .find(|m| m.is_default)
+            .and_then(|m| m.default_reasoning_effort.clone())
+    });
+
+    let mut providers: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for provider in config.model_providers.keys() {
+        providers.entry(provider.clone()).or_default();
+    }
+    for model in &models {
+        let list = providers.entry(model.provider.clone()).or_default();
+        if !list.contains(&model.model) {
+            list.push(model.model.clone());
+        }
+    }
+
+    ModelCatalog {
+        models,
+        providers,
+        default_model,
+        default_provider,
+        default_reasoning_effort,
+        fetched_at: Utc::now(),
+        app_server_error,
+    }
+}
+
+#[tauri::command]
+pub async fn list_models(
+    refresh: Option<bool>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<ModelCatalog, CodexError> {
+    if !refresh.unwrap_or(false)
+        && let Some(catalog) = state
+            .model_catalog
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire lock: {}", e))?
+            .get_fresh()
+    {
+        return Ok(catalog);
+    }
+
+    let config = config::load_codex_config().map_err(CodexError::config)?;
+    let fetched = state
+        .get_or_init_client(&app)
+        .and_then(|handle| fetch_app_server_models(&handle));
+    let catalog = match fetched {
+        Ok(models) => build_catalog(models, None, &config),
+        Err(e) => {
+            log::error!("model/list failed, using local models only: {}", e);
+            build_catalog(Vec::new(), Some(e.to_string()), &config)
+        }
+    };
+
+    // Only cache complete catalogs so a transient failure is retried on the next call.
+    if catalog.app_server_error.is_none() {
+        state
+            .model_catalog
+            .lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire lock: {}", e))?
+            .store(catalog.clone());
+    }
+    Ok(catalog)
+}
@@ -0,0 +1,282 @@
+//! Native desktop notifications for finished turns and approval requests, shown only while the
+//! window that shows the thread is not focused.
+//!
+//! The notification plugin reports clicks only on mobile, through its JavaScript `onAction`
+//! listener; on desktop, clicking a notification just brings the app to the front. So a window
+//! that gains focus within a few seconds of a notification shown while no window of the app
+//! was focused is taken as that click, and the notified thread is focused. Switching back to
+//! an app that was already in front never counts.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::{Duration, Instant};
+use tauri::{Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::activity::TurnState;
+use crate::codex::types::{ApprovalRequest, ApprovalRequestKind};
+use crate::commands::CodexError;
+use crate::state::AppState;
+use crate::storage;
+use crate::windows;
+
+const SETTINGS_FILE: &str = "notifications.json";
+/// How long after a notification focusing the app counts as clicking it.
+const CLICK_WINDOW: Duration = Duration::from_secs(5);
+const MAX_BODY_CHARS: usize = 160;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    #[serde(default = "default_true")]
+    pub turn_completed: bool,
+    #[serde(default = "default_true")]
+    pub turn_failed: bool,
+    #[serde(default = "default_true")]
+    pub approval_requested: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            turn_completed: true,
+            turn_failed: true,
+            approval_requested: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettingsUpdate {
+    #[serde(default)]
+    pub turn_completed: Option<bool>,
+    #[serde(default)]
+    pub turn_failed: Option<bool>,
+    #[serde(default)]
+    pub approval_requested: Option<bool>,
+}
+
+#[derive(Debug, Default)]
+pub struct NotificationCenter {
+    settings: Option<NotificationSettings>,
+    /// Thread of the last notification shown while the app was in the background, and when.
+    last_shown: Option<(String, Instant)>,
+}
+
+fn settings_path() -> Result<std::path::PathBuf, String> {
+    storage::get_app_data_path(SETTINGS_FILE)
+}
+
+impl NotificationCenter {
+    fn settings(&mut self) -> &mut NotificationSettings {
+        self.settings.get_or_insert_with(|| {
+            settings_path()
+                .and_then(|path| storage::load_json::<NotificationSettings>(&path))
+                .unwrap_or_else(|e| {
+                    log::warn!("Failed to load notification settings: {}", e);
+                    NotificationSettings::default()
+                })
+        })
+    }
+
+    fn update(
+        &mut self,
+        update: NotificationSettingsUpdate,
+    ) -> Result<NotificationSettings, String> {
+        let settings = self.settings();
+        if let Some(value) = update.turn_completed {
+            settings.turn_completed = value;
+        }
+        if let Some(value) = update.turn_failed {
+            settings.turn_failed = value;
+        }
+        if let Some(value) = update.approval_requested {
+            settings.approval_requested = value;
+        }
+        let settings = settings.clone();
+        storage::save_json(&settings_path()?, &settings)?;
+        Ok(settings)
+    }
+
+    /// Takes the thread of a notification shown recently enough to have been clicked.
+    fn take_clicked(&mut self) -> Option<String> {
+        let (thread_id, shown_at) = self.last_shown.take()?;
+        (shown_at.elapsed() <= CLICK_WINDOW).then_some(thread_id)
+    }
+}
+
+fn thread_visible(app: &tauri::AppHandle, thread_id: &str) -> bool {
+    windows::thread_windows(app, thread_id).iter().any(|label| {
+        app.get_webview_window(label)
+            .and_then(|window| window.is_focused().ok())
+            .unwrap_or(false)
+    })
+}
+
+fn thread_title(app: &tauri::AppHandle, thread_id: &str) -> Option<String> {
+    app.state::<AppState>()
+        .thread_metadata
+        .lock()
+        .ok()?
+        .get(thread_id)
+        .title
+}
+
+/// First line of `text`, cut to fit a notification.
+fn summary_line(text: &str) -> String {
+    let line = text
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("");
+    let line = line.trim();
+    if line.chars().count() <= MAX_BODY_CHARS {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(MAX_BODY_CHARS - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+fn app_focused(app: &tauri::AppHandle) -> bool {
+    app.webview_windows()
+        .values()
+        .any(|window| window.is_focused().unwrap_or(false))
+}
+
+fn show(app: &tauri::AppHandle, thread_id: &str, title: String, body: String) {
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        log::warn!("Failed to show notification: {}", e);
+        return;
+    }
+    let in_background = !app_focused(app);
+    if let Ok(mut center) = app.state::<AppState>().notifications.lock() {
+        center.last_shown = in_background.then(|| (thread_id.to_string(), Instant::now()));
+    }
+}
+
+/// Called after the activity tracker has recorded a `turn/completed` notification.
+pub fn on_turn_completed(app: &tauri::AppHandle, notification: &Value) {
+    let Some(thread_id) = windows::notification_thread_id(notification) else {
+        return;
+    };
+    let Some(turn_id) = notification
+        .pointer("/params/turn/id")
+        .and_then(Value::as_str)
+    else {
+        return;
+    };
+    let state = app.state::<AppState>();
+    let Some(turn) = state
+        .activity
+        .lock()
+        .ok()
+        .and_then(|activity| activity.turn(thread_id, turn_id))
+    else {
+        return;
+    };
+    let Some(settings) = state
+        .notifications
+        .lock()
+        .ok()
+        .map(|mut center| center.settings().clone())
+    else {
+        return;
+    };
+    let (enabled, heading, body) = match turn.state {
+        TurnState::Completed => (
+            settings.turn_completed,
+            "Turn completed",
+            turn.agent_message.as_deref().map(summary_line),
+        ),
+        TurnState::Failed => (
+            settings.turn_failed,
+            "Turn failed",
+            turn.error.as_deref().map(summary_line),
+        ),
+        // Interrupts come from the user, who doesn't need telling.
+        TurnState::Interrupted | TurnState::InProgress => return,
+    };
+    if !enabled || thread_visible(app, thread_id) {
+        return;
+    }
+    let title = match thread_title(app, thread_id) {
+        Some(title) => format!("{}: {}", heading, title),
+        None => heading.to_string(),
+    };
+    show(app, thread_id, title, body.unwrap_or_default());
+}
+
+pub fn on_approval_request(app: &tauri::AppHandle, request: &ApprovalRequest) {
+    let enabled = app
+        .state::<AppState>()
+        .notifications
+        .lock()
+        .map(|mut center| center.settings().approval_requested)
+        .unwrap_or(false);
+    if !enabled || thread_visible(app, &request.thread_id) {
+        return;
+    }
+    let (heading, body) = match &request.kind {
+        ApprovalRequestKind::CommandExecution { details, .. } => (
+            "Approve command?",
+            details
+                .as_ref()
+                .map(|d| summary_line(&d.command))
+                .or_else(|| request.reason.as_deref().map(summary_line)),
+        ),
+        ApprovalRequestKind::FileChange { files, .. } => (
+            "Approve file changes?",
+            Some(match files.as_slice() {
+                [file] => summary_line(&file.path),
+                files => format!("{} files", files.len()),
+            }),
+        ),
+    };
+    let title = match thread_title(app, &request.thread_id) {
+        Some(title) => format!("{} ({})", heading, title),
+        None => heading.to_string(),
+    };
+    show(app, &request.thread_id, title, body.unwrap_or_default());
+}
+
+/// Called when a window gains focus; brings up the thread of a just-shown notification.
+pub fn on_window_focused(app: &tauri::AppHandle, label: &str) {
+    let Some(thread_id) = app
+        .state::<AppState>()
+        .notifications
+        .lock()
+        .ok()
+        .and_then(|mut center| center.take_clicked())
+    else {
+        return;
+    };
+    windows::focus_thread(app, &thread_id, Some(label));
+}
+
+#[tauri::command]
+pub async fn get_notification_settings(
+    state: State<'_, AppState>,
+) -> Result<NotificationSettings, CodexError> {
+    let mut center = state
+        .notifications
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(center.settings().clone())
+}
+
+#[tauri::command]
+pub async fn update_notification_settings(
+    update: NotificationSettingsUpdate,
+    state: State<'_, AppState>,
+) -> Result<NotificationSettings, CodexError> {
+    state
+        .notifications
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?
+        .update(update)
+        .map_err(CodexError::config)
+}
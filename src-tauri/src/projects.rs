@@ -0,0 +1,377 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::State;
+
+use crate::commands::CodexError;
+use crate::config;
+use crate::state::AppState;
+use crate::storage;
+
+const PROJECTS_FILE: &str = "projects.json";
+const SANDBOX_MODES: [&str; 3] = ["read-only", "workspace-write", "danger-full-access"];
+const APPROVAL_POLICIES: [&str; 4] = ["untrusted", "on-failure", "on-request", "never"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectDefaults {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub model_provider: Option<String>,
+    #[serde(default)]
+    pub sandbox: Option<String>,
+    #[serde(default)]
+    pub approval_policy: Option<String>,
+    #[serde(default)]
+    pub writable_roots: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredProject {
+    name: String,
+    added_at: DateTime<Utc>,
+    #[serde(default)]
+    last_used_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    defaults: ProjectDefaults,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ProjectSource {
+    /// Added through Codexia.
+    Registry,
+    /// Only listed under `[projects]` in the Codex config.
+    Config,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectInfo {
+    pub path: String,
+    pub name: String,
+    pub source: ProjectSource,
+    pub exists: bool,
+    pub is_git_repo: bool,
+    pub trust_level: Option<String>,
+    pub added_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub defaults: ProjectDefaults,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectRegistry {
+    projects: BTreeMap<String, StoredProject>,
+    #[serde(skip)]
+    loaded: bool,
+}
+
+pub fn is_git_repo(path: &Path) -> bool {
+    path.ancestors().any(|dir| dir.join(".git").exists())
+}
+
+/// Checks that `path` is an existing directory and returns its canonical form.
+pub fn validate_directory(path: &str) -> Result<PathBuf, String> {
+    let path = Path::new(path.trim());
+    if path.as_os_str().is_empty() {
+        return Err("Project path is empty".to_string());
+    }
+    let canonical = path
+        .canonicalize()
+        .map_err(|e| format!("Directory {} does not exist: {}", path.display(), e))?;
+    if !canonical.is_dir() {
+        return Err(format!("{} is not a directory", canonical.display()));
+    }
+    Ok(canonical)
+}
+
+fn validate_defaults(defaults: &ProjectDefaults) -> Result<(), String> {
+    if let Some(sandbox) = &defaults.sandbox
+        && !SANDBOX_MODES.contains(&sandbox.as_str())
+    {
+        return Err(format!(
+            "Unknown sandbox mode '{}', expected one of {}",
+            sandbox,
+            SANDBOX_MODES.join(", ")
+        ));
+    }
+    if let Some(policy) = &defaults.approval_policy
+        && !APPROVAL_POLICIES.contains(&policy.as_str())
+    {
+        return Err(format!(
+            "Unknown approval policy '{}', expected one of {}",
+            policy,
+            APPROVAL_POLICIES.join(", ")
+        ));
+    }
+    for root in &defaults.writable_roots {
+        if !Path::new(root).is_absolute() {
+            return Err(format!("Writable root {} must be an absolute path", root));
+        }
+        if !Path::new(root).is_dir() {
+            return Err(format!("Writable root {} does not exist", root));
+        }
+    }
+    Ok(())
+}
+
+impl ProjectRegistry {
+    fn store_path() -> Result<PathBuf, String> {
+        storage::get_app_data_path(PROJECTS_FILE)
+    }
+
+    pub fn ensure_loaded(&mut self) {
+        if self.loaded {
+            return;
+        }
+        *self = Self::store_path()
+            .and_then(|path| storage::load_json::<ProjectRegistry>(&path))
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to load project registry: {}", e);
+                ProjectRegistry::default()
+            });
+        self.loaded = true;
+    }
+
+    fn save(&self) -> Result<(), String> {
+        storage::save_json(&Self::store_path()?, self)
+    }
+
+    fn info(
+        path: &str,
+        stored: Option<&StoredProject>,
+        trust_level: Option<String>,
+    ) -> ProjectInfo {
+        let dir = Path::new(path);
+        ProjectInfo {
+            path: path.to_string(),
+            name: stored.map(|p| p.name.clone()).unwrap_or_else(|| {
+                dir.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.to_string())
+            }),
+            source: if stored.is_some() {
+                ProjectSource::Registry
+            } else {
+                ProjectSource::Config
+            },
+            exists: dir.is_dir(),
+            is_git_repo: is_git_repo(dir),
+            trust_level,
+            added_at: stored.map(|p| p.added_at),
+            last_used_at: stored.and_then(|p| p.last_used_at),
+            defaults: stored.map(|p| p.defaults.clone()).unwrap_or_default(),
+        }
+    }
+
+    /// Registered projects plus the ones only known from `[projects]` in config.toml, most
+    /// recently used first.
+    pub fn list(&mut self) -> Vec<ProjectInfo> {
+        self.ensure_loaded();
+        let trusted = config::load_codex_config()
+            .map(|config| config.projects)
+            .unwrap_or_else(|e| {
+                log::warn!("Failed to read Codex projects: {}", e);
+                Default::default()
+            });
+
+        let mut projects: Vec<ProjectInfo> = self
+            .projects
+            .iter()
+            .map(|(path, stored)| {
+                let trust_level = trusted.get(path).map(|p| p.trust_level.clone());
+                Self::info(path, Some(stored), trust_level)
+            })
+            .collect();
+        for (path, project) in trusted {
+            if !self.projects.contains_key(&path) {
+                projects.push(Self::info(&path, None, Some(project.trust_level)));
+            }
+        }
+
+        projects.sort_by(|a, b| {
+            b.last_used_at
+                .cmp(&a.last_used_at)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        projects
+    }
+
+    pub fn get(&mut self, path: &str) -> Option<ProjectInfo> {
+        self.list().into_iter().find(|project| project.path == path)
+    }
+
+    pub fn add(
+        &mut self,
+        path: &str,
+        name: Option<String>,
+        defaults: Option<ProjectDefaults>,
+    ) -> Result<ProjectInfo, String> {
+        self.ensure_loaded();
+        let canonical = validate_directory(path)?;
+        let defaults = defaults.unwrap_or_default();
+        validate_defaults(&defaults)?;
+
+        let key = canonical.to_string_lossy().to_string();
+        let name = name
+            .map(|n| n.trim().to_string())
+            .filter(|n| !n.is_empty())
+            .or_else(|| {
+                canonical
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+            })
+            .unwrap_or_else(|| key.clone());
+        let stored = self.projects.entry(key.clone()).or_insert(StoredProject {
+            name: name.clone(),
+            added_at: Utc::now(),
+            last_used_at: None,
+            defaults: ProjectDefaults::default(),
+        });
+        stored.name = name;
+        stored.defaults = defaults;
+        self.save()?;
+
+        self.get(&key)
+            .ok_or_else(|| format!("Project {} vanished after saving", key))
+    }
+
+    pub fn remove(&mut self, path: &str) -> Result<bool, String> {
+        self.ensure_loaded();
+        let removed = self.projects.remove(path).is_some();
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    pub fn update_defaults(
+        &mut self,
+        path: &str,
+        defaults: ProjectDefaults,
+    ) -> Result<ProjectInfo, String> {
+        self.ensure_loaded();
+        validate_defaults(&defaults)?;
+        let stored = self
+            .projects
+            .get_mut(path)
+            .ok_or_else(|| format!("Project {} is not registered", path))?;
+        stored.defaults = defaults;
+        self.save()?;
+        self.get(path)
+            .ok_or_else(|| format!("Project {} vanished after saving", path))
+    }
+
+    fn find_stored(&self, cwd: &Path) -> Option<(&String, &StoredProject)> {
+        let cwd = cwd.canonicalize().ok()?;
+        self.projects
+            .iter()
+            .find(|(path, _)| Path::new(path) == cwd)
+    }
+
+    pub fn defaults_for(&mut self, cwd: &Path) -> Option<ProjectDefaults> {
+        self.ensure_loaded();
+        self.find_stored(cwd)
+            .map(|(_, stored)| stored.defaults.clone())
+    }
+
+    pub fn touch(&mut self, cwd: &Path) {
+        self.ensure_loaded();
+        let Some(key) = self.find_stored(cwd).map(|(path, _)| path.clone()) else {
+            return;
+        };
+        if let Some(stored) = self.projects.get_mut(&key) {
+            stored.last_used_at = Some(Utc::now());
+        }
+        if let Err(e) = self.save() {
+            log::warn!("Failed to save project registry: {}", e);
+        }
+    }
+}
+
+/// Fills the thread-start fields the caller left unset from the project's defaults. Works on
+/// the JSON form so it doesn't depend on the protocol's enum types.
+pub fn apply_defaults(params: &mut Value, defaults: &ProjectDefaults) {
+    let Some(params) = params.as_object_mut() else {
+        return;
+    };
+    let mut fill = |key: &str, value: &Option<String>| {
+        if let Some(value) = value
+            && params.get(key).is_none_or(Value::is_null)
+        {
+            params.insert(key.to_string(), Value::String(value.clone()));
+        }
+    };
+    fill("model", &defaults.model);
+    fill("modelProvider", &defaults.model_provider);
+    fill("sandbox", &defaults.sandbox);
+    fill("approvalPolicy", &defaults.approval_policy);
+
+    if !defaults.writable_roots.is_empty() {
+        let config = params
+            .entry("config")
+            .or_insert_with(|| Value::Object(Default::default()));
+        if config.is_null() {
+            *config = Value::Object(Default::default());
+        }
+        if let Some(config) = config.as_object_mut() {
+            config
+                .entry("sandbox_workspace_write.writable_roots")
+                .or_insert_with(|| serde_json::json!(defaults.writable_roots));
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_projects(state: State<'_, AppState>) -> Result<Vec<ProjectInfo>, CodexError> {
+    let mut registry = state
+        .projects
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(registry.list())
+}
+
+#[tauri::command]
+pub async fn add_project(
+    path: String,
+    name: Option<String>,
+    defaults: Option<ProjectDefaults>,
+    state: State<'_, AppState>,
+) -> Result<ProjectInfo, CodexError> {
+    let mut registry = state
+        .projects
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    registry
+        .add(&path, name, defaults)
+        .map_err(CodexError::config)
+}
+
+#[tauri::command]
+pub async fn remove_project(path: String, state: State<'_, AppState>) -> Result<bool, CodexError> {
+    let mut registry = state
+        .projects
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    registry.remove(&path).map_err(CodexError::config)
+}
+
+#[tauri::command]
+pub async fn update_project_defaults(
+    path: String,
+    defaults: ProjectDefaults,
+    state: State<'_, AppState>,
+) -> Result<ProjectInfo, CodexError> {
+    let mut registry = state
+        .projects
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    registry
+        .update_defaults(&path, defaults)
+        .map_err(CodexError::config)
+}
@@ -0,0 +1,500 @@
+//! Prompt templates: Markdown files invoked as `/name [arguments]` and expanded here before the
+//! turn is sent, so the app-server only ever sees the final text.
+//!
+//! Templates are read from `<project>/.codexia/prompts`, `~/.codexia-zen/prompts` and Codex's
+//! own `$CODEX_HOME/prompts`, in that order of precedence. They may start with a front matter
+//! block (`description:`, `argument-hint:`) and use these placeholders:
+//!
+//! - `{{selection}}`: the text selected in the UI
+//! - `{{git_diff}}`: `git diff HEAD` of the working directory
+//! - `{{file:path}}`: a file's contents, relative to the working directory
+//! - `{{args}}`: everything after the command name
+//! - `{{name}}`: any other value supplied by the caller
+//!
+//! Codex prompts keep their `$ARGUMENTS` and `$1`..`$9` placeholders.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::commands::CodexError;
+use crate::config;
+use crate::storage;
+
+const PROJECT_PROMPTS_DIR: &str = ".codexia/prompts";
+const PROMPTS_DIR: &str = "prompts";
+const CODEX_COMMAND_PREFIX: &str = "prompts:";
+/// Largest file or diff inlined into a prompt.
+const MAX_INCLUDE_BYTES: usize = 256 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PromptSource {
+    Project,
+    Global,
+    Codex,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    pub name: String,
+    pub description: Option<String>,
+    pub argument_hint: Option<String>,
+    pub source: PromptSource,
+    pub path: String,
+    /// Placeholders the caller may have to supply, e.g. `selection` or `ticket`.
+    pub variables: Vec<String>,
+    #[serde(skip)]
+    body: String,
+}
+
+/// Values available to placeholders when a prompt is expanded.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptContext {
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub selection: Option<String>,
+    #[serde(default)]
+    pub variables: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpandedPrompt {
+    /// Template used, or `None` when the input was not a known command.
+    pub name: Option<String>,
+    pub text: String,
+}
+
+fn prompt_dirs(cwd: Option<&Path>) -> Vec<(PromptSource, PathBuf)> {
+    let mut dirs = Vec::new();
+    if let Some(cwd) = cwd {
+        dirs.push((PromptSource::Project, cwd.join(PROJECT_PROMPTS_DIR)));
+    }
+    match storage::get_app_data_dir() {
+        Ok(dir) => dirs.push((PromptSource::Global, dir.join(PROMPTS_DIR))),
+        Err(e) => log::warn!("Failed to locate global prompts: {}", e),
+    }
+    match config::get_codex_home() {
+        Ok(dir) => dirs.push((PromptSource::Codex, dir.join(PROMPTS_DIR))),
+        Err(e) => log::warn!("Failed to locate Codex prompts: {}", e),
+    }
+    dirs
+}
+
+/// Splits an optional `---` front matter block from the body. The block ends at the first
+/// line that is exactly `---`, which may follow the opening one directly.
+fn parse_front_matter(content: &str) -> (HashMap<String, String>, &str) {
+    let mut fields = HashMap::new();
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (fields, content);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        offset += line.len();
+        if line.trim_end_matches(['\r', '\n']) == "---" {
+            let body = rest[offset..].trim_start_matches(['\r', '\n']);
+            return (fields, body);
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim().trim_matches('"').trim_matches('\'');
+            fields.insert(key.trim().to_lowercase(), value.to_string());
+        }
+    }
+    (HashMap::new(), content)
+}
+
+/// `{{...}}` placeholders in order of first appearance.
+fn placeholders(body: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + end].trim().to_string();
+        if !name.is_empty() && !names.contains(&name) {
+            names.push(name);
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    names
+}
+
+fn load_template(source: PromptSource, path: &Path) -> Result<PromptTemplate, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read prompt {}: {}", path.display(), e))?;
+    let (fields, body) = parse_front_matter(&content);
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(PromptTemplate {
+        name,
+        description: fields.get("description").cloned(),
+        argument_hint: fields.get("argument-hint").cloned(),
+        source,
+        path: path.to_string_lossy().to_string(),
+        variables: placeholders(body),
+        body: body.to_string(),
+    })
+}
+
+/// Every template visible from `cwd`; a name defined in several places resolves to the most
+/// specific one.
+pub fn list_templates(cwd: Option<&Path>) -> Vec<PromptTemplate> {
+    let mut templates: Vec<PromptTemplate> = Vec::new();
+    for (source, dir) in prompt_dirs(cwd) {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "md"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match load_template(source, &path) {
+                Ok(template) if !templates.iter().any(|t| t.name == template.name) => {
+                    templates.push(template)
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("{}", e),
+            }
+        }
+    }
+    templates.sort_by(|a, b| a.name.cmp(&b.name));
+    templates
+}
+
+fn git_diff(cwd: &Path) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(["diff", "HEAD"])
+        .current_dir(cwd)
+        .output()
+        .map_err(|e| format!("Failed to run git diff: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git diff failed in {}: {}",
+            cwd.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let diff = String::from_utf8_lossy(&output.stdout).to_string();
+    if diff.len() > MAX_INCLUDE_BYTES {
+        return Err(format!(
+            "The diff in {} is larger than {} KB",
+            cwd.display(),
+            MAX_INCLUDE_BYTES / 1024
+        ));
+    }
+    Ok(diff)
+}
+
+fn read_include(cwd: Option<&Path>, path: &str) -> Result<String, String> {
+    let path = Path::new(path.trim());
+    let path = match cwd {
+        Some(cwd) if path.is_relative() => cwd.join(path),
+        _ => path.to_path_buf(),
+    };
+    let size = fs::metadata(&path)
+        .map_err(|e| format!("Cannot include {}: {}", path.display(), e))?
+        .len();
+    if size > MAX_INCLUDE_BYTES as u64 {
+        return Err(format!(
+            "Cannot include {}: larger than {} KB",
+            path.display(),
+            MAX_INCLUDE_BYTES / 1024
+        ));
+    }
+    fs::read_to_string(&path).map_err(|e| format!("Cannot include {}: {}", path.display(), e))
+}
+
+/// Replaces Codex's `$ARGUMENTS` and `$1`..`$9` with the command arguments.
+fn expand_positional(body: &str, arguments: &str) -> String {
+    let words: Vec<&str> = arguments.split_whitespace().collect();
+    let mut text = String::with_capacity(body.len());
+    let mut rest = body;
+    while let Some(position) = rest.find('$') {
+        text.push_str(&rest[..position]);
+        let after = &rest[position + 1..];
+        if let Some(tail) = after.strip_prefix("ARGUMENTS") {
+            text.push_str(arguments);
+            rest = tail;
+        } else if let Some(index) = after
+            .chars()
+            .next()
+            .and_then(|c| c.to_digit(10))
+            .filter(|digit| *digit > 0)
+        {
+            text.push_str(words.get(index as usize - 1).copied().unwrap_or_default());
+            rest = &after[1..];
+        } else {
+            text.push('$');
+            rest = after;
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Expands a template's placeholders. Every missing value is reported at once.
+pub fn render(
+    template: &PromptTemplate,
+    arguments: &str,
+    context: &PromptContext,
+) -> Result<String, String> {
+    let cwd = context.cwd.as_deref().map(Path::new);
+    let body = if template.source == PromptSource::Codex {
+        expand_positional(&template.body, arguments)
+    } else {
+        template.body.clone()
+    };
+
+    let mut values: HashMap<&str, String> = HashMap::new();
+    let mut missing = Vec::new();
+    let mut errors = Vec::new();
+    for name in &template.variables {
+        let value = match name.as_str() {
+            "selection" => Ok(context.selection.clone().filter(|s| !s.is_empty())),
+            "args" | "arguments" => Ok(Some(arguments.to_string()).filter(|a| !a.is_empty())),
+            "git_diff" => match cwd {
+                Some(cwd) => git_diff(cwd).map(Some),
+                None => Err("{{git_diff}} needs a working directory".to_string()),
+            },
+            other => match other.strip_prefix("file:") {
+                Some(path) => read_include(cwd, path).map(Some),
+                None => Ok(context.variables.get(other).cloned()),
+            },
+        };
+        match value {
+            Ok(Some(value)) => {
+                values.insert(name, value);
+            }
+            Ok(None) => missing.push(name.as_str()),
+            Err(e) => errors.push(e),
+        }
+    }
+    if !missing.is_empty() {
+        errors.insert(
+            0,
+            format!(
+                "Prompt '{}' is missing values for: {}",
+                template.name,
+                missing.join(", ")
+            ),
+        );
+    }
+    if !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+
+    let mut text = String::with_capacity(body.len());
+    let mut rest = body.as_str();
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + end].trim();
+        text.push_str(&rest[..start]);
+        match values.get(name) {
+            Some(value) => text.push_str(value),
+            None => text.push_str(&rest[start..start + 2 + end + 2]),
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    text.push_str(rest);
+    Ok(text.trim().to_string())
+}
+
+/// Expands `/name arguments` when `name` is a known template; other text is returned as
+/// `None` so ordinary messages (and commands the app-server handles) pass through untouched.
+pub fn expand_command(
+    input: &str,
+    context: &PromptContext,
+) -> Result<Option<ExpandedPrompt>, String> {
+    let Some(command) = input.trim_start().strip_prefix('/') else {
+        return Ok(None);
+    };
+    let (name, arguments) = command
+        .split_once(char::is_whitespace)
+        .map(|(name, arguments)| (name, arguments.trim()))
+        .unwrap_or((command.trim_end(), ""));
+    let (name, codex_only) = match name.strip_prefix(CODEX_COMMAND_PREFIX) {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    if name.is_empty() {
+        return Ok(None);
+    }
+
+    let cwd = context.cwd.as_deref().map(Path::new);
+    let template = list_templates(cwd).into_iter().find(|template| {
+        template.name == name && (!codex_only || template.source == PromptSource::Codex)
+    });
+    match template {
+        Some(template) => Ok(Some(ExpandedPrompt {
+            text: render(&template, arguments, context)?,
+            name: Some(template.name),
+        })),
+        None => Ok(None),
+    }
+}
+
+#[tauri::command]
+pub async fn list_prompts(cwd: Option<String>) -> Result<Vec<PromptTemplate>, CodexError> {
+    Ok(list_templates(cwd.as_deref().map(Path::new)))
+}
+
+/// Expands `/name arguments` for previews; text that isn't a template is returned as is.
+#[tauri::command]
+pub async fn expand_prompt(
+    input: String,
+    context: Option<PromptContext>,
+) -> Result<ExpandedPrompt, CodexError> {
+    let context = context.unwrap_or_default();
+    let expanded = expand_command(&input, &context).map_err(CodexError::config)?;
+    Ok(expanded.unwrap_or(ExpandedPrompt {
+        name: None,
+        text: input,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(source: PromptSource, body: &str) -> PromptTemplate {
+        PromptTemplate {
+            name: "review".to_string(),
+            description: None,
+            argument_hint: None,
+            source,
+            path: String::new(),
+            variables: placeholders(body),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn front_matter_is_split_from_the_body() {
+        let (fields, body) = parse_front_matter(
+            "---\r\ndescription: \"Review a change\"\r\nargument-hint: [file]\r\n---\r\n\r\nBody",
+        );
+        assert_eq!(fields["description"], "Review a change");
+        assert_eq!(fields["argument-hint"], "[file]");
+        assert_eq!(body, "Body");
+
+        let (fields, body) = parse_front_matter("---\n---\nbody\n");
+        assert!(fields.is_empty());
+        assert_eq!(body, "body\n");
+
+        // Not front matter: no closing line, or no opening one.
+        let unclosed = "---\ndescription: x\nbody";
+        assert_eq!(parse_front_matter(unclosed), (HashMap::new(), unclosed));
+        let (fields, body) = parse_front_matter("text\n---\nmore");
+        assert!(fields.is_empty());
+        assert_eq!(body, "text\n---\nmore");
+
+        // A line that merely starts with `---` doesn't close the block.
+        let (fields, body) = parse_front_matter("---\na: 1\n----- note\nb: 2\n---\nbody");
+        assert_eq!(fields.len(), 2);
+        assert_eq!(body, "body");
+    }
+
+    #[test]
+    fn placeholders_are_listed_once_in_order() {
+        assert_eq!(
+            placeholders("{{ selection }} and {{file:src/a.rs}}, {{selection}} {{}} {{open"),
+            vec!["selection", "file:src/a.rs"]
+        );
+    }
+
+    #[test]
+    fn render_fills_values_and_reports_every_missing_one() {
+        let prompt = template(
+            PromptSource::Global,
+            "Fix {{ticket}} in {{selection}} ({{args}}) {{unknown",
+        );
+        let mut context = PromptContext {
+            selection: Some("fn main()".to_string()),
+            ..Default::default()
+        };
+        context
+            .variables
+            .insert("ticket".to_string(), "ABC-1".to_string());
+        assert_eq!(
+            render(&prompt, "quickly", &context).unwrap(),
+            "Fix ABC-1 in fn main() (quickly) {{unknown"
+        );
+
+        let error = render(&prompt, "", &PromptContext::default()).unwrap_err();
+        assert_eq!(
+            error,
+            "Prompt 'review' is missing values for: ticket, selection, args"
+        );
+
+        let error = render(
+            &template(PromptSource::Global, "{{git_diff}}"),
+            "",
+            &PromptContext::default(),
+        )
+        .unwrap_err();
+        assert!(error.contains("needs a working directory"));
+    }
+
+    #[test]
+    fn codex_prompts_expand_positional_arguments() {
+        assert_eq!(
+            expand_positional("$1 then $2, all: $ARGUMENTS, $9 $0 $x $", "a b"),
+            "a then b, all: a b,  $0 $x $"
+        );
+        assert_eq!(expand_positional("$12", "one two"), "one2");
+
+        let codex = template(PromptSource::Codex, "Explain $1 ($ARGUMENTS)");
+        assert_eq!(
+            render(&codex, "x.rs briefly", &PromptContext::default()).unwrap(),
+            "Explain x.rs (x.rs briefly)"
+        );
+        // Only Codex's own prompts use positional arguments.
+        let global = template(PromptSource::Global, "Explain $1");
+        assert_eq!(
+            render(&global, "x.rs", &PromptContext::default()).unwrap(),
+            "Explain $1"
+        );
+    }
+
+    #[test]
+    fn includes_are_relative_to_the_cwd_and_size_limited() {
+        let dir = std::env::temp_dir().join(format!("codexia-prompts-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("small.txt"), "hello").unwrap();
+        fs::write(dir.join("large.txt"), "x".repeat(MAX_INCLUDE_BYTES + 1)).unwrap();
+
+        let small = read_include(Some(&dir), " small.txt ");
+        let large = read_include(Some(&dir), "large.txt");
+        let absolute = read_include(None, &dir.join("small.txt").to_string_lossy());
+        let missing = read_include(Some(&dir), "missing.txt");
+        let template = template(PromptSource::Project, "{{file:small.txt}}");
+        let context = PromptContext {
+            cwd: Some(dir.to_string_lossy().to_string()),
+            ..Default::default()
+        };
+        let rendered = render(&template, "", &context);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(small.unwrap(), "hello");
+        assert!(large.unwrap_err().contains("larger than 256 KB"));
+        assert_eq!(absolute.unwrap(), "hello");
+        assert!(missing.unwrap_err().starts_with("Cannot include"));
+        assert_eq!(rendered.unwrap(), "hello");
+    }
+}
@@ -0,0 +1,774 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+use tauri::command;
+use walkdir::WalkDir;
+
+use crate::commands::CodexError;
+use crate::config::get_codex_home;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+const MAX_MATCHES_PER_SESSION: usize = 5;
+const SNIPPET_RADIUS: usize = 80;
+/// Summaries kept in `SUMMARY_CACHE`; past this the least recently modified rollouts are
+/// dropped first.
+const MAX_CACHED_SUMMARIES: usize = 2000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionGitInfo {
+    pub commit_hash: Option<String>,
+    pub branch: Option<String>,
+    pub repository_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub id: String,
+    pub path: String,
+    pub cwd: Option<String>,
+    pub model: Option<String>,
+    pub model_provider: Option<String>,
+    pub originator: Option<String>,
+    pub cli_version: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub first_user_message: Option<String>,
+    pub git: Option<SessionGitInfo>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionFilter {
+    /// Only sessions whose cwd is this directory or one of its subdirectories.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionListParams {
+    #[serde(default)]
+    pub offset: Option<usize>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default, flatten)]
+    pub filter: SessionFilter,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionListResponse {
+    pub data: Vec<SessionSummary>,
+    pub total: usize,
+    pub next_offset: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchParams {
+    pub query: String,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default, flatten)]
+    pub filter: SessionFilter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionMatchKind {
+    UserMessage,
+    AgentMessage,
+    Command,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionMatch {
+    pub kind: SessionMatchKind,
+    pub snippet: String,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchHit {
+    pub session: SessionSummary,
+    pub matches: Vec<SessionMatch>,
+}
+
+/// A single meaningful record extracted from a rollout line.
+#[derive(Debug, Clone)]
+pub enum RolloutEntry {
+    Meta {
+        id: String,
+        cwd: Option<String>,
+        model_provider: Option<String>,
+        originator: Option<String>,
+        cli_version: Option<String>,
+        git: Option<SessionGitInfo>,
+    },
+    TurnContext {
+        cwd: Option<String>,
+        model: Option<String>,
+    },
+    UserMessage(String),
+    AgentMessage(String),
+    Reasoning(String),
+    Command {
+        call_id: Option<String>,
+        command: String,
+    },
+    CommandOutput {
+        call_id: Option<String>,
+        output: String,
+    },
+    Patch {
+        patch: String,
+        paths: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct RolloutRecord {
+    pub timestamp: Option<DateTime<Utc>>,
+    pub entry: RolloutEntry,
+}
+
+static SUMMARY_CACHE: Lazy<Mutex<HashMap<PathBuf, (SystemTime, SessionSummary)>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn get_sessions_dir() -> Result<PathBuf, String> {
+    Ok(get_codex_home()?.join("sessions"))
+}
+
+/// Lists every `rollout-*.jsonl` file below the sessions directory.
+pub fn find_rollout_files() -> Result<Vec<PathBuf>, String> {
+    let sessions_dir = get_sessions_dir()?;
+    if !sessions_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let files = WalkDir::new(&sessions_dir)
+        .follow_links(true)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            name.starts_with("rollout-") && name.ends_with(".jsonl")
+        })
+        .map(|entry| entry.into_path())
+        .collect();
+
+    Ok(files)
+}
+
+/// Finds the rollout file for a session (thread) id.
+pub fn find_rollout_file(session_id: &str) -> Result<Option<PathBuf>, String> {
+    let suffix = format!("{}.jsonl", session_id);
+    Ok(find_rollout_files()?.into_iter().find(|path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().ends_with(&suffix))
+            .unwrap_or(false)
+    }))
+}
+
+/// Reads every recognised record of a rollout file, skipping lines that don't parse.
+pub fn read_rollout(path: &Path) -> Result<Vec<RolloutRecord>, String> {
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open rollout file {}: {}", path.display(), e))?;
+
+    let records = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| parse_rollout_line(&line))
+        .collect();
+
+    Ok(records)
+}
+
+pub fn parse_rollout_line(line: &str) -> Option<RolloutRecord> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let value: Value = serde_json::from_str(trimmed).ok()?;
+    let timestamp = value
+        .get("timestamp")
+        .and_then(Value::as_str)
+        .and_then(parse_timestamp);
+
+    let entry = match value.get("type").and_then(Value::as_str) {
+        Some("session_meta") => parse_meta(value.get("payload")?),
+        Some("turn_context") => {
+            let payload = value.get("payload")?;
+            Some(RolloutEntry::TurnContext {
+                cwd: string_field(payload, "cwd"),
+                model: string_field(payload, "model"),
+            })
+        }
+        Some("event_msg") => parse_event_msg(value.get("payload")?),
+        Some("response_item") => parse_response_item(value.get("payload")?),
+        // Early rollouts stored the session metadata as a bare first line.
+        None if value.get("id").is_some() && value.get("timestamp").is_some() => parse_meta(&value),
+        _ => None,
+    }?;
+
+    Some(RolloutRecord { timestamp, entry })
+}
+
+fn parse_meta(payload: &Value) -> Option<RolloutEntry> {
+    let git = payload
+        .get("git")
+        .filter(|git| git.is_object())
+        .map(|git| SessionGitInfo {
+            commit_hash: string_field(git, "commit_hash"),
+            branch: string_field(git, "branch"),
+            repository_url: string_field(git, "repository_url"),
+        });
+
+    Some(RolloutEntry::Meta {
+        id: string_field(payload, "id")?,
+        cwd: string_field(payload, "cwd"),
+        model_provider: string_field(payload, "model_provider"),
+        originator: string_field(payload, "originator"),
+        cli_version: string_field(payload, "cli_version"),
+        git,
+    })
+}
+
+fn parse_event_msg(payload: &Value) -> Option<RolloutEntry> {
+    match payload.get("type").and_then(Value::as_str)? {
+        "user_message" => string_field(payload, "message").map(RolloutEntry::UserMessage),
+        "agent_message" => string_field(payload, "message").map(RolloutEntry::AgentMessage),
+        "agent_reasoning" => string_field(payload, "text").map(RolloutEntry::Reasoning),
+        _ => None,
+    }
+}
+
+fn parse_response_item(payload: &Value) -> Option<RolloutEntry> {
+    let call_id = string_field(payload, "call_id");
+    match payload.get("type").and_then(Value::as_str)? {
+        "function_call" => {
+            let name = string_field(payload, "name")?;
+            let arguments: Value = payload
+                .get("arguments")
+                .and_then(Value::as_str)
+                .and_then(|args| serde_json::from_str(args).ok())?;
+            match name.as_str() {
+                "apply_patch" => {
+                    let patch = string_field(&arguments, "input")?;
+                    Some(RolloutEntry::Patch {
+                        paths: patch_paths(&patch),
+                        patch,
+                    })
+                }
+                _ => command_from_arguments(&arguments)
+                    .map(|command| RolloutEntry::Command { call_id, command }),
+            }
+        }
+        "local_shell_call" => {
+            let action = payload.get("action")?;
+            command_from_arguments(action).map(|command| RolloutEntry::Command { call_id, command })
+        }
+        "custom_tool_call" => {
+            let patch = string_field(payload, "input")?;
+            Some(RolloutEntry::Patch {
+                paths: patch_paths(&patch),
+                patch,
+            })
+        }
+        "function_call_output" | "custom_tool_call_output" => {
+            let output = payload.get("output")?;
+            let output = match output {
+                Value::String(text) => serde_json::from_str::<Value>(text)
+                    .ok()
+                    .and_then(|parsed| string_field(&parsed, "output"))
+                    .unwrap_or_else(|| text.clone()),
+                other => string_field(other, "content").unwrap_or_else(|| other.to_string()),
+            };
+            Some(RolloutEntry::CommandOutput { call_id, output })
+        }
+        _ => None,
+    }
+}
+
+/// Renders the shell command of a tool call, unwrapping `bash -lc <script>`.
+fn command_from_arguments(arguments: &Value) -> Option<String> {
+    if let Some(cmd) = string_field(arguments, "cmd") {
+        return Some(cmd);
+    }
+    let argv: Vec<String> = arguments
+        .get("command")?
+        .as_array()?
+        .iter()
+        .filter_map(|part| part.as_str().map(str::to_string))
+        .collect();
+    Some(display_command(&argv))
+}
+
+pub fn display_command(argv: &[String]) -> String {
+    match argv {
+        [shell, flag, script]
+            if (shell.ends_with("bash") || shell.ends_with("sh") || shell.ends_with("zsh"))
+                && (flag == "-lc" || flag == "-c") =>
+        {
+            script.clone()
+        }
+        _ => argv.join(" "),
+    }
+}
+
+/// Extracts the touched file paths from an `apply_patch` envelope.
+pub fn patch_paths(patch: &str) -> Vec<String> {
+    const MARKERS: [&str; 4] = [
+        "*** Add File: ",
+        "*** Update File: ",
+        "*** Delete File: ",
+        "*** Move to: ",
+    ];
+
+    let mut paths = Vec::new();
+    for line in patch.lines() {
+        for marker in MARKERS {
+            if let Some(path) = line.strip_prefix(marker) {
+                let path = path.trim().to_string();
+                if !paths.contains(&path) {
+                    paths.push(path);
+                }
+            }
+        }
+    }
+    paths
+}
+
+fn string_field(value: &Value, key: &str) -> Option<String> {
+    value.get(key).and_then(Value::as_str).map(str::to_string)
+}
+
+fn parse_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(raw)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+/// Whether a user message is context Codex injects on the user's behalf (environment,
+/// instructions) rather than something the user typed.
+pub fn is_injected_context(text: &str) -> bool {
+    let trimmed = text.trim_start();
+    trimmed.starts_with("<environment_context>") || trimmed.starts_with("<user_instructions>")
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Builds the summary of a rollout file. Reading stops once the metadata, model and first
+/// user message are known, so this stays cheap for long sessions.
+pub fn summarize_rollout(path: &Path) -> Result<SessionSummary, String> {
+    let mtime = modified_time(path);
+    if let Some(mtime) = mtime
+        && let Ok(cache) = SUMMARY_CACHE.lock()
+        && let Some((cached_mtime, summary)) = cache.get(path)
+        && *cached_mtime == mtime
+    {
+        return Ok(summary.clone());
+    }
+
+    let file = File::open(path)
+        .map_err(|e| format!("Failed to open rollout file {}: {}", path.display(), e))?;
+
+    let mut summary = SessionSummary {
+        id: String::new(),
+        path: path.display().to_string(),
+        cwd: None,
+        model: None,
+        model_provider: None,
+        originator: None,
+        cli_version: None,
+        created_at: None,
+        updated_at: mtime.map(DateTime::<Utc>::from),
+        first_user_message: None,
+        git: None,
+    };
+
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let Some(record) = parse_rollout_line(&line) else {
+            continue;
+        };
+        match record.entry {
+            RolloutEntry::Meta {
+                id,
+                cwd,
+                model_provider,
+                originator,
+                cli_version,
+                git,
+            } => {
+                summary.id = id;
+                summary.cwd = cwd;
+                summary.model_provider = model_provider;
+                summary.originator = originator;
+                summary.cli_version = cli_version;
+                summary.git = git;
+                summary.created_at = record.timestamp;
+            }
+            RolloutEntry::TurnContext { cwd, model } => {
+                if summary.model.is_none() {
+                    summary.model = model;
+                }
+                if summary.cwd.is_none() {
+                    summary.cwd = cwd;
+                }
+            }
+            RolloutEntry::UserMessage(text)
+                if !is_injected_context(&text) && summary.first_user_message.is_none() =>
+            {
+                summary.first_user_message = Some(text);
+            }
+            _ => {}
+        }
+
+        if !summary.id.is_empty() && summary.model.is_some() && summary.first_user_message.is_some()
+        {
+            break;
+        }
+    }
+
+    if summary.id.is_empty() {
+        return Err(format!("No session metadata found in {}", path.display()));
+    }
+
+    if let (Some(mtime), Ok(mut cache)) = (mtime, SUMMARY_CACHE.lock()) {
+        if cache.len() >= MAX_CACHED_SUMMARIES
+            && !cache.contains_key(path)
+            && let Some(oldest) = cache
+                .iter()
+                .min_by_key(|(_, (cached_mtime, _))| *cached_mtime)
+                .map(|(cached_path, _)| cached_path.clone())
+        {
+            cache.remove(&oldest);
+        }
+        cache.insert(path.to_path_buf(), (mtime, summary.clone()));
+    }
+
+    Ok(summary)
+}
+
+fn matches_filter(summary: &SessionSummary, filter: &SessionFilter) -> bool {
+    if let Some(cwd) = &filter.cwd {
+        match &summary.cwd {
+            Some(session_cwd) if Path::new(session_cwd).starts_with(cwd) => {}
+            _ => return false,
+        }
+    }
+    if let Some(model) = &filter.model
+        && summary.model.as_deref() != Some(model.as_str())
+    {
+        return false;
+    }
+    let created_at = summary.created_at.or(summary.updated_at);
+    if let Some(since) = filter.since
+        && created_at.is_none_or(|created| created < since)
+    {
+        return false;
+    }
+    if let Some(until) = filter.until
+        && created_at.is_none_or(|created| created > until)
+    {
+        return false;
+    }
+    true
+}
+
+/// Summaries of every readable rollout matching the filter, newest first.
+pub fn collect_sessions(filter: &SessionFilter) -> Result<Vec<SessionSummary>, String> {
+    let mut sessions: Vec<SessionSummary> = find_rollout_files()?
+        .iter()
+        .filter_map(|path| match summarize_rollout(path) {
+            Ok(summary) => Some(summary),
+            Err(e) => {
+                log::debug!("Skipping rollout file: {}", e);
+                None
+            }
+        })
+        .filter(|summary| matches_filter(summary, filter))
+        .collect();
+
+    sessions.sort_by(|a, b| {
+        b.created_at
+            .or(b.updated_at)
+            .cmp(&a.created_at.or(a.updated_at))
+    });
+    Ok(sessions)
+}
+
+/// Finds `needle_lower` in `text` ignoring case, as a range of char indices into `text`.
+/// Chars are compared one by one because lowercasing can add chars ('İ' becomes two), so
+/// positions in a lowercased copy don't line up with the original.
+fn find_ignore_case(text: &str, needle_lower: &str) -> Option<(usize, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    let needle: Vec<char> = needle_lower.chars().collect();
+    'starts: for start in 0..chars.len() {
+        let mut matched = 0;
+        let mut end = start;
+        while matched < needle.len() {
+            // Running out of text here rules out every later start too.
+            for lower in chars.get(end)?.to_lowercase() {
+                if needle.get(matched) != Some(&lower) {
+                    continue 'starts;
+                }
+                matched += 1;
+            }
+            end += 1;
+        }
+        return Some((start, end));
+    }
+    None
+}
+
+pub fn make_snippet(text: &str, needle_lower: &str) -> String {
+    let Some((match_start, match_end)) = find_ignore_case(text, needle_lower) else {
+        return text.chars().take(SNIPPET_RADIUS * 2).collect();
+    };
+
+    let total = text.chars().count();
+    let start = match_start.saturating_sub(SNIPPET_RADIUS);
+    let end = (match_end + SNIPPET_RADIUS).min(total);
+    let snippet: String = text.chars().skip(start).take(end - start).collect();
+    let snippet = snippet.replace('\n', " ");
+
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < total { "…" } else { "" };
+    format!("{}{}{}", prefix, snippet.trim(), suffix)
+}
+
+fn search_rollout(path: &Path, needle_lower: &str) -> Result<Vec<SessionMatch>, String> {
+    let mut matches = Vec::new();
+    for record in read_rollout(path)? {
+        let (kind, text) = match record.entry {
+            RolloutEntry::UserMessage(text) if !is_injected_context(&text) => {
+                (SessionMatchKind::UserMessage, text)
+            }
+            RolloutEntry::AgentMessage(text) => (SessionMatchKind::AgentMessage, text),
+            RolloutEntry::Command { command, .. } => (SessionMatchKind::Command, command),
+            _ => continue,
+        };
+
+        if find_ignore_case(&text, needle_lower).is_some() {
+            matches.push(SessionMatch {
+                kind,
+                snippet: make_snippet(&text, needle_lower),
+                timestamp: record.timestamp,
+            });
+            if matches.len() >= MAX_MATCHES_PER_SESSION {
+                break;
+            }
+        }
+    }
+    Ok(matches)
+}
+
+#[command]
+pub async fn list_sessions(params: SessionListParams) -> Result<SessionListResponse, CodexError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let sessions = collect_sessions(&params.filter).map_err(CodexError::io)?;
+        let total = sessions.len();
+        let offset = params.offset.unwrap_or(0);
+        let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+
+        let data: Vec<SessionSummary> = sessions.into_iter().skip(offset).take(limit).collect();
+        let next_offset = (offset + data.len() < total).then_some(offset + data.len());
+
+        Ok(SessionListResponse {
+            data,
+            total,
+            next_offset,
+        })
+    })
+    .await
+    .map_err(|e| CodexError::internal(format!("Failed to list sessions: {}", e)))?
+}
+
+#[command]
+pub async fn search_sessions(
+    params: SessionSearchParams,
+) -> Result<Vec<SessionSearchHit>, CodexError> {
+    let needle = params.query.trim().to_lowercase();
+    if needle.is_empty() {
+        return Err(CodexError::internal("Search query must not be empty"));
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut hits = Vec::new();
+        for session in collect_sessions(&params.filter).map_err(CodexError::io)? {
+            let matches = match search_rollout(Path::new(&session.path), &needle) {
+                Ok(matches) => matches,
+                Err(e) => {
+                    log::warn!("Skipping rollout file: {}", e);
+                    continue;
+                }
+            };
+            if !matches.is_empty() {
+                hits.push(SessionSearchHit { session, matches });
+                if hits.len() >= limit {
+                    break;
+                }
+            }
+        }
+        Ok(hits)
+    })
+    .await
+    .map_err(|e| CodexError::internal(format!("Failed to search sessions: {}", e)))?
+}
+
+#[command]
+pub async fn read_session(session_id: String) -> Result<SessionSummary, CodexError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let path = find_rollout_file(&session_id)
+            .map_err(CodexError::io)?
+            .ok_or_else(|| {
+                CodexError::io(format!("No rollout file found for session {}", session_id))
+            })?;
+        summarize_rollout(&path).map_err(CodexError::io)
+    })
+    .await
+    .map_err(|e| CodexError::internal(format!("Failed to read session: {}", e)))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn parse(value: Value) -> RolloutEntry {
+        parse_rollout_line(&value.to_string())
+            .expect("line should parse")
+            .entry
+    }
+
+    #[test]
+    fn meta_lines_are_parsed_in_both_formats() {
+        let record = parse_rollout_line(
+            &json!({
+                "timestamp": "2025-09-01T10:00:00Z",
+                "type": "session_meta",
+                "payload": {
+                    "id": "t1",
+                    "cwd": "/work",
+                    "git": { "branch": "main" },
+                },
+            })
+            .to_string(),
+        )
+        .unwrap();
+        assert!(record.timestamp.is_some());
+        let RolloutEntry::Meta { id, cwd, git, .. } = record.entry else {
+            panic!("expected session metadata");
+        };
+        assert_eq!(id, "t1");
+        assert_eq!(cwd.as_deref(), Some("/work"));
+        assert_eq!(git.and_then(|git| git.branch).as_deref(), Some("main"));
+
+        let legacy = parse(json!({ "id": "t2", "timestamp": "2025-01-01T00:00:00Z" }));
+        assert!(matches!(legacy, RolloutEntry::Meta { id, .. } if id == "t2"));
+    }
+
+    #[test]
+    fn messages_and_commands_are_parsed() {
+        let user = parse(json!({
+            "type": "event_msg",
+            "payload": { "type": "user_message", "message": "fix the build" },
+        }));
+        assert!(matches!(user, RolloutEntry::UserMessage(text) if text == "fix the build"));
+
+        let command = parse(json!({
+            "type": "response_item",
+            "payload": {
+                "type": "function_call",
+                "name": "shell",
+                "call_id": "c1",
+                "arguments": json!({ "command": ["bash", "-lc", "cargo test"] }).to_string(),
+            },
+        }));
+        assert!(matches!(
+            command,
+            RolloutEntry::Command { call_id, command }
+                if call_id.as_deref() == Some("c1") && command == "cargo test"
+        ));
+
+        let patch = parse(json!({
+            "type": "response_item",
+            "payload": {
+                "type": "custom_tool_call",
+                "input": "*** Begin Patch\n*** Update File: a.rs\n*** Add File: b.rs\n*** Update File: a.rs\n*** End Patch",
+            },
+        }));
+        assert!(matches!(patch, RolloutEntry::Patch { paths, .. } if paths == ["a.rs", "b.rs"]));
+    }
+
+    #[test]
+    fn unknown_and_malformed_lines_are_skipped() {
+        assert!(parse_rollout_line("").is_none());
+        assert!(parse_rollout_line("{ not json").is_none());
+        assert!(
+            parse_rollout_line(&json!({ "type": "compacted", "payload": {} }).to_string())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn injected_context_is_recognised() {
+        assert!(is_injected_context(
+            "  <environment_context>\n</environment_context>"
+        ));
+        assert!(is_injected_context(
+            "<user_instructions>be brief</user_instructions>"
+        ));
+        assert!(!is_injected_context("what is <environment_context>?"));
+    }
+
+    #[test]
+    fn snippets_are_centred_on_the_match() {
+        let text = format!("{}Needle{}", "a".repeat(200), "b".repeat(200));
+        let snippet = make_snippet(&text, "needle");
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains("Needle"));
+        assert_eq!(
+            snippet.chars().count(),
+            SNIPPET_RADIUS * 2 + "needle".len() + 2
+        );
+
+        assert_eq!(make_snippet("short\ntext", "text"), "short text");
+        assert_eq!(make_snippet("no match here", "zzz"), "no match here");
+    }
+
+    #[test]
+    fn snippets_match_text_that_grows_when_lowercased() {
+        // 'İ' lowercases to two chars, which used to shift the snippet past the match.
+        let text = format!("{}tail", "İ".repeat(100));
+        let snippet = make_snippet(&text, "tail");
+        assert!(snippet.ends_with("tail"), "{}", snippet);
+        assert_eq!(find_ignore_case(&text, "tail"), Some((100, 104)));
+        assert_eq!(find_ignore_case("xİy", "i\u{307}y"), Some((1, 3)));
+        assert_eq!(find_ignore_case("xİy", "iy"), None);
+        assert_eq!(find_ignore_case("abc", "abcd"), None);
+    }
+}
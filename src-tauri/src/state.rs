@@ -1,9 +1,40 @@
 use std::sync::{Mutex, MutexGuard};
+use crate::activity::ActivityTracker;
+use crate::attachments::AttachmentStore;
+use crate::batch::BatchStore;
+use crate::bridge::BridgeState;
+use crate::checkpoints::CheckpointStore;
+use crate::codex::approvals::ApprovalRegistry;
+use crate::codex::error::ClientError;
 use crate::codex::handles::CodexClientHandle;
+use crate::history_index::HistoryIndex;
+use crate::mcp::McpServerState;
+use crate::models::ModelCatalogCache;
+use crate::notifications::NotificationCenter;
+use crate::projects::ProjectRegistry;
+use crate::thread_metadata::ThreadMetadataStore;
+use crate::turn_queue::TurnQueue;
+use crate::usage::UsageLedger;
+use crate::windows::WindowRegistry;
 use anyhow::{Context, Result};
 
 pub struct AppState {
     pub codex_client: Mutex<Option<CodexClientHandle>>,
+    pub history_index: Mutex<HistoryIndex>,
+    pub approvals: Mutex<ApprovalRegistry>,
+    pub thread_metadata: Mutex<ThreadMetadataStore>,
+    pub model_catalog: Mutex<ModelCatalogCache>,
+    pub usage: Mutex<UsageLedger>,
+    pub checkpoints: Mutex<CheckpointStore>,
+    pub projects: Mutex<ProjectRegistry>,
+    pub bridge: Mutex<BridgeState>,
+    pub activity: Mutex<ActivityTracker>,
+    pub mcp: Mutex<McpServerState>,
+    pub attachments: Mutex<AttachmentStore>,
+    pub turn_queue: Mutex<TurnQueue>,
+    pub batches: Mutex<BatchStore>,
+    pub windows: Mutex<WindowRegistry>,
+    pub notifications: Mutex<NotificationCenter>,
 }
 
 pub struct ClientGuard<'a> {
@@ -23,6 +54,21 @@ impl AppState {
     pub fn new() -> Self {
         Self {
             codex_client: Mutex::new(None),
+            history_index: Mutex::new(HistoryIndex::default()),
+            approvals: Mutex::new(ApprovalRegistry::default()),
+            thread_metadata: Mutex::new(ThreadMetadataStore::default()),
+            model_catalog: Mutex::new(ModelCatalogCache::default()),
+            usage: Mutex::new(UsageLedger::default()),
+            checkpoints: Mutex::new(CheckpointStore::default()),
+            projects: Mutex::new(ProjectRegistry::default()),
+            bridge: Mutex::new(BridgeState::default()),
+            activity: Mutex::new(ActivityTracker::default()),
+            mcp: Mutex::new(McpServerState::default()),
+            attachments: Mutex::new(AttachmentStore::default()),
+            turn_queue: Mutex::new(TurnQueue::default()),
+            batches: Mutex::new(BatchStore::default()),
+            windows: Mutex::new(WindowRegistry::default()),
+            notifications: Mutex::new(NotificationCenter::default()),
         }
     }
 
@@ -31,7 +77,7 @@ impl AppState {
             .map_err(|e| anyhow::anyhow!("Failed to acquire lock: {}", e))?;
 
         let handle = guard.as_ref()
-            .context("Codex client not initialized")?;
+            .ok_or(ClientError::NotInitialized)?;
 
         // SAFETY: We're extending the lifetime of the handle reference to match the guard.
         // This is safe because ClientGuard holds the guard, ensuring the handle stays valid.
@@ -45,6 +91,39 @@ impl AppState {
         })
     }
 
+    /// Interrupts the running turns and stops the app-server, if one is running. The next
+    /// command starts a new one, in which threads have to be resumed.
+    pub fn shutdown_client(&self) -> Result<()> {
+        let handle = self.codex_client.lock()
+            .map_err(|e| anyhow::anyhow!("Failed to acquire lock: {}", e))?
+            .take();
+        let Some(handle) = handle else {
+            return Ok(());
+        };
+        let interrupts = self
+            .activity
+            .lock()
+            .map(|activity| activity.running_turns())
+            .unwrap_or_default();
+        let result = handle.shutdown(interrupts);
+        if let Ok(mut activity) = self.activity.lock() {
+            activity.server_stopped();
+        }
+        if let Ok(mut queue) = self.turn_queue.lock() {
+            queue.server_stopped();
+        }
+        if let Ok(mut approvals) = self.approvals.lock() {
+            approvals.clear_pending();
+        }
+        // The threads ended with the app-server, and so did their attachments.
+        if let Ok(mut attachments) = self.attachments.lock()
+            && let Err(e) = attachments.clear_all()
+        {
+            log::warn!("Failed to remove attachments: {}", e);
+        }
+        result
+    }
+
     pub fn get_or_init_client(&self, app: &tauri::AppHandle) -> Result<ClientGuard<'_>> {
         let mut guard = self.codex_client.lock()
             .map_err(|e| anyhow::anyhow!("Failed to acquire lock: {}", e))?;
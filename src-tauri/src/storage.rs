@@ -0,0 +1,79 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory for state owned by Codexia itself (indexes, metadata, usage), kept apart from
+/// `~/.codex` so we never write into files the Codex CLI manages.
+pub fn get_app_data_dir() -> Result<PathBuf, String> {
+    let home_dir = dirs::home_dir().ok_or("Could not find home directory")?;
+    let dir = home_dir.join(".codexia-zen");
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create data directory {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+pub fn get_app_data_path(file_name: &str) -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join(file_name))
+}
+
+/// Loads a JSON document, falling back to the default value when the file does not exist.
+pub fn load_json<T>(path: &Path) -> Result<T, String>
+where
+    T: DeserializeOwned + Default,
+{
+    if !path.exists() {
+        return Ok(T::default());
+    }
+
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse {}: {}", path.display(), e))
+}
+
+/// Loads a JSON document that gets saved back later. A file that can't be read or parsed is
+/// renamed aside (`<name>.corrupt-<timestamp>`) so the next save doesn't replace the user's
+/// data with the default; when it can't even be moved, the error is returned and the caller
+/// must not save over it.
+pub fn load_json_or_set_aside<T>(path: &Path) -> Result<T, String>
+where
+    T: DeserializeOwned + Default,
+{
+    let error = match load_json(path) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+    let mut aside = path.as_os_str().to_owned();
+    aside.push(format!(
+        ".corrupt-{}",
+        chrono::Utc::now().format("%Y%m%d%H%M%S")
+    ));
+    let aside = PathBuf::from(aside);
+    fs::rename(path, &aside)
+        .map_err(|e| format!("{} (could not move it aside: {})", error, e))?;
+    log::warn!("{}; moved it to {}", error, aside.display());
+    Ok(T::default())
+}
+
+/// Writes a JSON document through a temporary file so a crash never leaves it half-written.
+pub fn save_json<T>(path: &Path, value: &T) -> Result<(), String>
+where
+    T: Serialize,
+{
+    let content = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("Failed to serialize {}: {}", path.display(), e))?;
+    write_atomic(path, content.as_bytes())
+}
+
+/// Writes already serialized JSON the same way as `save_json`.
+pub fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, content)
+        .map_err(|e| format!("Failed to write {}: {}", tmp_path.display(), e))?;
+    fs::rename(&tmp_path, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
@@ -0,0 +1,377 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Instant;
+use tauri::{Manager, State};
+
+use crate::commands::CodexError;
+use crate::config::get_codex_home;
+use crate::sessions;
+use crate::state::AppState;
+use crate::storage;
+
+const METADATA_FILE: &str = "thread-metadata.json";
+/// How long metadata for a thread whose rollout vanished is kept before it is dropped, so a
+/// temporarily unmounted or relocated `CODEX_HOME` doesn't wipe the user's titles and notes.
+const MISSING_GRACE_DAYS: i64 = 30;
+/// Minimum time between the reconciliations `thread_list` starts in the background.
+const AUTO_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadMetadata {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub missing_since: Option<DateTime<Utc>>,
+}
+
+impl ThreadMetadata {
+    fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.tags.is_empty()
+            && self.color.is_none()
+            && !self.pinned
+            && !self.archived
+            && self.notes.is_none()
+    }
+}
+
+/// Partial update: only the fields that are present are changed. Empty strings clear
+/// the optional text fields.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadMetadataUpdate {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub pinned: Option<bool>,
+    #[serde(default)]
+    pub archived: Option<bool>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadMetadataFilter {
+    #[serde(default)]
+    pub tags: Option<Vec<String>>,
+    #[serde(default)]
+    pub pinned: Option<bool>,
+    #[serde(default)]
+    pub include_archived: bool,
+    #[serde(default)]
+    pub archived_only: bool,
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Case-insensitive match against the custom title, notes and tags.
+    #[serde(default)]
+    pub query: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThreadMetadataStore {
+    threads: HashMap<String, ThreadMetadata>,
+    #[serde(skip)]
+    loaded: bool,
+    #[serde(skip)]
+    last_pruned: Option<Instant>,
+}
+
+fn normalize_text(value: String) -> Option<String> {
+    let trimmed = value.trim();
+    (!trimmed.is_empty()).then(|| trimmed.to_string())
+}
+
+impl ThreadMetadataStore {
+    fn store_path() -> Result<PathBuf, String> {
+        storage::get_app_data_path(METADATA_FILE)
+    }
+
+    pub fn ensure_loaded(&mut self) {
+        if self.loaded {
+            return;
+        }
+        match Self::store_path().and_then(|path| storage::load_json_or_set_aside(&path)) {
+            Ok(store) => {
+                *self = store;
+                self.loaded = true;
+            }
+            // Stays unloaded, which keeps `save` from replacing the file; loading is retried.
+            Err(e) => log::error!("Failed to load thread metadata: {}", e),
+        }
+    }
+
+    fn save(&self) -> Result<(), String> {
+        if !self.loaded {
+            return Err("Thread metadata could not be loaded, so it is not saved".to_string());
+        }
+        storage::save_json(&Self::store_path()?, self)
+    }
+
+    pub fn get(&mut self, thread_id: &str) -> ThreadMetadata {
+        self.ensure_loaded();
+        self.threads.get(thread_id).cloned().unwrap_or_default()
+    }
+
+    pub fn update(
+        &mut self,
+        thread_id: &str,
+        update: ThreadMetadataUpdate,
+    ) -> Result<ThreadMetadata, String> {
+        self.ensure_loaded();
+        let entry = self.threads.entry(thread_id.to_string()).or_default();
+
+        if let Some(title) = update.title {
+            entry.title = normalize_text(title);
+        }
+        if let Some(tags) = update.tags {
+            let tags: BTreeSet<String> = tags.into_iter().filter_map(normalize_text).collect();
+            entry.tags = tags.into_iter().collect();
+        }
+        if let Some(color) = update.color {
+            entry.color = normalize_text(color);
+        }
+        if let Some(pinned) = update.pinned {
+            entry.pinned = pinned;
+        }
+        if let Some(archived) = update.archived {
+            entry.archived = archived;
+        }
+        if let Some(notes) = update.notes {
+            entry.notes = normalize_text(notes);
+        }
+        entry.updated_at = Some(Utc::now());
+
+        let metadata = entry.clone();
+        if metadata.is_empty() {
+            self.threads.remove(thread_id);
+        }
+        self.save()?;
+        Ok(metadata)
+    }
+
+    pub fn matches(&mut self, thread_id: &str, filter: &ThreadMetadataFilter) -> bool {
+        let metadata = self.get(thread_id);
+
+        if filter.archived_only {
+            if !metadata.archived {
+                return false;
+            }
+        } else if metadata.archived && !filter.include_archived {
+            return false;
+        }
+        if filter.pinned.is_some_and(|pinned| pinned != metadata.pinned) {
+            return false;
+        }
+        if filter
+            .tags
+            .as_ref()
+            .is_some_and(|tags| !tags.iter().all(|tag| metadata.tags.contains(tag)))
+        {
+            return false;
+        }
+        if filter
+            .color
+            .as_ref()
+            .is_some_and(|color| metadata.color.as_ref() != Some(color))
+        {
+            return false;
+        }
+        if let Some(query) = filter.query.as_ref().map(|q| q.trim().to_lowercase())
+            && !query.is_empty()
+        {
+            let haystack = [
+                metadata.title.clone().unwrap_or_default(),
+                metadata.notes.clone().unwrap_or_default(),
+                metadata.tags.join(" "),
+            ]
+            .join("\n")
+            .to_lowercase();
+            if !haystack.contains(&query) {
+                return false;
+            }
+        }
+        true
+    }
+
+    pub fn tags(&mut self) -> Vec<String> {
+        self.ensure_loaded();
+        let tags: BTreeSet<String> = self
+            .threads
+            .values()
+            .flat_map(|metadata| metadata.tags.iter().cloned())
+            .collect();
+        tags.into_iter().collect()
+    }
+
+    /// Reconciles the store with the rollout files on disk: entries whose thread has no rollout
+    /// are flagged as missing and dropped once the grace period has passed. Returns the ids
+    /// that were removed.
+    pub fn prune(&mut self) -> Result<Vec<String>, String> {
+        let stems = rollout_stems()?;
+        self.prune_against(&stems)
+    }
+
+    /// `prune` against the file stems of the rollouts found on disk.
+    fn prune_against(&mut self, stems: &[String]) -> Result<Vec<String>, String> {
+        self.ensure_loaded();
+        self.last_pruned = Some(Instant::now());
+        if self.threads.is_empty() || stems.is_empty() {
+            // Without rollouts there is nothing to reconcile against; most likely CODEX_HOME
+            // is not available right now.
+            return Ok(Vec::new());
+        }
+
+        let mut existing: HashSet<String> = HashSet::new();
+        for name in stems {
+            if let Some(id) = self.threads.keys().find(|id| name.ends_with(id.as_str())) {
+                existing.insert(id.clone());
+            }
+        }
+
+        let now = Utc::now();
+        let mut removed = Vec::new();
+        let mut changed = false;
+        self.threads.retain(|id, metadata| {
+            if existing.contains(id) {
+                if metadata.missing_since.take().is_some() {
+                    changed = true;
+                }
+                return true;
+            }
+            match metadata.missing_since {
+                Some(since) if now - since > Duration::days(MISSING_GRACE_DAYS) => {
+                    removed.push(id.clone());
+                    false
+                }
+                Some(_) => true,
+                None => {
+                    metadata.missing_since = Some(now);
+                    changed = true;
+                    true
+                }
+            }
+        });
+
+        if changed || !removed.is_empty() {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+}
+
+/// File stems of every rollout, live and archived.
+fn rollout_stems() -> Result<Vec<String>, String> {
+    let archived_dir = get_codex_home()?.join("archived_sessions");
+    let mut rollouts = sessions::find_rollout_files()?;
+    if archived_dir.exists() {
+        rollouts.extend(
+            walkdir::WalkDir::new(&archived_dir)
+                .into_iter()
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.into_path()),
+        );
+    }
+    Ok(rollouts
+        .iter()
+        .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .collect())
+}
+
+/// Reconciles the store in the background unless that was done within
+/// `AUTO_PRUNE_INTERVAL`. The rollout walk runs without the store locked.
+pub fn schedule_prune(app: &tauri::AppHandle) {
+    {
+        let state = app.state::<AppState>();
+        let Ok(mut store) = state.thread_metadata.lock() else {
+            return;
+        };
+        if store
+            .last_pruned
+            .is_some_and(|at| at.elapsed() < AUTO_PRUNE_INTERVAL)
+        {
+            return;
+        }
+        store.last_pruned = Some(Instant::now());
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = rollout_stems().and_then(|stems| {
+            app.state::<AppState>()
+                .thread_metadata
+                .lock()
+                .map_err(|e| format!("Failed to acquire lock: {}", e))?
+                .prune_against(&stems)
+        });
+        match result {
+            Ok(removed) if !removed.is_empty() => {
+                log::info!("Removed metadata of {} vanished threads", removed.len())
+            }
+            Ok(_) => {}
+            Err(e) => log::error!("Failed to reconcile thread metadata: {}", e),
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_thread_metadata(
+    thread_id: String,
+    state: State<'_, AppState>,
+) -> Result<ThreadMetadata, CodexError> {
+    let mut store = state
+        .thread_metadata
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(store.get(&thread_id))
+}
+
+#[tauri::command]
+pub async fn update_thread_metadata(
+    thread_id: String,
+    update: ThreadMetadataUpdate,
+    state: State<'_, AppState>,
+) -> Result<ThreadMetadata, CodexError> {
+    let mut store = state
+        .thread_metadata
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    store.update(&thread_id, update).map_err(CodexError::io)
+}
+
+#[tauri::command]
+pub async fn list_thread_tags(state: State<'_, AppState>) -> Result<Vec<String>, CodexError> {
+    let mut store = state
+        .thread_metadata
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(store.tags())
+}
+
+#[tauri::command]
+pub async fn prune_thread_metadata(state: State<'_, AppState>) -> Result<Vec<String>, CodexError> {
+    let mut store = state
+        .thread_metadata
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    store.prune().map_err(CodexError::io)
+}
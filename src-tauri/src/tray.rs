@@ -0,0 +1,361 @@
+//! System tray icon: whether turns are running or waiting on approval, the threads loaded
+//! this session, and quick actions that don't need the window. The menu is rebuilt whenever
+//! a turn starts or ends and whenever an approval request arrives or is answered.
+
+use serde_json::json;
+use tauri::menu::{Menu, MenuBuilder, MenuItemBuilder, SubmenuBuilder};
+use tauri::tray::TrayIconBuilder;
+use tauri::{Manager, Wry};
+
+use crate::activity::ThreadSnapshot;
+use crate::codex::command_analysis::RiskLevel;
+use crate::codex::types::{ApprovalRequest, ApprovalRequestKind};
+use crate::commands::{self, ApprovalDecisionType, ApprovalResponse};
+use crate::projects::ProjectInfo;
+use crate::state::AppState;
+use crate::windows::{self, MAIN_WINDOW};
+
+const TRAY_ID: &str = "main";
+const MAX_THREADS: usize = 10;
+const MAX_PROJECTS: usize = 8;
+const MAX_LABEL_CHARS: usize = 48;
+
+/// What the menu shows, gathered up front so no lock is held while it is built.
+struct TrayStatus {
+    threads: Vec<(ThreadSnapshot, String)>,
+    pending: Vec<ApprovalRequest>,
+    projects: Vec<ProjectInfo>,
+}
+
+impl TrayStatus {
+    fn collect(app: &tauri::AppHandle) -> Self {
+        let state = app.state::<AppState>();
+        let snapshots = state
+            .activity
+            .lock()
+            .map(|activity| activity.snapshots())
+            .unwrap_or_default();
+        let threads = match state.thread_metadata.lock() {
+            Ok(mut metadata) => snapshots
+                .into_iter()
+                .take(MAX_THREADS)
+                .map(|snapshot| {
+                    let title = metadata.get(&snapshot.thread_id).title.unwrap_or_else(|| {
+                        format!(
+                            "Thread {}",
+                            snapshot.thread_id.chars().take(8).collect::<String>()
+                        )
+                    });
+                    (snapshot, title)
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        let pending = state
+            .approvals
+            .lock()
+            .map(|approvals| approvals.pending())
+            .unwrap_or_default();
+        let projects = state
+            .projects
+            .lock()
+            .map(|mut projects| projects.list().into_iter().take(MAX_PROJECTS).collect())
+            .unwrap_or_default();
+        Self {
+            threads,
+            pending,
+            projects,
+        }
+    }
+
+    fn running(&self) -> usize {
+        self.threads
+            .iter()
+            .filter(|(thread, _)| thread.running)
+            .count()
+    }
+
+    fn summary(&self) -> String {
+        let running = self.running();
+        match (running, self.pending.len()) {
+            (0, 0) => "Idle".to_string(),
+            (running, 0) => format!("{} running", running),
+            (0, pending) => format!("{} awaiting approval", pending),
+            (running, pending) => format!("{} running, {} awaiting approval", running, pending),
+        }
+    }
+}
+
+fn truncate(text: &str) -> String {
+    let text = text.lines().next().unwrap_or("").trim();
+    if text.chars().count() <= MAX_LABEL_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(MAX_LABEL_CHARS - 1).collect();
+    format!("{}…", cut.trim_end())
+}
+
+/// Whether `text` shows in a menu label as it is.
+fn fits(text: &str) -> bool {
+    text.lines().count() <= 1 && text.trim().chars().count() <= MAX_LABEL_CHARS
+}
+
+/// Whether the label shows everything there is to approve, so approving from the tray is
+/// safe: a single low-risk command or a single file shown in full. Anything else has to be
+/// looked at in the window.
+fn approvable_from_tray(request: &ApprovalRequest) -> bool {
+    match &request.kind {
+        ApprovalRequestKind::CommandExecution { details, .. } => details
+            .as_ref()
+            .is_some_and(|d| d.risk.level == RiskLevel::Low && fits(&d.command)),
+        ApprovalRequestKind::FileChange { files, .. } => {
+            matches!(files.as_slice(), [file] if fits(&file.path))
+        }
+    }
+}
+
+fn describe_approval(request: &ApprovalRequest) -> String {
+    match &request.kind {
+        ApprovalRequestKind::CommandExecution { details, .. } => details
+            .as_ref()
+            .map(|d| truncate(&d.command))
+            .unwrap_or_else(|| "command".to_string()),
+        ApprovalRequestKind::FileChange { files, .. } => match files.as_slice() {
+            [file] => truncate(&file.path),
+            files => format!("{} file changes", files.len()),
+        },
+    }
+}
+
+fn build_menu(app: &tauri::AppHandle, status: &TrayStatus) -> tauri::Result<Menu<Wry>> {
+    let status_item = MenuItemBuilder::with_id("status", status.summary())
+        .enabled(false)
+        .build(app)?;
+
+    let mut threads = SubmenuBuilder::new(app, "Threads").enabled(!status.threads.is_empty());
+    for (thread, title) in &status.threads {
+        let label = if thread.running {
+            format!("{} (running)", truncate(title))
+        } else {
+            truncate(title)
+        };
+        threads = threads.text(format!("thread:{}", thread.thread_id), label);
+    }
+    let threads = threads.build()?;
+
+    // Item ids name the request shown, so a click answers that one even if it was answered
+    // elsewhere before the menu was rebuilt.
+    let oldest = status.pending.first();
+    let approve = match oldest {
+        Some(request) if approvable_from_tray(request) => MenuItemBuilder::with_id(
+            format!("approve:{}", request.request_id),
+            format!("Approve: {}", describe_approval(request)),
+        ),
+        Some(request) => MenuItemBuilder::with_id(
+            format!("review:{}", request.thread_id),
+            format!("Show: {}", describe_approval(request)),
+        ),
+        None => MenuItemBuilder::with_id("approve", "Approve oldest request").enabled(false),
+    }
+    .build(app)?;
+    let decline = match oldest {
+        Some(request) => MenuItemBuilder::with_id(
+            format!("decline:{}", request.request_id),
+            "Decline oldest request",
+        ),
+        None => MenuItemBuilder::with_id("decline", "Decline oldest request").enabled(false),
+    }
+    .build(app)?;
+
+    let mut interrupt = SubmenuBuilder::new(app, "Interrupt").enabled(status.running() > 0);
+    for (thread, title) in status.threads.iter().filter(|(thread, _)| thread.running) {
+        if let Some(turn) = thread.turns.last() {
+            interrupt = interrupt.text(
+                format!("interrupt:{}:{}", thread.thread_id, turn.turn_id),
+                truncate(title),
+            );
+        }
+    }
+    let interrupt = interrupt.build()?;
+
+    let mut new_thread =
+        SubmenuBuilder::new(app, "New thread in").enabled(!status.projects.is_empty());
+    for project in &status.projects {
+        new_thread = new_thread.text(format!("project:{}", project.path), truncate(&project.name));
+    }
+    let new_thread = new_thread.build()?;
+
+    MenuBuilder::new(app)
+        .item(&status_item)
+        .separator()
+        .item(&threads)
+        .item(&approve)
+        .item(&decline)
+        .item(&interrupt)
+        .item(&new_thread)
+        .separator()
+        .text("show", "Show window")
+        .text("quit", "Quit")
+        .build()
+}
+
+fn update(app: &tauri::AppHandle) -> tauri::Result<()> {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let status = TrayStatus::collect(app);
+    tray.set_menu(Some(build_menu(app, &status)?))?;
+    tray.set_tooltip(Some(format!("codexia-zen: {}", status.summary())))
+}
+
+/// Rebuilds the tray menu. Callable from the client's event loop: the work is done on the
+/// async runtime.
+pub fn refresh(app: &tauri::AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = update(&app) {
+            log::warn!("Failed to update tray menu: {}", e);
+        }
+    });
+}
+
+/// Creates the tray icon; called once at setup.
+pub fn init(app: &tauri::AppHandle) -> tauri::Result<()> {
+    let status = TrayStatus::collect(app);
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip(format!("codexia-zen: {}", status.summary()))
+        .menu(&build_menu(app, &status)?)
+        .on_menu_event(|app, event| on_menu_event(app, event.id().as_ref()));
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+fn on_menu_event(app: &tauri::AppHandle, id: &str) {
+    match id {
+        "show" => {
+            if let Some(window) = app.get_webview_window(MAIN_WINDOW)
+                && let Err(e) = window
+                    .show()
+                    .and_then(|_| window.unminimize())
+                    .and_then(|_| window.set_focus())
+            {
+                log::warn!("Failed to show main window: {}", e);
+            }
+        }
+        "quit" => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = app.state::<AppState>().shutdown_client() {
+                    log::warn!("Failed to stop codex app-server: {}", e);
+                }
+                app.exit(0);
+            });
+        }
+        _ => {
+            if let Some(request_id) = id.strip_prefix("approve:") {
+                answer(app, request_id, true);
+            } else if let Some(request_id) = id.strip_prefix("decline:") {
+                answer(app, request_id, false);
+            } else if let Some(thread_id) = id
+                .strip_prefix("thread:")
+                .or_else(|| id.strip_prefix("review:"))
+            {
+                windows::focus_thread(app, thread_id, None);
+            } else if let Some(ids) = id.strip_prefix("interrupt:")
+                && let Some((thread_id, turn_id)) = ids.split_once(':')
+            {
+                interrupt(app, thread_id.to_string(), turn_id.to_string());
+            } else if let Some(path) = id.strip_prefix("project:") {
+                start_thread(app, path.to_string());
+            }
+        }
+    }
+}
+
+fn answer(app: &tauri::AppHandle, request_id: &str, accept: bool) {
+    let request = app
+        .state::<AppState>()
+        .approvals
+        .lock()
+        .ok()
+        .and_then(|approvals| {
+            approvals
+                .pending()
+                .into_iter()
+                .find(|request| request.request_id == request_id)
+        });
+    let Some(request) = request else {
+        log::info!("Approval {} was already answered", request_id);
+        refresh(app);
+        return;
+    };
+    let response = ApprovalResponse {
+        request_id: request.request_id,
+        decision: if accept {
+            ApprovalDecisionType::Accept
+        } else {
+            ApprovalDecisionType::Decline
+        },
+        is_command_execution: matches!(request.kind, ApprovalRequestKind::CommandExecution { .. }),
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        if let Err(e) = commands::respond_to_approval(response, state, app.clone()).await {
+            log::error!("Failed to answer approval from the tray: {}", e);
+        }
+    });
+}
+
+fn interrupt(app: &tauri::AppHandle, thread_id: String, turn_id: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let params =
+            match serde_json::from_value(json!({ "threadId": thread_id, "turnId": turn_id })) {
+                Ok(params) => params,
+                Err(e) => {
+                    log::error!("Invalid interrupt parameters: {}", e);
+                    return;
+                }
+            };
+        let state = app.state::<AppState>();
+        if let Err(e) = commands::turn_interrupt(params, state).await {
+            log::error!("Failed to interrupt thread {}: {}", thread_id, e);
+        }
+    });
+}
+
+fn start_thread(app: &tauri::AppHandle, cwd: String) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let params = match serde_json::from_value(json!({ "cwd": cwd })) {
+            Ok(params) => params,
+            Err(e) => {
+                log::error!("Invalid thread options: {}", e);
+                return;
+            }
+        };
+        let state = app.state::<AppState>();
+        let thread_id = match commands::thread_start(params, state.clone(), app.clone()).await {
+            Ok(response) => serde_json::to_value(&response.thread)
+                .ok()
+                .and_then(|thread| {
+                    thread
+                        .get("id")
+                        .and_then(|id| id.as_str().map(str::to_string))
+                }),
+            Err(e) => {
+                log::error!("Failed to start thread in {}: {}", cwd, e);
+                // The thread can still be opened if the app-server did start it.
+                e.recovered_id("/thread/id")
+            }
+        };
+        if let Some(thread_id) = thread_id {
+            windows::focus_thread(&app, &thread_id, None);
+            refresh(&app);
+        }
+    });
+}
@@ -0,0 +1,481 @@
+//! Turns submitted while the thread is busy. Each thread has its own queue; when a turn ends
+//! (completed, failed or interrupted) the next queued turn is sent through the same path as
+//! `turn_start`, so prompt templates, attachments and checkpoints apply to it too. A turn that
+//! can't be sent stays at the head of its queue until it is retried, edited or removed.
+
+use chrono::{DateTime, Utc};
+use codex_app_server_protocol::TurnStartParams;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use tauri::{Manager, State};
+
+use crate::commands::{self, CodexError};
+use crate::prompts::PromptContext;
+use crate::state::AppState;
+use crate::windows;
+
+const QUEUE_EVENT: &str = "codex://turn-queue";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedTurn {
+    pub id: String,
+    pub params: TurnStartParams,
+    #[serde(default)]
+    pub prompt_context: Option<PromptContext>,
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    /// First text input, for display.
+    pub preview: String,
+    pub enqueued_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnQueueState {
+    pub thread_id: String,
+    pub items: Vec<QueuedTurn>,
+    /// A queued turn was sent and hasn't finished yet.
+    pub dispatching: bool,
+    /// Why the first queued turn could not be sent; it waits at the head of the queue.
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct ThreadQueue {
+    items: Vec<QueuedTurn>,
+    dispatching: bool,
+    last_error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct TurnQueue {
+    threads: HashMap<String, ThreadQueue>,
+}
+
+fn preview(params: &TurnStartParams) -> String {
+    serde_json::to_value(params)
+        .ok()
+        .and_then(|value| {
+            value
+                .get("input")?
+                .as_array()?
+                .iter()
+                .find_map(|item| item.get("text").and_then(Value::as_str).map(str::to_string))
+        })
+        .unwrap_or_default()
+}
+
+impl TurnQueue {
+    pub fn state(&self, thread_id: &str) -> TurnQueueState {
+        let queue = self.threads.get(thread_id);
+        TurnQueueState {
+            thread_id: thread_id.to_string(),
+            items: queue.map(|q| q.items.clone()).unwrap_or_default(),
+            dispatching: queue.is_some_and(|q| q.dispatching),
+            last_error: queue.and_then(|q| q.last_error.clone()),
+        }
+    }
+
+    fn queue_mut(&mut self, thread_id: &str) -> Result<&mut ThreadQueue, String> {
+        self.threads
+            .get_mut(thread_id)
+            .ok_or_else(|| format!("Thread {} has no queued turns", thread_id))
+    }
+
+    pub fn push(
+        &mut self,
+        params: TurnStartParams,
+        prompt_context: Option<PromptContext>,
+        attachments: Vec<String>,
+    ) -> QueuedTurn {
+        let item = QueuedTurn {
+            id: uuid::Uuid::new_v4().to_string(),
+            preview: preview(&params),
+            params,
+            prompt_context,
+            attachments,
+            enqueued_at: Utc::now(),
+        };
+        self.threads
+            .entry(item.params.thread_id.clone())
+            .or_default()
+            .items
+            .push(item.clone());
+        item
+    }
+
+    /// Puts the queue in the given order; items missing from `ids` keep their relative order
+    /// after the listed ones.
+    pub fn reorder(&mut self, thread_id: &str, ids: &[String]) -> Result<(), String> {
+        let queue = self.queue_mut(thread_id)?;
+        if let Some(unknown) = ids
+            .iter()
+            .find(|id| !queue.items.iter().any(|i| &i.id == *id))
+        {
+            return Err(format!("Queued turn {} not found", unknown));
+        }
+        queue.items.sort_by_key(|item| {
+            ids.iter()
+                .position(|id| id == &item.id)
+                .unwrap_or(usize::MAX)
+        });
+        Ok(())
+    }
+
+    pub fn update(
+        &mut self,
+        thread_id: &str,
+        item_id: &str,
+        params: TurnStartParams,
+        prompt_context: Option<PromptContext>,
+        attachments: Option<Vec<String>>,
+    ) -> Result<QueuedTurn, String> {
+        if params.thread_id != thread_id {
+            return Err("A queued turn cannot be moved to another thread".to_string());
+        }
+        let item = self
+            .queue_mut(thread_id)?
+            .items
+            .iter_mut()
+            .find(|item| item.id == item_id)
+            .ok_or_else(|| format!("Queued turn {} not found", item_id))?;
+        item.preview = preview(&params);
+        item.params = params;
+        item.prompt_context = prompt_context;
+        if let Some(attachments) = attachments {
+            item.attachments = attachments;
+        }
+        Ok(item.clone())
+    }
+
+    pub fn remove(&mut self, thread_id: &str, item_id: &str) -> Result<QueuedTurn, String> {
+        let queue = self.queue_mut(thread_id)?;
+        let position = queue
+            .items
+            .iter()
+            .position(|item| item.id == item_id)
+            .ok_or_else(|| format!("Queued turn {} not found", item_id))?;
+        Ok(queue.items.remove(position))
+    }
+
+    /// Takes the next turn to send, unless one is already on its way.
+    fn take_next(&mut self, thread_id: &str) -> Option<QueuedTurn> {
+        let queue = self.threads.get_mut(thread_id)?;
+        if queue.dispatching || queue.items.is_empty() {
+            return None;
+        }
+        queue.dispatching = true;
+        queue.last_error = None;
+        Some(queue.items.remove(0))
+    }
+
+    /// Puts a turn that could not be sent back at the head of its queue.
+    fn dispatch_failed(&mut self, item: QueuedTurn, error: String) {
+        let queue = self
+            .threads
+            .entry(item.params.thread_id.clone())
+            .or_default();
+        queue.dispatching = false;
+        queue.last_error = Some(error);
+        queue.items.insert(0, item);
+    }
+
+    /// Records the end of a turn; returns whether queued turns are waiting, or `None` when
+    /// the thread has no queue at all.
+    fn turn_finished(&mut self, thread_id: &str) -> Option<bool> {
+        let queue = self.threads.get_mut(thread_id)?;
+        queue.dispatching = false;
+        let pending = !queue.items.is_empty();
+        if !pending && queue.last_error.is_none() {
+            self.threads.remove(thread_id);
+        }
+        Some(pending)
+    }
+
+    /// The app-server stopped, so no queued turn is on its way any more; the queues wait
+    /// for `retry_turn_queue` or the next enqueued turn.
+    pub fn server_stopped(&mut self) {
+        for queue in self.threads.values_mut() {
+            queue.dispatching = false;
+        }
+    }
+}
+
+fn emit_state(app: &tauri::AppHandle, thread_id: &str) {
+    let state = app.state::<AppState>();
+    let Ok(payload) = state.turn_queue.lock().map(|queue| queue.state(thread_id)) else {
+        return;
+    };
+    if let Err(e) = windows::emit_for_thread(app, Some(thread_id), QUEUE_EVENT, payload) {
+        log::warn!("Failed to emit turn queue state: {}", e);
+    }
+}
+
+/// Sends the thread's next queued turn if the thread is idle.
+pub async fn dispatch_next(app: tauri::AppHandle, thread_id: String) {
+    let state = app.state::<AppState>();
+    let running = state
+        .activity
+        .lock()
+        .ok()
+        .and_then(|activity| activity.snapshot(&thread_id))
+        .is_some_and(|snapshot| snapshot.running);
+    if running {
+        return;
+    }
+    let next = match state.turn_queue.lock() {
+        Ok(mut queue) => queue.take_next(&thread_id),
+        Err(e) => {
+            log::error!("Failed to acquire turn queue lock: {}", e);
+            return;
+        }
+    };
+    let Some(item) = next else {
+        return;
+    };
+    emit_state(&app, &thread_id);
+
+    log::info!("Sending queued turn {} of thread {}", item.id, thread_id);
+    let result = commands::turn_start(
+        item.params.clone(),
+        item.prompt_context.clone(),
+        Some(item.attachments.clone()),
+        state.clone(),
+        app.clone(),
+    )
+    .await;
+    if let Err(e) = result {
+        log::error!("Queued turn {} could not be sent: {}", item.id, e);
+        if let Ok(mut queue) = state.turn_queue.lock() {
+            queue.dispatch_failed(item, e.to_string());
+        }
+        emit_state(&app, &thread_id);
+    }
+}
+
+/// Called for every turn that ends; sends the next queued turn of that thread.
+pub fn on_turn_finished(app: &tauri::AppHandle, thread_id: &str) {
+    let state = app.state::<AppState>();
+    let pending = match state.turn_queue.lock() {
+        Ok(mut queue) => queue.turn_finished(thread_id),
+        Err(_) => return,
+    };
+    let Some(pending) = pending else {
+        return;
+    };
+    emit_state(app, thread_id);
+    if pending {
+        // Runs off the client's event loop, which has to be free to process the request.
+        tauri::async_runtime::spawn(dispatch_next(app.clone(), thread_id.to_string()));
+    }
+}
+
+#[tauri::command]
+pub async fn enqueue_turn(
+    params: TurnStartParams,
+    prompt_context: Option<PromptContext>,
+    attachments: Option<Vec<String>>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<QueuedTurn, CodexError> {
+    let thread_id = params.thread_id.clone();
+    let item = state
+        .turn_queue
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?
+        .push(params, prompt_context, attachments.unwrap_or_default());
+    emit_state(&app, &thread_id);
+    dispatch_next(app, thread_id).await;
+    Ok(item)
+}
+
+/// Sends the thread's first queued turn again, e.g. after it failed to send.
+#[tauri::command]
+pub async fn retry_turn_queue(
+    thread_id: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<TurnQueueState, CodexError> {
+    dispatch_next(app, thread_id.clone()).await;
+    let queue = state
+        .turn_queue
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(queue.state(&thread_id))
+}
+
+#[tauri::command]
+pub async fn list_turn_queue(
+    thread_id: String,
+    state: State<'_, AppState>,
+) -> Result<TurnQueueState, CodexError> {
+    let queue = state
+        .turn_queue
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(queue.state(&thread_id))
+}
+
+#[tauri::command]
+pub async fn reorder_turn_queue(
+    thread_id: String,
+    ids: Vec<String>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<TurnQueueState, CodexError> {
+    let result = {
+        let mut queue = state
+            .turn_queue
+            .lock()
+            .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+        queue
+            .reorder(&thread_id, &ids)
+            .map_err(CodexError::internal)?;
+        queue.state(&thread_id)
+    };
+    emit_state(&app, &thread_id);
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn update_queued_turn(
+    thread_id: String,
+    item_id: String,
+    params: TurnStartParams,
+    prompt_context: Option<PromptContext>,
+    attachments: Option<Vec<String>>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<QueuedTurn, CodexError> {
+    let item = state
+        .turn_queue
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?
+        .update(&thread_id, &item_id, params, prompt_context, attachments)
+        .map_err(CodexError::internal)?;
+    emit_state(&app, &thread_id);
+    Ok(item)
+}
+
+#[tauri::command]
+pub async fn remove_queued_turn(
+    thread_id: String,
+    item_id: String,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<QueuedTurn, CodexError> {
+    let item = state
+        .turn_queue
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?
+        .remove(&thread_id, &item_id)
+        .map_err(CodexError::internal)?;
+    emit_state(&app, &thread_id);
+    Ok(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn params(thread_id: &str, text: &str) -> TurnStartParams {
+        serde_json::from_value(json!({
+            "threadId": thread_id,
+            "input": [{ "type": "text", "text": text }],
+        }))
+        .unwrap()
+    }
+
+    fn queue_of(texts: &[&str]) -> (TurnQueue, Vec<String>) {
+        let mut queue = TurnQueue::default();
+        let ids = texts
+            .iter()
+            .map(|text| queue.push(params("t1", text), None, Vec::new()).id)
+            .collect();
+        (queue, ids)
+    }
+
+    fn previews(queue: &TurnQueue) -> Vec<String> {
+        queue
+            .state("t1")
+            .items
+            .into_iter()
+            .map(|item| item.preview)
+            .collect()
+    }
+
+    #[test]
+    fn reorder_puts_listed_items_first() {
+        let (mut queue, ids) = queue_of(&["a", "b", "c"]);
+        queue.reorder("t1", &[ids[2].clone()]).unwrap();
+        assert_eq!(previews(&queue), ["c", "a", "b"]);
+        queue
+            .reorder("t1", &[ids[1].clone(), ids[0].clone(), ids[2].clone()])
+            .unwrap();
+        assert_eq!(previews(&queue), ["b", "a", "c"]);
+
+        assert!(queue.reorder("t1", &["missing".to_string()]).is_err());
+        assert!(queue.reorder("t2", &[]).is_err());
+        assert_eq!(previews(&queue), ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn take_next_sends_one_turn_at_a_time() {
+        let (mut queue, _) = queue_of(&["a", "b"]);
+        assert_eq!(queue.take_next("t1").unwrap().preview, "a");
+        assert!(queue.state("t1").dispatching);
+        assert!(queue.take_next("t1").is_none());
+
+        assert_eq!(queue.turn_finished("t1"), Some(true));
+        assert_eq!(queue.take_next("t1").unwrap().preview, "b");
+        assert!(queue.take_next("t2").is_none());
+    }
+
+    #[test]
+    fn failed_dispatch_waits_at_the_head() {
+        let (mut queue, ids) = queue_of(&["a", "b"]);
+        let item = queue.take_next("t1").unwrap();
+        queue.dispatch_failed(item, "offline".to_string());
+
+        let state = queue.state("t1");
+        assert!(!state.dispatching);
+        assert_eq!(state.last_error.as_deref(), Some("offline"));
+        assert_eq!(state.items[0].id, ids[0]);
+
+        // A retry clears the error.
+        assert_eq!(queue.take_next("t1").unwrap().id, ids[0]);
+        assert_eq!(queue.state("t1").last_error, None);
+    }
+
+    #[test]
+    fn turn_finished_drops_empty_queues() {
+        let (mut queue, _) = queue_of(&["a"]);
+        assert_eq!(queue.turn_finished("t2"), None);
+
+        queue.take_next("t1").unwrap();
+        assert_eq!(queue.turn_finished("t1"), Some(false));
+        assert_eq!(queue.turn_finished("t1"), None);
+
+        // A queue holding an error is kept so the error stays visible.
+        let (mut queue, ids) = queue_of(&["a"]);
+        let item = queue.take_next("t1").unwrap();
+        queue.dispatch_failed(item, "offline".to_string());
+        queue.remove("t1", &ids[0]).unwrap();
+        assert_eq!(queue.turn_finished("t1"), Some(false));
+        assert_eq!(queue.state("t1").last_error.as_deref(), Some("offline"));
+    }
+
+    #[test]
+    fn server_stop_unblocks_the_queue() {
+        let (mut queue, _) = queue_of(&["a", "b"]);
+        queue.take_next("t1").unwrap();
+        assert!(queue.take_next("t1").is_none());
+
+        queue.server_stopped();
+        assert!(!queue.state("t1").dispatching);
+        assert_eq!(queue.take_next("t1").unwrap().preview, "b");
+    }
+}
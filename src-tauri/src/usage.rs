@@ -0,0 +1,704 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::{AddAssign, Sub};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::{Manager, State};
+
+use crate::commands::CodexError;
+use crate::state::AppState;
+use crate::storage;
+
+const LEDGER_FILE: &str = "usage.json";
+const PRICES_FILE: &str = "prices.json";
+const UNKNOWN: &str = "unknown";
+/// How long after a change the ledger is written, so a burst of turns is saved once.
+const SAVE_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub cached_input_tokens: u64,
+    pub output_tokens: u64,
+    pub reasoning_output_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.cached_input_tokens += other.cached_input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.reasoning_output_tokens += other.reasoning_output_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+impl Sub for TokenUsage {
+    type Output = TokenUsage;
+
+    fn sub(self, other: Self) -> Self {
+        TokenUsage {
+            input_tokens: self.input_tokens.saturating_sub(other.input_tokens),
+            cached_input_tokens: self
+                .cached_input_tokens
+                .saturating_sub(other.cached_input_tokens),
+            output_tokens: self.output_tokens.saturating_sub(other.output_tokens),
+            reasoning_output_tokens: self
+                .reasoning_output_tokens
+                .saturating_sub(other.reasoning_output_tokens),
+            total_tokens: self.total_tokens.saturating_sub(other.total_tokens),
+        }
+    }
+}
+
+impl TokenUsage {
+    fn is_zero(&self) -> bool {
+        *self == TokenUsage::default()
+    }
+
+    /// Whether every counter is at least the one in `other`, i.e. `self` can be a later
+    /// cumulative total of the same thread.
+    fn dominates(&self, other: &TokenUsage) -> bool {
+        self.input_tokens >= other.input_tokens
+            && self.cached_input_tokens >= other.cached_input_tokens
+            && self.output_tokens >= other.output_tokens
+            && self.reasoning_output_tokens >= other.reasoning_output_tokens
+    }
+
+    /// Reads a usage object in either the v2 (camelCase) or the legacy event (snake_case) shape.
+    fn from_value(value: &Value) -> Option<TokenUsage> {
+        let field = |camel: &str, snake: &str| {
+            value
+                .get(camel)
+                .or_else(|| value.get(snake))
+                .and_then(Value::as_u64)
+        };
+        let input_tokens = field("inputTokens", "input_tokens")?;
+        let output_tokens = field("outputTokens", "output_tokens")?;
+        Some(TokenUsage {
+            input_tokens,
+            cached_input_tokens: field("cachedInputTokens", "cached_input_tokens").unwrap_or(0),
+            output_tokens,
+            reasoning_output_tokens: field("reasoningOutputTokens", "reasoning_output_tokens")
+                .unwrap_or(0),
+            total_tokens: field("totalTokens", "total_tokens")
+                .unwrap_or(input_tokens + output_tokens),
+        })
+    }
+}
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPrice {
+    pub input: f64,
+    #[serde(default)]
+    pub cached_input: Option<f64>,
+    pub output: f64,
+}
+
+impl ModelPrice {
+    fn cost(&self, usage: &TokenUsage) -> f64 {
+        let cached = usage.cached_input_tokens.min(usage.input_tokens);
+        let uncached = usage.input_tokens - cached;
+        // Reasoning tokens are billed as part of the output tokens.
+        (uncached as f64 * self.input
+            + cached as f64 * self.cached_input.unwrap_or(self.input)
+            + usage.output_tokens as f64 * self.output)
+            / 1_000_000.0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PriceTable {
+    pub currency: String,
+    pub models: BTreeMap<String, ModelPrice>,
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        let mut models = BTreeMap::new();
+        for (model, input, cached_input, output) in [
+            ("gpt-5", 1.25, 0.125, 10.0),
+            ("gpt-5-codex", 1.25, 0.125, 10.0),
+            ("gpt-5.1", 1.25, 0.125, 10.0),
+            ("gpt-5.1-codex", 1.25, 0.125, 10.0),
+            ("gpt-5.1-codex-mini", 0.25, 0.025, 2.0),
+            ("gpt-5-mini", 0.25, 0.025, 2.0),
+        ] {
+            models.insert(
+                model.to_string(),
+                ModelPrice {
+                    input,
+                    cached_input: Some(cached_input),
+                    output,
+                },
+            );
+        }
+        Self {
+            currency: "USD".to_string(),
+            models,
+        }
+    }
+}
+
+impl PriceTable {
+    fn path() -> Result<PathBuf, String> {
+        storage::get_app_data_path(PRICES_FILE)
+    }
+
+    /// Loads the price table, writing the defaults on first use so users have a file to edit.
+    pub fn load() -> Result<PriceTable, String> {
+        let path = Self::path()?;
+        if !path.exists() {
+            let table = PriceTable::default();
+            storage::save_json(&path, &table)?;
+            return Ok(table);
+        }
+        storage::load_json(&path)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        storage::save_json(&Self::path()?, self)
+    }
+
+    /// Exact model match first, then the longest configured prefix (so dated snapshots such
+    /// as `gpt-5-codex-2025-09-15` pick up the base model's price).
+    fn price_for(&self, model: &str) -> Option<&ModelPrice> {
+        self.models.get(model).or_else(|| {
+            self.models
+                .iter()
+                .filter(|(name, _)| model.starts_with(name.as_str()))
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, price)| price)
+        })
+    }
+
+    fn cost(&self, model: &str, usage: &TokenUsage) -> Option<f64> {
+        self.price_for(model).map(|price| price.cost(usage))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitWindow {
+    pub used_percent: f64,
+    pub window_minutes: Option<u64>,
+    pub resets_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitSnapshot {
+    pub primary: Option<RateLimitWindow>,
+    pub secondary: Option<RateLimitWindow>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn rate_limit_window(value: Option<&Value>) -> Option<RateLimitWindow> {
+    let value = value.filter(|v| v.is_object())?;
+    let field = |camel: &str, snake: &str| value.get(camel).or_else(|| value.get(snake));
+    let resets_at = field("resetsAt", "resets_at")
+        .and_then(Value::as_i64)
+        .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+        .or_else(|| {
+            field("resetsInSeconds", "resets_in_seconds")
+                .and_then(Value::as_i64)
+                .map(|secs| Utc::now() + chrono::Duration::seconds(secs))
+        });
+
+    Some(RateLimitWindow {
+        used_percent: field("usedPercent", "used_percent")?.as_f64()?,
+        window_minutes: field("windowDurationMins", "window_minutes").and_then(Value::as_u64),
+        resets_at,
+    })
+}
+
+/// Usage of one turn on one local calendar day (a turn running over midnight gets two records).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnUsageRecord {
+    pub thread_id: String,
+    pub turn_id: Option<String>,
+    pub project: String,
+    pub model: String,
+    pub day: NaiveDate,
+    pub usage: TokenUsage,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ThreadState {
+    project: Option<String>,
+    model: Option<String>,
+    last_total: TokenUsage,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageLedger {
+    threads: HashMap<String, ThreadState>,
+    records: Vec<TurnUsageRecord>,
+    rate_limits: Option<RateLimitSnapshot>,
+    #[serde(skip)]
+    loaded: bool,
+    #[serde(skip)]
+    dirty: bool,
+    #[serde(skip)]
+    save_scheduled: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReportParams {
+    #[serde(default)]
+    pub since: Option<NaiveDate>,
+    #[serde(default)]
+    pub until: Option<NaiveDate>,
+    #[serde(default)]
+    pub thread_id: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBucket {
+    pub usage: TokenUsage,
+    /// `None` when none of the bucket's models has a price in the table.
+    pub cost: Option<f64>,
+    pub turns: usize,
+}
+
+impl UsageBucket {
+    fn add(&mut self, record: &TurnUsageRecord, cost: Option<f64>) {
+        self.usage += record.usage;
+        if let Some(cost) = cost {
+            *self.cost.get_or_insert(0.0) += cost;
+        }
+        self.turns += 1;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnUsageEntry {
+    #[serde(flatten)]
+    pub record: TurnUsageRecord,
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReport {
+    pub currency: String,
+    pub total: UsageBucket,
+    pub by_day: BTreeMap<NaiveDate, UsageBucket>,
+    pub by_project: BTreeMap<String, UsageBucket>,
+    pub by_thread: BTreeMap<String, UsageBucket>,
+    pub by_model: BTreeMap<String, UsageBucket>,
+    /// Per-turn detail, only filled in when the report is scoped to one thread.
+    pub turns: Vec<TurnUsageEntry>,
+    pub unpriced_models: Vec<String>,
+    pub rate_limits: Option<RateLimitSnapshot>,
+}
+
+impl UsageLedger {
+    fn ledger_path() -> Result<PathBuf, String> {
+        storage::get_app_data_path(LEDGER_FILE)
+    }
+
+    pub fn ensure_loaded(&mut self) {
+        if self.loaded {
+            return;
+        }
+        match Self::ledger_path().and_then(|path| storage::load_json_or_set_aside(&path)) {
+            Ok(ledger) => {
+                *self = ledger;
+                self.loaded = true;
+            }
+            // Stays unloaded, which keeps it from being saved over the file; loading is retried.
+            Err(e) => log::error!("Failed to load usage ledger: {}", e),
+        }
+    }
+
+    /// Serializes the ledger if it changed, so it can be written without holding the lock.
+    fn take_snapshot(&mut self) -> Result<Option<(PathBuf, Vec<u8>)>, String> {
+        if !self.dirty {
+            return Ok(None);
+        }
+        if !self.loaded {
+            return Err("Usage ledger could not be loaded, so it is not saved".to_string());
+        }
+        let content = serde_json::to_vec(self)
+            .map_err(|e| format!("Failed to serialize usage ledger: {}", e))?;
+        self.dirty = false;
+        Ok(Some((Self::ledger_path()?, content)))
+    }
+
+    pub fn save_if_dirty(&mut self) -> Result<(), String> {
+        match self.take_snapshot()? {
+            Some((path, content)) => storage::write_atomic(&path, &content),
+            None => Ok(()),
+        }
+    }
+
+    /// Remembers the project and model of a thread, from `thread/started` or the response
+    /// that started or resumed it. Usage of threads never seen that way counts as unknown.
+    pub fn set_thread_context(
+        &mut self,
+        thread_id: &str,
+        project: Option<String>,
+        model: Option<String>,
+    ) {
+        self.ensure_loaded();
+        let thread = self.threads.entry(thread_id.to_string()).or_default();
+        if project.is_some() {
+            thread.project = project;
+        }
+        if model.is_some() {
+            thread.model = model;
+        }
+        self.dirty = true;
+    }
+
+    /// Accounts token-usage and rate-limit notifications and remembers the context of started
+    /// threads; other notifications are ignored.
+    pub fn record_notification(&mut self, notification: &Value) {
+        let method = notification
+            .get("method")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let Some(params) = notification.get("params") else {
+            return;
+        };
+
+        match method {
+            "thread/started" => {
+                let Some(thread) = params.get("thread") else {
+                    return;
+                };
+                if let Some(thread_id) = thread.get("id").and_then(Value::as_str) {
+                    let field = |key| thread.get(key).and_then(Value::as_str).map(str::to_string);
+                    self.set_thread_context(thread_id, field("cwd"), field("model"));
+                }
+            }
+            "thread/tokenUsage/updated" => {
+                let total = params
+                    .get("tokenUsage")
+                    .and_then(|usage| usage.get("total"))
+                    .and_then(TokenUsage::from_value);
+                if let (Some(thread_id), Some(total)) =
+                    (params.get("threadId").and_then(Value::as_str), total)
+                {
+                    let turn_id = params.get("turnId").and_then(Value::as_str);
+                    self.record_total(thread_id, turn_id, total);
+                }
+            }
+            "account/rateLimits/updated" => {
+                if let Some(rate_limits) = params.get("rateLimits") {
+                    self.record_rate_limits(rate_limits);
+                }
+            }
+            // Legacy `codex/event/token_count` events carry both usage and rate limits.
+            "codex/event/token_count" => {
+                let Some(msg) = params.get("msg") else {
+                    return;
+                };
+                let total = msg
+                    .get("info")
+                    .and_then(|info| info.get("total_token_usage"))
+                    .and_then(TokenUsage::from_value);
+                if let (Some(thread_id), Some(total)) =
+                    (params.get("conversationId").and_then(Value::as_str), total)
+                {
+                    let turn_id = params.get("id").and_then(Value::as_str);
+                    self.record_total(thread_id, turn_id, total);
+                }
+                if let Some(rate_limits) = msg.get("rate_limits") {
+                    self.record_rate_limits(rate_limits);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn record_rate_limits(&mut self, rate_limits: &Value) {
+        self.ensure_loaded();
+        let primary = rate_limit_window(rate_limits.get("primary"));
+        let secondary = rate_limit_window(rate_limits.get("secondary"));
+        if primary.is_none() && secondary.is_none() {
+            return;
+        }
+        self.rate_limits = Some(RateLimitSnapshot {
+            primary,
+            secondary,
+            updated_at: Utc::now(),
+        });
+        self.dirty = true;
+    }
+
+    /// Turns a cumulative thread total into a delta against the last total we saw, which makes
+    /// repeated or duplicated notifications harmless.
+    fn record_total(&mut self, thread_id: &str, turn_id: Option<&str>, total: TokenUsage) {
+        self.ensure_loaded();
+        let thread = self.threads.entry(thread_id.to_string()).or_default();
+
+        let delta = if total.dominates(&thread.last_total) {
+            total - thread.last_total
+        } else {
+            // The server restarted its counters (e.g. a new app-server process).
+            total
+        };
+        thread.last_total = total;
+        if delta.is_zero() {
+            return;
+        }
+
+        let project = thread
+            .project
+            .clone()
+            .unwrap_or_else(|| UNKNOWN.to_string());
+        let model = thread.model.clone().unwrap_or_else(|| UNKNOWN.to_string());
+        let day = Local::now().date_naive();
+        let existing = self.records.iter_mut().rev().find(|record| {
+            record.thread_id == thread_id
+                && record.turn_id.as_deref() == turn_id
+                && record.day == day
+        });
+        match existing {
+            Some(record) => record.usage += delta,
+            None => self.records.push(TurnUsageRecord {
+                thread_id: thread_id.to_string(),
+                turn_id: turn_id.map(str::to_string),
+                project,
+                model,
+                day,
+                usage: delta,
+            }),
+        }
+        self.dirty = true;
+    }
+
+    pub fn report(&mut self, params: &UsageReportParams, prices: &PriceTable) -> UsageReport {
+        self.ensure_loaded();
+        let mut report = UsageReport {
+            currency: prices.currency.clone(),
+            total: UsageBucket::default(),
+            by_day: BTreeMap::new(),
+            by_project: BTreeMap::new(),
+            by_thread: BTreeMap::new(),
+            by_model: BTreeMap::new(),
+            turns: Vec::new(),
+            unpriced_models: Vec::new(),
+            rate_limits: self.rate_limits.clone(),
+        };
+
+        let records = self.records.iter().filter(|record| {
+            params.since.is_none_or(|since| record.day >= since)
+                && params.until.is_none_or(|until| record.day <= until)
+                && params
+                    .thread_id
+                    .as_ref()
+                    .is_none_or(|thread_id| &record.thread_id == thread_id)
+                && params.project.as_ref().is_none_or(|project| {
+                    Path::new(&record.project).starts_with(Path::new(project))
+                })
+        });
+
+        for record in records {
+            let cost = prices.cost(&record.model, &record.usage);
+            if cost.is_none() && !report.unpriced_models.contains(&record.model) {
+                report.unpriced_models.push(record.model.clone());
+            }
+
+            report.total.add(record, cost);
+            report
+                .by_day
+                .entry(record.day)
+                .or_default()
+                .add(record, cost);
+            report
+                .by_project
+                .entry(record.project.clone())
+                .or_default()
+                .add(record, cost);
+            report
+                .by_thread
+                .entry(record.thread_id.clone())
+                .or_default()
+                .add(record, cost);
+            report
+                .by_model
+                .entry(record.model.clone())
+                .or_default()
+                .add(record, cost);
+            if params.thread_id.is_some() {
+                report.turns.push(TurnUsageEntry {
+                    record: record.clone(),
+                    cost,
+                });
+            }
+        }
+
+        report
+    }
+}
+
+/// Saves the ledger a little later on the async runtime. Called from the client's event loop,
+/// which must not wait for the write.
+pub fn schedule_save(app: &tauri::AppHandle) {
+    {
+        let state = app.state::<AppState>();
+        let Ok(mut ledger) = state.usage.lock() else {
+            return;
+        };
+        if !ledger.dirty || ledger.save_scheduled {
+            return;
+        }
+        ledger.save_scheduled = true;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(SAVE_DELAY).await;
+        let state = app.state::<AppState>();
+        let snapshot = match state.usage.lock() {
+            Ok(mut ledger) => {
+                ledger.save_scheduled = false;
+                ledger.take_snapshot()
+            }
+            Err(_) => return,
+        };
+        let result = snapshot.and_then(|snapshot| match snapshot {
+            Some((path, content)) => storage::write_atomic(&path, &content),
+            None => Ok(()),
+        });
+        if let Err(e) = result {
+            log::warn!("Failed to save usage ledger: {}", e);
+            if let Ok(mut ledger) = state.usage.lock() {
+                ledger.dirty = true;
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_usage_report(
+    params: UsageReportParams,
+    state: State<'_, AppState>,
+) -> Result<UsageReport, CodexError> {
+    let prices = PriceTable::load().map_err(CodexError::config)?;
+    let mut ledger = state
+        .usage
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(ledger.report(&params, &prices))
+}
+
+#[tauri::command]
+pub async fn get_price_table() -> Result<PriceTable, CodexError> {
+    PriceTable::load().map_err(CodexError::config)
+}
+
+#[tauri::command]
+pub async fn update_price_table(table: PriceTable) -> Result<PriceTable, CodexError> {
+    table.save().map_err(CodexError::config)?;
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(input: u64, cached: u64, output: u64) -> TokenUsage {
+        TokenUsage {
+            input_tokens: input,
+            cached_input_tokens: cached,
+            output_tokens: output,
+            reasoning_output_tokens: 0,
+            total_tokens: input + output,
+        }
+    }
+
+    fn ledger() -> UsageLedger {
+        // Marked as loaded so the tests never read the user's ledger.
+        UsageLedger {
+            loaded: true,
+            ..UsageLedger::default()
+        }
+    }
+
+    #[test]
+    fn dominates_needs_every_counter() {
+        assert!(usage(10, 5, 3).dominates(&usage(10, 5, 3)));
+        assert!(usage(11, 5, 4).dominates(&usage(10, 5, 3)));
+        assert!(!usage(11, 4, 4).dominates(&usage(10, 5, 3)));
+        assert!(!usage(9, 5, 4).dominates(&usage(10, 5, 3)));
+    }
+
+    #[test]
+    fn totals_are_recorded_as_deltas() {
+        let mut ledger = ledger();
+        ledger.set_thread_context("t1", Some("/work".to_string()), Some("gpt-5".to_string()));
+        ledger.record_total("t1", Some("turn-1"), usage(100, 20, 10));
+        ledger.record_total("t1", Some("turn-1"), usage(150, 20, 30));
+
+        assert_eq!(ledger.records.len(), 1);
+        let record = &ledger.records[0];
+        assert_eq!(record.usage, usage(150, 20, 30));
+        assert_eq!(record.project, "/work");
+        assert_eq!(record.model, "gpt-5");
+        assert_eq!(record.day, Local::now().date_naive());
+
+        ledger.record_total("t1", Some("turn-2"), usage(200, 20, 40));
+        assert_eq!(ledger.records.len(), 2);
+        assert_eq!(ledger.records[1].usage, usage(50, 0, 10));
+    }
+
+    #[test]
+    fn repeated_totals_count_once() {
+        let mut ledger = ledger();
+        ledger.record_total("t1", Some("turn-1"), usage(100, 0, 10));
+        ledger.dirty = false;
+        ledger.record_total("t1", Some("turn-1"), usage(100, 0, 10));
+        assert!(!ledger.dirty);
+        assert_eq!(ledger.records.len(), 1);
+        assert_eq!(ledger.records[0].usage, usage(100, 0, 10));
+        assert_eq!(ledger.records[0].project, UNKNOWN);
+    }
+
+    #[test]
+    fn a_restarted_counter_counts_in_full() {
+        let mut ledger = ledger();
+        ledger.record_total("t1", Some("turn-1"), usage(100, 0, 10));
+        // A new app-server process starts the thread's totals from zero again.
+        ledger.record_total("t1", Some("turn-2"), usage(30, 0, 5));
+        ledger.record_total("t1", Some("turn-2"), usage(40, 0, 5));
+        assert_eq!(ledger.records[1].usage, usage(40, 0, 5));
+
+        let total: TokenUsage = ledger
+            .records
+            .iter()
+            .fold(TokenUsage::default(), |mut sum, r| {
+                sum += r.usage;
+                sum
+            });
+        assert_eq!(total, usage(140, 0, 15));
+    }
+
+    #[test]
+    fn threads_are_tracked_separately() {
+        let mut ledger = ledger();
+        ledger.record_total("t1", None, usage(100, 0, 10));
+        ledger.record_total("t2", None, usage(20, 0, 2));
+        ledger.record_total("t1", None, usage(110, 0, 10));
+        assert_eq!(ledger.records.len(), 2);
+        assert_eq!(ledger.records[0].usage, usage(110, 0, 10));
+        assert_eq!(ledger.records[1].usage, usage(20, 0, 2));
+    }
+}
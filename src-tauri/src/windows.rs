@@ -0,0 +1,326 @@
+//! Window registry for per-thread windows. Events that belong to a thread go only to the
+//! windows subscribed to it; threads no window has claimed stay with the main window, so a
+//! second window doesn't receive every thread's stream.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::{BTreeMap, BTreeSet};
+use tauri::{Emitter, Manager, State, WebviewUrl, WebviewWindowBuilder};
+
+use crate::commands::CodexError;
+use crate::state::AppState;
+
+pub const MAIN_WINDOW: &str = "main";
+/// Asks the window that receives it to select the thread in the payload.
+const FOCUS_THREAD_EVENT: &str = "codex://focus-thread";
+const THREAD_WINDOW_PREFIX: &str = "thread-";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowInfo {
+    pub label: String,
+    pub thread_ids: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct WindowRegistry {
+    /// Window label to the threads it shows.
+    windows: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl WindowRegistry {
+    pub fn subscribe(&mut self, label: &str, thread_ids: &[String]) {
+        self.windows
+            .entry(label.to_string())
+            .or_default()
+            .extend(thread_ids.iter().cloned());
+    }
+
+    pub fn unsubscribe(&mut self, label: &str, thread_ids: &[String]) {
+        if let Some(threads) = self.windows.get_mut(label) {
+            for thread_id in thread_ids {
+                threads.remove(thread_id);
+            }
+        }
+    }
+
+    pub fn threads(&self, label: &str) -> Vec<String> {
+        self.windows
+            .get(label)
+            .map(|threads| threads.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn list(&self) -> Vec<WindowInfo> {
+        self.windows
+            .iter()
+            .map(|(label, threads)| WindowInfo {
+                label: label.clone(),
+                thread_ids: threads.iter().cloned().collect(),
+            })
+            .collect()
+    }
+
+    /// Windows subscribed to the thread; empty when the thread belongs to the main window.
+    pub fn owners(&self, thread_id: &str) -> Vec<String> {
+        self.windows
+            .iter()
+            .filter(|(_, threads)| threads.contains(thread_id))
+            .map(|(label, _)| label.clone())
+            .collect()
+    }
+
+    /// Forgets a closed window; returns the threads that no window shows any more.
+    pub fn window_closed(&mut self, label: &str) -> Vec<String> {
+        let Some(threads) = self.windows.remove(label) else {
+            return Vec::new();
+        };
+        threads
+            .into_iter()
+            .filter(|thread_id| self.owners(thread_id).is_empty())
+            .collect()
+    }
+}
+
+/// Thread an app-server notification belongs to, if any.
+pub fn notification_thread_id(notification: &Value) -> Option<&str> {
+    notification
+        .pointer("/params/threadId")
+        .or_else(|| notification.pointer("/params/thread/id"))
+        .and_then(Value::as_str)
+}
+
+/// Windows that show the thread: its own windows, or the main window.
+pub fn thread_windows(app: &tauri::AppHandle, thread_id: &str) -> Vec<String> {
+    let owners = app
+        .state::<AppState>()
+        .windows
+        .lock()
+        .map(|windows| windows.owners(thread_id))
+        .unwrap_or_default();
+    if owners.is_empty() {
+        vec![MAIN_WINDOW.to_string()]
+    } else {
+        owners
+    }
+}
+
+/// Brings the thread to the front: raises a window showing it, unless `focused` (the window
+/// that already has focus) is one, and asks that window to select the thread.
+pub fn focus_thread(app: &tauri::AppHandle, thread_id: &str, focused: Option<&str>) {
+    let targets = thread_windows(app, thread_id);
+    if !targets.iter().any(|target| Some(target.as_str()) == focused)
+        && let Some(window) = targets
+            .first()
+            .and_then(|target| app.get_webview_window(target))
+        && let Err(e) = window
+            .show()
+            .and_then(|_| window.unminimize())
+            .and_then(|_| window.set_focus())
+    {
+        log::warn!("Failed to focus the window of thread {}: {}", thread_id, e);
+    }
+    if let Err(e) = emit_for_thread(
+        app,
+        Some(thread_id),
+        FOCUS_THREAD_EVENT,
+        json!({ "threadId": thread_id }),
+    ) {
+        log::warn!("Failed to emit thread focus: {}", e);
+    }
+}
+
+/// Emits a thread's event to the windows showing the thread, or to the main window when none
+/// does. Events without a thread go to every window.
+pub fn emit_for_thread<S>(
+    app: &tauri::AppHandle,
+    thread_id: Option<&str>,
+    event: &str,
+    payload: S,
+) -> tauri::Result<()>
+where
+    S: Serialize + Clone,
+{
+    let Some(thread_id) = thread_id else {
+        return app.emit(event, payload);
+    };
+    let mut owners = app
+        .state::<AppState>()
+        .windows
+        .lock()
+        .map(|windows| windows.owners(thread_id))
+        .unwrap_or_default();
+    if owners.is_empty() {
+        if app.get_webview_window(MAIN_WINDOW).is_none() {
+            return app.emit(event, payload);
+        }
+        owners.push(MAIN_WINDOW.to_string());
+    }
+    for label in owners {
+        app.emit_to(label.as_str(), event, payload.clone())?;
+    }
+    Ok(())
+}
+
+/// Drops a destroyed window's subscriptions and hands approvals still waiting in its threads
+/// back to the main window, so closing a window can't strand a turn. Closing a thread window
+/// also drops the attachments of its threads, unless a turn still reads them.
+pub fn on_window_destroyed(app: &tauri::AppHandle, label: &str) {
+    let state = app.state::<AppState>();
+    let orphaned = match state.windows.lock() {
+        Ok(mut windows) => windows.window_closed(label),
+        Err(_) => return,
+    };
+    if orphaned.is_empty() {
+        return;
+    }
+    if label.starts_with(THREAD_WINDOW_PREFIX) {
+        let idle: Vec<&String> = match state.activity.lock() {
+            Ok(activity) => orphaned
+                .iter()
+                .filter(|thread_id| {
+                    !activity
+                        .snapshot(thread_id)
+                        .is_some_and(|snapshot| snapshot.running)
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        if let Ok(mut attachments) = state.attachments.lock() {
+            for thread_id in idle {
+                if let Err(e) = attachments.clear(thread_id) {
+                    log::warn!("Failed to remove attachments of thread {}: {}", thread_id, e);
+                }
+            }
+        }
+    }
+    log::info!(
+        "Window {} closed, {} thread(s) return to the main window",
+        label,
+        orphaned.len()
+    );
+    let pending = state
+        .approvals
+        .lock()
+        .map(|approvals| approvals.pending())
+        .unwrap_or_default();
+    for request in pending
+        .iter()
+        .filter(|request| orphaned.contains(&request.thread_id))
+    {
+        if let Err(e) = emit_for_thread(
+            app,
+            Some(&request.thread_id),
+            "codex://approval-request",
+            request,
+        ) {
+            log::warn!("Failed to re-route approval request: {}", e);
+        }
+    }
+}
+
+fn thread_window_label(thread_id: &str) -> String {
+    let sanitized: String = thread_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}{}", THREAD_WINDOW_PREFIX, sanitized)
+}
+
+/// Opens the thread in a window of its own, or focuses the one already showing it. Returns
+/// the window's label.
+#[tauri::command]
+pub async fn open_thread_window(
+    thread_id: String,
+    title: Option<String>,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<String, CodexError> {
+    let label = thread_window_label(&thread_id);
+    if let Some(window) = app.get_webview_window(&label) {
+        window
+            .unminimize()
+            .and_then(|_| window.set_focus())
+            .map_err(|e| CodexError::internal(format!("Failed to focus window: {}", e)))?;
+        return Ok(label);
+    }
+
+    // Subscribed before the window exists so no event of the thread falls in between.
+    state
+        .windows
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?
+        .subscribe(&label, std::slice::from_ref(&thread_id));
+    let result =
+        WebviewWindowBuilder::new(&app, label.as_str(), WebviewUrl::App("index.html".into()))
+            .title(title.unwrap_or_else(|| "codexia-zen".to_string()))
+            .inner_size(800.0, 600.0)
+            .build();
+    if let Err(e) = result {
+        if let Ok(mut windows) = state.windows.lock() {
+            windows.window_closed(&label);
+        }
+        return Err(CodexError::internal(format!(
+            "Failed to open window: {}",
+            e
+        )));
+    }
+    log::info!("Opened window {} for thread {}", label, thread_id);
+    Ok(label)
+}
+
+/// Threads the calling window shows; a thread window asks for this on load.
+#[tauri::command]
+pub async fn get_window_threads(
+    window: tauri::Window,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, CodexError> {
+    let windows = state
+        .windows
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(windows.threads(window.label()))
+}
+
+#[tauri::command]
+pub async fn subscribe_window_threads(
+    thread_ids: Vec<String>,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, CodexError> {
+    let mut windows = state
+        .windows
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    windows.subscribe(window.label(), &thread_ids);
+    Ok(windows.threads(window.label()))
+}
+
+#[tauri::command]
+pub async fn unsubscribe_window_threads(
+    thread_ids: Vec<String>,
+    window: tauri::Window,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, CodexError> {
+    let mut windows = state
+        .windows
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    windows.unsubscribe(window.label(), &thread_ids);
+    Ok(windows.threads(window.label()))
+}
+
+#[tauri::command]
+pub async fn list_windows(state: State<'_, AppState>) -> Result<Vec<WindowInfo>, CodexError> {
+    let windows = state
+        .windows
+        .lock()
+        .map_err(|e| CodexError::internal(format!("Failed to acquire lock: {}", e)))?;
+    Ok(windows.list())
+}
@@ -2,7 +2,7 @@ import { create } from 'zustand';
 import { persist } from 'zustand/middleware';
 import type { SandboxMode } from '@/bindings/v2/SandboxMode';
 import type { AskForApproval } from '@/bindings/v2/AskForApproval';
-import { readTextFile, BaseDirectory } from '@tauri-apps/plugin-fs';
+import { invoke } from '@tauri-apps/api/core';
 
 export type ModelProvider = string;
 export type ReasoningEffort = 'low' | 'medium' | 'high' | 'xhigh';
@@ -49,21 +49,26 @@ export const SANDBOX_APPROVAL_MAP: Record<SandboxMode, AskForApproval> = {
   'danger-full-access': 'never',
 };
 
-// Default models when profile.json doesn't exist
+// Fallback models when the backend model catalog is unavailable
 const DEFAULT_PROVIDER_MODELS: Record<string, string[]> = {
   openai: ["gpt-5.1-codex", "gpt-5.1-codex-max", "gpt-5.1-codex-mini", "gpt-5.1", "gpt-5.2"],
 };
 
-// Load provider models from ~/.codex/profile.json
+interface ModelCatalog {
+  providers: Record<string, string[]>;
+}
+
+// Load provider models from the backend catalog (app-server model/list merged with
+// models configured in ~/.codex/config.toml and the legacy profile.json)
 async function loadProviderModels(): Promise<Record<string, string[]>> {
   try {
-    const profileJson = await readTextFile('.codex/profile.json', {
-      baseDir: BaseDirectory.Home,
-    });
-    const profile = JSON.parse(profileJson) as Record<string, string[]>;
-    return profile;
+    const catalog = await invoke<ModelCatalog>('list_models');
+    const providers = Object.fromEntries(
+      Object.entries(catalog.providers).filter(([, models]) => models.length > 0),
+    );
+    return Object.keys(providers).length > 0 ? providers : DEFAULT_PROVIDER_MODELS;
   } catch (error) {
-    console.error('Failed to load profile.json:', error);
+    console.error('Failed to load model catalog:', error);
     // Fallback to default
     return DEFAULT_PROVIDER_MODELS;
   }